@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Round-trips structurally valid reports through the wire format.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sev::firmware::guest::AttestationReport;
+
+fuzz_target!(|report: AttestationReport| {
+    let bytes = bincode::serialize(&report).unwrap();
+
+    assert_eq!(
+        AttestationReport::from_bytes_strict(&bytes).unwrap(),
+        report
+    );
+});
@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Feeds arbitrary bytes to the attestation report parser, which must
+//! reject malformed input with an error rather than panic.
+
+#![no_main]
+
+use std::convert::TryFrom;
+
+use libfuzzer_sys::fuzz_target;
+use sev::firmware::guest::AttestationReport;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(report) = AttestationReport::try_from(data) {
+        let _ = report.reserved_regions();
+        let _ = report.to_string();
+    }
+});
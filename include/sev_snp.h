@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#ifndef _RUST_SEV_SNP_H
+#define _RUST_SEV_SNP_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// The call succeeded.
+#define SEV_SNP_OK 0
+
+// A required pointer argument was NULL.
+#define SEV_SNP_ERR_NULL_POINTER -1
+
+// The bytes could not be parsed as an attestation report.
+#define SEV_SNP_ERR_INVALID_REPORT -2
+
+// A certificate could not be parsed.
+#define SEV_SNP_ERR_INVALID_CERTIFICATE -3
+
+// The certificate chain or the report signature did not verify.
+#define SEV_SNP_ERR_VERIFICATION -4
+
+// An output buffer is too small for the requested field.
+#define SEV_SNP_ERR_BUFFER_TOO_SMALL -5
+
+// The library was built without a cryptographic backend.
+#define SEV_SNP_ERR_UNSUPPORTED -6
+
+// The length of the MEASUREMENT field.
+#define SEV_SNP_MEASUREMENT_LEN 48
+
+// The length of the REPORT_DATA field.
+#define SEV_SNP_REPORT_DATA_LEN 64
+
+// The length of the CHIP_ID field.
+#define SEV_SNP_CHIP_ID_LEN 64
+
+// An opaque, parsed SEV-SNP attestation report.
+typedef struct sev_snp_report_t sev_snp_report_t;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Describes an error code returned by this API.
+//
+// The returned string is static and must not be freed.
+const char *sev_snp_strerror(int code);
+
+// Parses `len` bytes at `bytes` as an attestation report.
+//
+// On success, `*out` holds a report which must be released with
+// [sev_snp_report_free].
+//
+// # Safety
+//
+// `bytes` must be valid for reads of `len` bytes and `out` must be valid
+// for writes.
+int sev_snp_report_parse(const uint8_t *bytes, size_t len, sev_snp_report_t **out);
+
+// Releases a report returned by [sev_snp_report_parse]. NULL is ignored.
+//
+// # Safety
+//
+// `report` must be NULL or a pointer returned by [sev_snp_report_parse]
+// which has not already been freed.
+void sev_snp_report_free(sev_snp_report_t *report);
+
+// Copies the MEASUREMENT ([SEV_SNP_MEASUREMENT_LEN] bytes) into `out`.
+//
+// # Safety
+//
+// `report` must be a valid report and `out` must be valid for writes of
+// `out_len` bytes.
+int sev_snp_report_measurement(const sev_snp_report_t *report, uint8_t *out, size_t out_len);
+
+// Copies the REPORT_DATA ([SEV_SNP_REPORT_DATA_LEN] bytes) into `out`.
+//
+// # Safety
+//
+// `report` must be a valid report and `out` must be valid for writes of
+// `out_len` bytes.
+int sev_snp_report_report_data(const sev_snp_report_t *report, uint8_t *out, size_t out_len);
+
+// Copies the CHIP_ID ([SEV_SNP_CHIP_ID_LEN] bytes) into `out`.
+//
+// # Safety
+//
+// `report` must be a valid report and `out` must be valid for writes of
+// `out_len` bytes.
+int sev_snp_report_chip_id(const sev_snp_report_t *report, uint8_t *out, size_t out_len);
+
+// Verifies the report against a PEM-encoded ARK and ASK and a DER-encoded
+// VCEK: the chain must verify and the VCEK must sign the report.
+//
+// Returns [SEV_SNP_ERR_UNSUPPORTED] if the library was built without the
+// `openssl` or `crypto_nossl` feature.
+//
+// # Safety
+//
+// `report` must be a valid report and each certificate pointer must be
+// valid for reads of its length.
+int sev_snp_report_verify(const sev_snp_report_t *report,
+                          const uint8_t *ark_pem,
+                          size_t ark_len,
+                          const uint8_t *ask_pem,
+                          size_t ask_len,
+                          const uint8_t *vcek_der,
+                          size_t vcek_len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* _RUST_SEV_SNP_H */
@@ -9,3 +9,10 @@ pub mod genoa;
 pub mod milan;
 
 use super::*;
+
+/// Whether `ark` is the ARK of one of the builtin generations.
+pub(crate) fn is_builtin_ark(ark: &Certificate) -> bool {
+    [milan::ark(), genoa::ark()]
+        .iter()
+        .any(|builtin| matches!(builtin, Ok(builtin) if builtin == ark))
+}
@@ -18,12 +18,28 @@ pub struct Chain {
 impl<'a> Verifiable for &'a Chain {
     type Output = &'a Certificate;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "verify_ca_chain", level = "debug", skip_all)
+    )]
     fn verify(self) -> Result<Self::Output> {
         // Verify that ARK is self-signed.
-        (&self.ark, &self.ark).verify()?;
+        {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("verify_certificate", signer = "ARK", signee = "ARK")
+                .entered();
+
+            (&self.ark, &self.ark).verify()?;
+        }
 
         // Verify that ARK signs ASK.
-        (&self.ark, &self.ask).verify()?;
+        {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("verify_certificate", signer = "ARK", signee = "ASK")
+                .entered();
+
+            (&self.ark, &self.ask).verify()?;
+        }
 
         Ok(&self.ask)
     }
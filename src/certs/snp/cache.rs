@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Caching of VCEK/VLEK certificate chains, so that verifying many reports
+//! from the same platform does not fetch and parse the same chain again.
+
+use super::*;
+
+use crate::firmware::{
+    guest::{AttestationReport, ChipId, SigningKey},
+    host::TcbVersion,
+};
+
+use std::{collections::HashMap, sync::Mutex};
+
+/// Identifies the chain certifying an attestation report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CertCacheKey {
+    /// The CHIP_ID of the report.
+    pub chip_id: ChipId,
+
+    /// The REPORTED_TCB of the report, to which the VCEK is bound.
+    pub tcb: TcbVersion,
+
+    /// The key which signed the report.
+    pub signing_key: SigningKey,
+}
+
+impl From<&AttestationReport> for CertCacheKey {
+    fn from(report: &AttestationReport) -> Self {
+        Self {
+            chip_id: report.chip_id,
+            tcb: report.reported_tcb,
+            signing_key: report.key_info.signing_key_type(),
+        }
+    }
+}
+
+/// A store of certificate chains. Both methods take `&self`, so that a
+/// cache may be shared between threads.
+pub trait CertCache {
+    /// Look up the chain stored for `key`.
+    fn get(&self, key: &CertCacheKey) -> Option<Chain>;
+
+    /// Store the chain for `key`.
+    fn put(&self, key: CertCacheKey, chain: Chain);
+}
+
+/// An in-memory [CertCache] holding up to a fixed number of chains, evicting
+/// the least recently used one when full.
+#[derive(Debug)]
+pub struct MemoryCertCache {
+    max_entries: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    /// Incremented on every access, to timestamp the entries.
+    clock: u64,
+
+    entries: HashMap<CertCacheKey, (Chain, u64)>,
+}
+
+impl MemoryCertCache {
+    /// Creates a cache holding up to `max_entries` chains.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            state: Default::default(),
+        }
+    }
+
+    /// The number of chains currently cached.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether no chain is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CertCache for MemoryCertCache {
+    fn get(&self, key: &CertCacheKey) -> Option<Chain> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+
+        state.entries.get_mut(key).map(|(chain, used)| {
+            *used = clock;
+            chain.clone()
+        })
+    }
+
+    fn put(&self, key: CertCacheKey, chain: Chain) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.max_entries {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| *key);
+
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        state.entries.insert(key, (chain, clock));
+    }
+}
+
+/// Verify an attestation report against the chain certifying it, which is
+/// taken from `cache` or, on a miss, obtained from `fetch` (e.g. from the
+/// KDS) and cached once it verifies and is rooted in one of AMD's builtin
+/// ARKs.
+pub fn verify_report_with_cache<C, F>(report: &AttestationReport, cache: &C, fetch: F) -> Result<()>
+where
+    C: CertCache + ?Sized,
+    F: FnOnce(&CertCacheKey) -> Result<Chain>,
+{
+    let key = CertCacheKey::from(report);
+
+    let chain = match cache.get(&key) {
+        Some(chain) => chain,
+        None => {
+            let chain = fetch(&key)?;
+            if !builtin::is_builtin_ark(&chain.ca.ark) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "the chain's ARK is not one of AMD's builtin ARKs",
+                ));
+            }
+            chain.verify()?;
+            cache.put(key, chain.clone());
+            chain
+        }
+    };
+
+    (&chain, report).verify()
+}
@@ -2,7 +2,6 @@
 
 use super::*;
 
-use crate::error::CertFormatError;
 use openssl::pkey::{PKey, Public};
 use openssl::x509::X509;
 
@@ -11,33 +10,6 @@ use openssl::x509::X509;
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Certificate(X509);
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum CertFormat {
-    Pem,
-    Der,
-}
-
-impl std::fmt::Display for CertFormat {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Pem => write!(f, "pem"),
-            Self::Der => write!(f, "der"),
-        }
-    }
-}
-
-impl std::str::FromStr for CertFormat {
-    type Err = CertFormatError;
-
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        match s {
-            "pem" => Ok(Self::Pem),
-            "der" => Ok(Self::Der),
-            _ => Err(CertFormatError::UnknownFormat),
-        }
-    }
-}
-
 /// Wrap an X509 struct into a Certificate.
 impl From<X509> for Certificate {
     fn from(x509: X509) -> Self {
@@ -106,14 +78,10 @@ impl Certificate {
         Ok(self.0.public_key()?)
     }
 
-    /// Identifies the format of a certificate based upon the first twenty-seven
-    /// bytes of a byte stream. A non-PEM format assumes DER format.
+    /// Identifies the format of a certificate based upon the PEM
+    /// "-----BEGIN" marker. A non-PEM format assumes DER format.
     pub fn identify_format(bytes: &[u8]) -> CertFormat {
-        const PEM_START: &[u8] = b"-----BEGIN CERTIFICATE-----";
-        match &bytes[0..27] {
-            PEM_START => CertFormat::Pem,
-            _ => CertFormat::Der,
-        }
+        CertFormat::identify(bytes)
     }
 
     /// An façade method for constructing a Certificate from raw bytes.
@@ -174,16 +142,14 @@ AFZEAwoKCQ==
     }
 
     #[test]
-    #[should_panic]
-    fn test_identify_format_panic_pem() {
+    fn test_identify_format_short_pem() {
         let dummy_pem: &[u8] = b"-----BEGIN CERTIFICATE---";
 
         assert_eq!(Certificate::identify_format(dummy_pem), CertFormat::Pem)
     }
 
     #[test]
-    #[should_panic]
-    fn test_identify_format_panic_der() {
+    fn test_identify_format_short_der() {
         let dummy_der: &[u8] = &[
             0x30, 0x82, 0x06, 0x63, 0x30, 0x82, 0x04, 0x12, 0xa0, 0x03, 0x02, 0x01, 0x02, 0x02,
         ];
@@ -3,13 +3,15 @@
 use super::*;
 
 use der::{referenced::OwnedToRef, Decode, DecodePem, Encode};
+use rsa::pkcs1::DecodeRsaPublicKey;
 use rsa::signature; // re-export of signature crate
 use signature::Verifier;
-use spki::ObjectIdentifier;
+use spki::{AlgorithmIdentifierOwned, ObjectIdentifier};
 use std::convert::TryFrom;
 use std::io;
 use std::io::ErrorKind;
 use x509_cert::der; // re-export of der crate
+use x509_cert::name::Name;
 use x509_cert::spki; // re-export of spki crate
 
 /// Structures/interfaces for SEV-SNP certificates.
@@ -24,44 +26,75 @@ impl Verifiable for (&Certificate, &Certificate) {
     type Output = ();
 
     fn verify(self) -> Result<Self::Output> {
-        let signer = &self.0 .0;
         let signee = &self.1 .0;
 
-        if signee.signature_algorithm.oid != RSA_SSA_PSS_OID {
+        let message = signee.tbs_certificate.to_der().map_err(|e| {
+            io_error_other(format!("failed to encode tbs_certificate as DER: {e:?}"))
+        })?;
+
+        self.0
+            .verify_signature(
+                &signee.signature_algorithm,
+                &message,
+                signee.signature.raw_bytes(),
+            )
+            .map_err(|e| {
+                io_error_other(format!(
+                    "Signer certificate does not RSA sign signee certificate: {e}"
+                ))
+            })
+    }
+}
+
+impl Certificate {
+    /// Verifies an RSA-PSS signature made by this certificate's key.
+    pub(crate) fn verify_signature(
+        &self,
+        algorithm: &AlgorithmIdentifierOwned,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<()> {
+        if algorithm.oid != RSA_SSA_PSS_OID {
             return Err(io_error_other(format!(
                 "unsupported signature algorithm: {:?}",
-                signee.signature_algorithm
+                algorithm
             )));
         }
 
         let rsa_verifying_key = {
-            let signer_spki_ref = signer
-                .tbs_certificate
-                .subject_public_key_info
-                .owned_to_ref();
-            let signer_pubkey_rsa = rsa::RsaPublicKey::try_from(signer_spki_ref)
-                .map_err(|e| io_error_other(format!("invalid RSA public key: {e:?}")))?;
+            let signer_spki = &self.0.tbs_certificate.subject_public_key_info;
+
+            // The key may be identified as RSASSA-PSS rather than
+            // rsaEncryption; either way it is a PKCS #1 RSAPublicKey.
+            let signer_pubkey_rsa = match signer_spki.algorithm.oid {
+                RSA_SSA_PSS_OID => {
+                    rsa::RsaPublicKey::from_pkcs1_der(signer_spki.subject_public_key.raw_bytes())
+                        .map_err(|e| io_error_other(format!("invalid RSA public key: {e:?}")))?
+                }
+                _ => rsa::RsaPublicKey::try_from(signer_spki.owned_to_ref())
+                    .map_err(|e| io_error_other(format!("invalid RSA public key: {e:?}")))?,
+            };
             rsa::pss::VerifyingKey::<sha2::Sha384>::new(signer_pubkey_rsa)
         };
 
-        let message = signee.tbs_certificate.to_der().map_err(|e| {
-            io_error_other(format!("failed to encode tbs_certificate as DER: {e:?}"))
-        })?;
-
-        let rsa_signature = rsa::pss::Signature::try_from(signee.signature.raw_bytes())
+        let rsa_signature = rsa::pss::Signature::try_from(signature)
             .map_err(|e| io_error_other(format!("invalid RSA signature: {e:?}")))?;
 
         rsa_verifying_key
-            .verify(&message, &rsa_signature)
-            .map_err(|e| {
-                io_error_other(format!(
-                    "Signer certificate does not RSA sign signee certificate: {e}"
-                ))
-            })
+            .verify(message, &rsa_signature)
+            .map_err(|e| io_error_other(e.to_string()))
+    }
+
+    /// The certificate's issuer name.
+    pub(crate) fn issuer(&self) -> &Name {
+        &self.0.tbs_certificate.issuer
+    }
+
+    /// The certificate's DER-encoded serial number.
+    pub(crate) fn serial_number(&self) -> &[u8] {
+        self.0.tbs_certificate.serial_number.as_bytes()
     }
-}
 
-impl Certificate {
     /// Create a Certificate from a PEM-encoded X509 structure.
     pub fn from_pem(pem: &[u8]) -> Result<Self> {
         let cert = x509_cert::Certificate::from_pem(pem)
@@ -93,6 +126,20 @@ impl Certificate {
             .map_err(|e| io_error_other(format!("DER-encoding failed: {e:?}")))
     }
 
+    /// Identifies the format of a certificate based upon the PEM
+    /// "-----BEGIN" marker. A non-PEM format assumes DER format.
+    pub fn identify_format(bytes: &[u8]) -> CertFormat {
+        CertFormat::identify(bytes)
+    }
+
+    /// An façade method for constructing a Certificate from raw bytes.
+    pub fn from_bytes(raw_bytes: &[u8]) -> Result<Self> {
+        match Self::identify_format(raw_bytes) {
+            CertFormat::Pem => Self::from_pem(raw_bytes),
+            CertFormat::Der => Self::from_der(raw_bytes),
+        }
+    }
+
     /// Retrieve the public key in SEC1 encoding.
     pub fn public_key_sec1(&self) -> &[u8] {
         self.0
@@ -2,10 +2,16 @@
 
 use super::*;
 
-use crate::firmware::host::{CertTableEntry, CertType};
+use crate::{
+    error::CrlError,
+    firmware::host::{CertTableEntry, CertType},
+};
+
+use std::path::{Path, PathBuf};
 
 /// Interfaces for a complete SEV-SNP certificate chain.
 
+#[derive(Clone, Debug)]
 pub struct Chain {
     /// The Certificate Authority (CA) chain.
     pub ca: ca::Chain,
@@ -17,12 +23,22 @@ pub struct Chain {
 impl<'a> Verifiable for &'a Chain {
     type Output = &'a Certificate;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "verify_chain", level = "debug", skip_all)
+    )]
     fn verify(self) -> Result<Self::Output> {
         // Verify that ARK is self-signed and ARK signs ASK.
         let ask = self.ca.verify()?;
 
         // Verify that ASK signs VCEK.
-        (ask, &self.vek).verify()?;
+        {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("verify_certificate", signer = "ASK", signee = "VEK")
+                .entered();
+
+            (ask, &self.vek).verify()?;
+        }
 
         Ok(&self.vek)
     }
@@ -125,6 +141,29 @@ impl Chain {
         })
     }
 
+    /// Load the ARK, ASK (or ASVK), and VEK from files, each of which may
+    /// be either PEM or DER encoded. Errors name the file which failed.
+    pub fn from_files(ark: &Path, ask: &Path, vek: &Path) -> Result<Self> {
+        Ok(Self {
+            ca: ca::Chain {
+                ark: load_cert(ark)?,
+                ask: load_cert(ask)?,
+            },
+            vek: load_cert(vek)?,
+        })
+    }
+
+    /// Load a chain from a directory holding conventionally named
+    /// certificates: `ark`, `ask` or `asvk`, and `vcek` or `vlek`, each
+    /// with a `.pem`, `.der` or `.crt` extension.
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let ark = find_cert(dir, &["ark"])?;
+        let ask = find_cert(dir, &["ask", "asvk"])?;
+        let vek = find_cert(dir, &["vcek", "vlek"])?;
+
+        Self::from_files(&ark, &ask, &vek)
+    }
+
     /// Deserialize a PEM-encoded ARK, ASK, and VEK to a SEV-SNP chain.
     pub fn from_pem(ark: &[u8], ask: &[u8], vek: &[u8]) -> Result<Self> {
         Ok(Self {
@@ -140,4 +179,62 @@ impl Chain {
             vek: Certificate::from_der(vek)?,
         })
     }
+
+    /// Verify the chain, then check it against a CRL signed by its ARK.
+    ///
+    /// Every certificate issued by the CRL's issuer is looked up in its
+    /// revocation list. Revocation takes precedence over expiry, so
+    /// [`CrlError::Expired`] is only returned for an otherwise valid chain.
+    pub fn verify_with_crl(&self, crl: &Crl) -> std::result::Result<&Certificate, CrlError> {
+        let vek = self.verify().map_err(CrlError::Verification)?;
+
+        (&self.ca.ark, crl)
+            .verify()
+            .map_err(CrlError::Verification)?;
+
+        for cert in [&self.ca.ark, &self.ca.ask, &self.vek].iter() {
+            if let Some(revoked) = crl.revocation(cert).map_err(CrlError::Verification)? {
+                return Err(CrlError::Revoked(revoked));
+            }
+        }
+
+        match crl.next_update().map_err(CrlError::Verification)? {
+            Some(next_update) if next_update < std::time::SystemTime::now() => {
+                Err(CrlError::Expired { next_update })
+            }
+            _ => Ok(vek),
+        }
+    }
+}
+
+/// Read a PEM or DER encoded certificate, naming the file on failure.
+fn load_cert(path: &Path) -> Result<Certificate> {
+    std::fs::read(path)
+        .and_then(|bytes| Certificate::from_bytes(&bytes))
+        .map_err(|e| Error::new(e.kind(), format!("{}: {e}", path.display())))
+}
+
+/// Find the first certificate in `dir` matching one of `names` with a known
+/// certificate extension.
+fn find_cert(dir: &Path, names: &[&str]) -> Result<PathBuf> {
+    const EXTENSIONS: [&str; 3] = ["pem", "der", "crt"];
+
+    names
+        .iter()
+        .flat_map(|name| {
+            EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("{name}.{ext}")))
+        })
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "no {} certificate found in {}",
+                    names.join("/"),
+                    dir.display()
+                ),
+            )
+        })
 }
@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "openssl")]
+use std::time::Duration;
+
+#[cfg(feature = "openssl")]
+use openssl::{
+    asn1::{Asn1Time, Asn1TimeRef},
+    x509::{ReasonCode, X509Crl, X509},
+};
+
+#[cfg(feature = "crypto_nossl")]
+use x509_cert::{
+    crl::CertificateList,
+    der::{oid::AssociatedOid, pem, Decode, Encode},
+    ext::pkix::CrlReason,
+    time::Time,
+};
+
+/// The reason a certificate was revoked, as defined in RFC 5280 section 5.3.1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RevocationReason {
+    /// No reason given.
+    Unspecified,
+
+    /// The certificate's private key was compromised.
+    KeyCompromise,
+
+    /// The issuing CA's private key was compromised.
+    CaCompromise,
+
+    /// The subject's affiliation changed.
+    AffiliationChanged,
+
+    /// The certificate was replaced.
+    Superseded,
+
+    /// The certificate is no longer needed.
+    CessationOfOperation,
+
+    /// The certificate is temporarily on hold.
+    CertificateHold,
+
+    /// The certificate was removed from a previous delta CRL.
+    RemoveFromCrl,
+
+    /// The privileges granted to the subject were withdrawn.
+    PrivilegeWithdrawn,
+
+    /// The attribute authority's private key was compromised.
+    AaCompromise,
+}
+
+impl TryFrom<i64> for RevocationReason {
+    type Error = i64;
+
+    fn try_from(code: i64) -> std::result::Result<Self, Self::Error> {
+        Ok(match code {
+            0 => Self::Unspecified,
+            1 => Self::KeyCompromise,
+            2 => Self::CaCompromise,
+            3 => Self::AffiliationChanged,
+            4 => Self::Superseded,
+            5 => Self::CessationOfOperation,
+            6 => Self::CertificateHold,
+            8 => Self::RemoveFromCrl,
+            9 => Self::PrivilegeWithdrawn,
+            10 => Self::AaCompromise,
+            _ => return Err(code),
+        })
+    }
+}
+
+impl fmt::Display for RevocationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Unspecified => "unspecified",
+            Self::KeyCompromise => "key compromise",
+            Self::CaCompromise => "CA compromise",
+            Self::AffiliationChanged => "affiliation changed",
+            Self::Superseded => "superseded",
+            Self::CessationOfOperation => "cessation of operation",
+            Self::CertificateHold => "certificate hold",
+            Self::RemoveFromCrl => "remove from CRL",
+            Self::PrivilegeWithdrawn => "privilege withdrawn",
+            Self::AaCompromise => "AA compromise",
+        };
+
+        write!(f, "{reason}")
+    }
+}
+
+/// An entry in the revocation list of a [`Crl`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevokedCert {
+    /// The big-endian serial number of the revoked certificate, without
+    /// leading zero bytes.
+    pub serial: Vec<u8>,
+
+    /// When the certificate was revoked.
+    pub revocation_date: SystemTime,
+
+    /// Why the certificate was revoked, if the entry says.
+    pub reason: Option<RevocationReason>,
+}
+
+impl fmt::Display for RevokedCert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "certificate with serial {} was revoked at {}",
+            hex::encode(&self.serial),
+            UtcTime(self.revocation_date)
+        )?;
+
+        match self.reason {
+            Some(reason) => write!(f, " ({reason})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Formats a [`SystemTime`] as an RFC 3339 UTC timestamp.
+pub(crate) struct UtcTime(pub SystemTime);
+
+impl fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs(),
+            Err(_) => return write!(f, "before 1970-01-01T00:00:00Z"),
+        };
+
+        // Civil date from days since the epoch (H. Hinnant's algorithm).
+        let z = (secs / 86400) as i64 + 719468;
+        let era = z / 146097;
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let time = secs % 86400;
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+}
+
+/// A Certificate Revocation List (CRL), such as the one AMD publishes
+/// for the ARK and ASK of each product.
+pub struct Crl(
+    #[cfg(feature = "openssl")] X509Crl,
+    #[cfg(feature = "crypto_nossl")] CertificateList,
+);
+
+impl Crl {
+    /// Parses a CRL from PEM or DER, identified as for certificates.
+    pub fn from_bytes(raw_bytes: &[u8]) -> Result<Self> {
+        match CertFormat::identify(raw_bytes) {
+            CertFormat::Pem => Self::from_pem(raw_bytes),
+            CertFormat::Der => Self::from_der(raw_bytes),
+        }
+    }
+
+    /// Whether the CRL's nextUpdate time has passed, meaning a newer
+    /// CRL should have been published.
+    pub fn is_expired(&self) -> Result<bool> {
+        Ok(matches!(self.next_update()?, Some(next) if next < SystemTime::now()))
+    }
+
+    /// Looks up a certificate in the revocation list.
+    ///
+    /// Serial numbers are only unique per issuer, so certificates not
+    /// issued by the CRL's issuer are never reported as revoked.
+    pub fn revocation(&self, cert: &Certificate) -> Result<Option<RevokedCert>> {
+        if !self.issued(cert)? {
+            return Ok(None);
+        }
+
+        let serial = serial_number(cert)?;
+
+        Ok(self
+            .revoked_certs()?
+            .into_iter()
+            .find(|entry| entry.serial == serial))
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl Crl {
+    /// Create a Crl from a PEM-encoded X509 CRL.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        Ok(Self(X509Crl::from_pem(pem)?))
+    }
+
+    /// Create a Crl from a DER-encoded X509 CRL.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        Ok(Self(X509Crl::from_der(der)?))
+    }
+
+    /// Serialize a Crl to DER.
+    pub fn to_der(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_der()?)
+    }
+
+    /// When the CRL was issued.
+    pub fn this_update(&self) -> Result<SystemTime> {
+        system_time(self.0.last_update())
+    }
+
+    /// When the next CRL is expected to be issued, if stated.
+    pub fn next_update(&self) -> Result<Option<SystemTime>> {
+        self.0.next_update().map(system_time).transpose()
+    }
+
+    /// All entries of the revocation list.
+    pub fn revoked_certs(&self) -> Result<Vec<RevokedCert>> {
+        let entries = match self.0.get_revoked() {
+            Some(entries) => entries,
+            None => return Ok(vec![]),
+        };
+
+        entries
+            .iter()
+            .map(|entry| {
+                let reason = match entry.extension::<ReasonCode>()? {
+                    Some((_, code)) => RevocationReason::try_from(code.get_i64()?).ok(),
+                    None => None,
+                };
+
+                Ok(RevokedCert {
+                    serial: entry.serial_number().to_bn()?.to_vec(),
+                    revocation_date: system_time(entry.revocation_date())?,
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    fn issued(&self, cert: &Certificate) -> Result<bool> {
+        let cert = X509::from(cert);
+
+        Ok(cert.issuer_name().to_der()? == self.0.issuer_name().to_der()?)
+    }
+}
+
+#[cfg(feature = "openssl")]
+fn serial_number(cert: &Certificate) -> Result<Vec<u8>> {
+    Ok(X509::from(cert).serial_number().to_bn()?.to_vec())
+}
+
+#[cfg(feature = "openssl")]
+fn system_time(time: &Asn1TimeRef) -> Result<SystemTime> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    let secs = i64::from(diff.days) * 86400 + i64::from(diff.secs);
+
+    Ok(match u64::try_from(secs) {
+        Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+        Err(_) => UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()),
+    })
+}
+
+/// Verify if the public key of a Certificate signs a Crl.
+#[cfg(feature = "openssl")]
+impl Verifiable for (&Certificate, &Crl) {
+    type Output = ();
+
+    fn verify(self) -> Result<Self::Output> {
+        let key = X509::from(self.0).public_key()?;
+
+        match self.1 .0.verify(&key)? {
+            true => Ok(()),
+            false => Err(Error::new(
+                ErrorKind::Other,
+                "Signer certificate does not sign CRL",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "crypto_nossl")]
+impl Crl {
+    /// Create a Crl from a PEM-encoded X509 CRL.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        let (_, der) = pem::decode_vec(pem)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid PEM: {e}")))?;
+
+        Self::from_der(&der)
+    }
+
+    /// Create a Crl from a DER-encoded X509 CRL.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let crl = CertificateList::from_der(der)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid DER: {e}")))?;
+        Ok(Self(crl))
+    }
+
+    /// Serialize a Crl to DER.
+    pub fn to_der(&self) -> Result<Vec<u8>> {
+        self.0
+            .to_der()
+            .map_err(|e| Error::new(ErrorKind::Other, format!("DER-encoding failed: {e}")))
+    }
+
+    /// When the CRL was issued.
+    pub fn this_update(&self) -> Result<SystemTime> {
+        Ok(system_time(&self.0.tbs_cert_list.this_update))
+    }
+
+    /// When the next CRL is expected to be issued, if stated.
+    pub fn next_update(&self) -> Result<Option<SystemTime>> {
+        Ok(self.0.tbs_cert_list.next_update.as_ref().map(system_time))
+    }
+
+    /// All entries of the revocation list.
+    pub fn revoked_certs(&self) -> Result<Vec<RevokedCert>> {
+        let entries = match &self.0.tbs_cert_list.revoked_certificates {
+            Some(entries) => entries,
+            None => return Ok(vec![]),
+        };
+
+        entries
+            .iter()
+            .map(|entry| {
+                let reason = match entry
+                    .crl_entry_extensions
+                    .iter()
+                    .flatten()
+                    .find(|ext| ext.extn_id == CrlReason::OID)
+                {
+                    Some(ext) => {
+                        let code = CrlReason::from_der(ext.extn_value.as_bytes()).map_err(|e| {
+                            Error::new(ErrorKind::InvalidData, format!("invalid CRL reason: {e}"))
+                        })?;
+                        RevocationReason::try_from(code as i64).ok()
+                    }
+                    None => None,
+                };
+
+                Ok(RevokedCert {
+                    serial: strip_leading_zeros(entry.serial_number.as_bytes()),
+                    revocation_date: system_time(&entry.revocation_date),
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    fn issued(&self, cert: &Certificate) -> Result<bool> {
+        Ok(cert.issuer() == &self.0.tbs_cert_list.issuer)
+    }
+}
+
+#[cfg(feature = "crypto_nossl")]
+fn serial_number(cert: &Certificate) -> Result<Vec<u8>> {
+    Ok(strip_leading_zeros(cert.serial_number()))
+}
+
+#[cfg(feature = "crypto_nossl")]
+fn strip_leading_zeros(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+#[cfg(feature = "crypto_nossl")]
+fn system_time(time: &Time) -> SystemTime {
+    UNIX_EPOCH + time.to_unix_duration()
+}
+
+/// Verify if the public key of a Certificate signs a Crl.
+#[cfg(feature = "crypto_nossl")]
+impl Verifiable for (&Certificate, &Crl) {
+    type Output = ();
+
+    fn verify(self) -> Result<Self::Output> {
+        let crl = &self.1 .0;
+
+        let message = crl.tbs_cert_list.to_der().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to encode tbs_cert_list as DER: {e:?}"),
+            )
+        })?;
+
+        self.0
+            .verify_signature(
+                &crl.signature_algorithm,
+                &message,
+                crl.signature.raw_bytes(),
+            )
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("Signer certificate does not sign CRL: {e}"),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn utc_time_display() {
+        let time = |secs| UtcTime(UNIX_EPOCH + Duration::from_secs(secs)).to_string();
+
+        assert_eq!(time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(time(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(time(1_792_174_414), "2026-10-16T18:13:34Z");
+    }
+
+    #[test]
+    fn revocation_reason_codes() {
+        assert_eq!(
+            RevocationReason::try_from(1),
+            Ok(RevocationReason::KeyCompromise)
+        );
+        assert_eq!(
+            RevocationReason::try_from(10),
+            Ok(RevocationReason::AaCompromise)
+        );
+        assert_eq!(RevocationReason::try_from(7), Err(7));
+    }
+}
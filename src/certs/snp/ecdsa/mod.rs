@@ -11,6 +11,8 @@ use crate::certs::snp::{AsLeBytes, FromLe};
 #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 use std::convert::TryFrom;
 
+use crate::error::SignatureError;
+
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
 
@@ -18,6 +20,19 @@ use serde_big_array::BigArray;
 use openssl::{bn, ecdsa};
 
 const SIG_PIECE_SIZE: usize = std::mem::size_of::<[u8; 72]>();
+
+/// Size of a P-384 scalar; the rest of each 72-byte component is zero.
+const P384_SCALAR_SIZE: usize = 48;
+
+/// Order of the P-384 group, big-endian. Valid r and s lie in [1, order).
+const P384_ORDER: [u8; P384_SCALAR_SIZE] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc7, 0x63, 0x4d, 0x81, 0xf4, 0x37, 0x2d, 0xdf,
+    0x58, 0x1a, 0x0d, 0xb2, 0x48, 0xb0, 0xa7, 0x7a, 0xec, 0xec, 0x19, 0x6a, 0xcc, 0xc5, 0x29, 0x73,
+];
+
+/// Size of the raw big-endian r||s encoding used by JOSE and COSE.
+pub const RAW_RS_SIZE: usize = P384_SCALAR_SIZE * 2;
 const R_S_SIZE: usize = SIG_PIECE_SIZE * 2usize;
 
 #[repr(C)]
@@ -33,6 +48,17 @@ pub struct Signature {
 }
 
 impl Signature {
+    /// Builds a signature from `r` and `s` components in the SNP wire
+    /// format: little-endian, zero-padded to 72 bytes, as returned by
+    /// [r](Self::r) and [s](Self::s).
+    pub fn from_components(r: [u8; 72], s: [u8; 72]) -> Self {
+        Self {
+            r,
+            s,
+            ..Default::default()
+        }
+    }
+
     /// Returns the signatures `r` component
     pub fn r(&self) -> &[u8; 72] {
         &self.r
@@ -42,6 +68,11 @@ impl Signature {
     pub fn s(&self) -> &[u8; 72] {
         &self.s
     }
+
+    /// Returns the reserved bytes following `r` and `s`.
+    pub(crate) fn reserved(&self) -> &[u8] {
+        &self._reserved
+    }
 }
 
 impl std::fmt::Debug for Signature {
@@ -62,6 +93,15 @@ impl PartialEq for Signature {
     }
 }
 
+impl std::hash::Hash for Signature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // Only r and s take part in equality, so the reserved area is
+        // left out of the hash as well.
+        self.r.hash(state);
+        self.s.hash(state);
+    }
+}
+
 impl Default for Signature {
     fn default() -> Self {
         Signature {
@@ -153,3 +193,318 @@ impl TryFrom<&Signature> for Vec<u8> {
         Ok(ecdsa::EcdsaSig::try_from(value)?.to_der()?)
     }
 }
+
+/// Arbitrary 48-byte r and s, with the bytes above them and the reserved
+/// area left zero.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Signature {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut signature = Self::default();
+        u.fill_buffer(&mut signature.r[..P384_SCALAR_SIZE])?;
+        u.fill_buffer(&mut signature.s[..P384_SCALAR_SIZE])?;
+
+        Ok(signature)
+    }
+}
+
+/// Conversions to and from interchange encodings, shared by both crypto
+/// backends. The firmware stores r and s as 72-byte little-endian values;
+/// the encodings below use big-endian P-384 scalars.
+impl Signature {
+    /// A component as a big-endian scalar, rejecting values out of the
+    /// P-384 range.
+    fn scalar(
+        component: &[u8; 72],
+        name: &'static str,
+    ) -> std::result::Result<[u8; P384_SCALAR_SIZE], SignatureError> {
+        let len = component
+            .iter()
+            .rposition(|b| *b != 0)
+            .map_or(0, |last| last + 1);
+
+        if len > P384_SCALAR_SIZE {
+            return Err(SignatureError::ComponentTooLong {
+                component: name,
+                len,
+            });
+        }
+
+        let mut scalar = [0u8; P384_SCALAR_SIZE];
+        for (dst, src) in scalar.iter_mut().rev().zip(component.iter()) {
+            *dst = *src;
+        }
+
+        check_scalar(&scalar)?;
+
+        Ok(scalar)
+    }
+
+    fn from_scalars(
+        r: &[u8; P384_SCALAR_SIZE],
+        s: &[u8; P384_SCALAR_SIZE],
+    ) -> std::result::Result<Self, SignatureError> {
+        check_scalar(r)?;
+        check_scalar(s)?;
+
+        let mut signature = Self::default();
+        for (dst, src) in signature.r.iter_mut().zip(r.iter().rev()) {
+            *dst = *src;
+        }
+        for (dst, src) in signature.s.iter_mut().zip(s.iter().rev()) {
+            *dst = *src;
+        }
+
+        Ok(signature)
+    }
+
+    /// Encode the signature as the fixed-size big-endian `r || s` used by
+    /// JOSE (ES384) and COSE.
+    pub fn to_raw_rs(&self) -> std::result::Result<[u8; RAW_RS_SIZE], SignatureError> {
+        let mut raw = [0u8; RAW_RS_SIZE];
+        raw[..P384_SCALAR_SIZE].copy_from_slice(&Self::scalar(&self.r, "r")?);
+        raw[P384_SCALAR_SIZE..].copy_from_slice(&Self::scalar(&self.s, "s")?);
+
+        Ok(raw)
+    }
+
+    /// Decode a big-endian `r || s` signature.
+    pub fn from_raw_rs(raw: &[u8; RAW_RS_SIZE]) -> std::result::Result<Self, SignatureError> {
+        let mut r = [0u8; P384_SCALAR_SIZE];
+        let mut s = [0u8; P384_SCALAR_SIZE];
+        r.copy_from_slice(&raw[..P384_SCALAR_SIZE]);
+        s.copy_from_slice(&raw[P384_SCALAR_SIZE..]);
+
+        Self::from_scalars(&r, &s)
+    }
+
+    /// Encode the signature as a DER `SEQUENCE { INTEGER r, INTEGER s }`.
+    pub fn to_der(&self) -> std::result::Result<Vec<u8>, SignatureError> {
+        let mut integers = vec![];
+        der_integer(&mut integers, &Self::scalar(&self.r, "r")?);
+        der_integer(&mut integers, &Self::scalar(&self.s, "s")?);
+
+        // Two integers of at most 2 + 49 bytes always fit a short-form length.
+        let mut der = vec![0x30, integers.len() as u8];
+        der.extend_from_slice(&integers);
+
+        Ok(der)
+    }
+
+    /// Decode a DER-encoded ECDSA P-384 signature.
+    pub fn from_der(der: &[u8]) -> std::result::Result<Self, SignatureError> {
+        let integers = match der {
+            [0x30, len, integers @ ..] if *len as usize == integers.len() => integers,
+            _ => return Err(SignatureError::InvalidDer),
+        };
+
+        let (r, rest) = parse_der_integer(integers, "r")?;
+        let (s, rest) = parse_der_integer(rest, "s")?;
+
+        if !rest.is_empty() {
+            return Err(SignatureError::InvalidDer);
+        }
+
+        Self::from_scalars(&r, &s)
+    }
+}
+
+/// Checks that a big-endian scalar lies in [1, order).
+fn check_scalar(scalar: &[u8; P384_SCALAR_SIZE]) -> std::result::Result<(), SignatureError> {
+    if scalar.iter().all(|b| *b == 0) || scalar[..] >= P384_ORDER[..] {
+        return Err(SignatureError::InvalidComponents);
+    }
+
+    Ok(())
+}
+
+/// Appends a positive DER INTEGER holding the big-endian `scalar`.
+fn der_integer(out: &mut Vec<u8>, scalar: &[u8; P384_SCALAR_SIZE]) {
+    let first = scalar
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(P384_SCALAR_SIZE - 1);
+    let value = &scalar[first..];
+    let pad = value[0] & 0x80 != 0;
+
+    out.push(0x02);
+    out.push((value.len() + pad as usize) as u8);
+    if pad {
+        out.push(0);
+    }
+    out.extend_from_slice(value);
+}
+
+/// Parses a positive, minimally encoded DER INTEGER of at most a P-384
+/// scalar, returning it big-endian along with the remaining input.
+fn parse_der_integer<'a>(
+    der: &'a [u8],
+    name: &'static str,
+) -> std::result::Result<([u8; P384_SCALAR_SIZE], &'a [u8]), SignatureError> {
+    let (value, rest) = match der {
+        [0x02, len, rest @ ..] if *len > 0 && *len < 0x80 && *len as usize <= rest.len() => {
+            rest.split_at(*len as usize)
+        }
+        _ => return Err(SignatureError::InvalidDer),
+    };
+
+    // Negative, or padded with a zero which is not needed.
+    if value[0] & 0x80 != 0 || (value.len() > 1 && value[0] == 0 && value[1] & 0x80 == 0) {
+        return Err(SignatureError::InvalidDer);
+    }
+
+    let value = match value {
+        [0, unpadded @ ..] if !unpadded.is_empty() => unpadded,
+        _ => value,
+    };
+
+    if value.len() > P384_SCALAR_SIZE {
+        return Err(SignatureError::ComponentTooLong {
+            component: name,
+            len: value.len(),
+        });
+    }
+
+    let mut scalar = [0u8; P384_SCALAR_SIZE];
+    scalar[P384_SCALAR_SIZE - value.len()..].copy_from_slice(value);
+
+    Ok((scalar, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::fixtures::milan_report_bytes;
+
+    fn test_signature() -> Signature {
+        let mut signature = Signature::default();
+        for i in 0..P384_SCALAR_SIZE {
+            signature.r[i] = i as u8 + 1;
+            signature.s[i] = 0xff - i as u8;
+        }
+        signature
+    }
+
+    #[test]
+    fn test_der_round_trip() {
+        let signature = test_signature();
+
+        let der = signature.to_der().unwrap();
+        assert_eq!(der[0], 0x30);
+        assert_eq!(Signature::from_der(&der).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_der_layout() {
+        let der = test_signature().to_der().unwrap();
+
+        // SEQUENCE { INTEGER r, INTEGER s }, with s padded by a leading zero
+        // since its top bit is set.
+        assert_eq!(der[..4], [0x30, 0x65, 0x02, 0x30]);
+        assert_eq!(der[4], 48);
+        assert_eq!(der[4 + 48..4 + 48 + 3], [0x02, 0x31, 0x00]);
+        assert_eq!(der[4 + 48 + 3], 0xd0);
+        assert_eq!(der.len(), 2 + 0x65);
+    }
+
+    /// The signature of the Milan fixture report.
+    fn milan_signature() -> Signature {
+        bincode::deserialize(&milan_report_bytes()[0x2a0..]).unwrap()
+    }
+
+    const MILAN_R: &str = "72827fd0029b56ee2b7dec81480554cb05c0379cc2cb70e13da66ea9b7ee4044d54a2af43d235f62971966aa114fab61";
+    const MILAN_S: &str = "49bf903b08ac41cb4673dccf309eabc5446dbb31a95cb1407e976e8c773bc5bbeabf6efe571daf0b1d9a91beb97e9d20";
+
+    #[test]
+    fn test_from_components() {
+        let signature = milan_signature();
+
+        assert_eq!(
+            Signature::from_components(*signature.r(), *signature.s()),
+            signature
+        );
+    }
+
+    #[test]
+    fn test_raw_rs_known_answer() {
+        let signature = milan_signature();
+        let raw = signature.to_raw_rs().unwrap();
+
+        assert_eq!(hex::encode(&raw[..48]), MILAN_R);
+        assert_eq!(hex::encode(&raw[48..]), MILAN_S);
+        assert_eq!(Signature::from_raw_rs(&raw).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_der_known_answer() {
+        let signature = milan_signature();
+        let der = signature.to_der().unwrap();
+
+        // Neither r nor s has its top bit set, so neither is padded.
+        let expected = format!("30640230{}0230{}", MILAN_R, MILAN_S);
+        assert_eq!(hex::encode(&der), expected);
+        assert_eq!(Signature::from_der(&der).unwrap(), signature);
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn test_der_matches_openssl() {
+        let signature = test_signature();
+        let der = signature.to_der().unwrap();
+
+        let sig = ecdsa::EcdsaSig::try_from(&signature).unwrap();
+        assert_eq!(sig.to_der().unwrap(), der);
+        assert_eq!(
+            Signature::from(ecdsa::EcdsaSig::from_der(&der).unwrap()),
+            signature
+        );
+    }
+
+    #[test]
+    fn test_component_too_long() {
+        let mut signature = test_signature();
+        signature.s[50] = 1;
+
+        let err = SignatureError::ComponentTooLong {
+            component: "s",
+            len: 51,
+        };
+        assert_eq!(signature.to_raw_rs(), Err(err));
+        assert_eq!(signature.to_der(), Err(err));
+
+        // INTEGER r of 49 significant bytes.
+        let mut der = vec![0x30, 2 + 49 + 3, 0x02, 49];
+        der.extend_from_slice(&[0x01; 49]);
+        der.extend_from_slice(&[0x02, 0x01, 0x01]);
+        assert_eq!(
+            Signature::from_der(&der),
+            Err(SignatureError::ComponentTooLong {
+                component: "r",
+                len: 49
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_components() {
+        assert_eq!(
+            Signature::default().to_raw_rs(),
+            Err(SignatureError::InvalidComponents)
+        );
+
+        let mut raw = [0x01; RAW_RS_SIZE];
+        raw[..P384_SCALAR_SIZE].copy_from_slice(&P384_ORDER);
+        assert_eq!(
+            Signature::from_raw_rs(&raw),
+            Err(SignatureError::InvalidComponents)
+        );
+    }
+
+    #[test]
+    fn test_from_der_invalid() {
+        assert_eq!(
+            Signature::from_der(&[0x30, 0x03, 0x02, 0x01]),
+            Err(SignatureError::InvalidDer)
+        );
+    }
+}
@@ -0,0 +1,429 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cross-checking of the AMD X.509 extensions carried by VCEK and VLEK
+//! certificates against an attestation report.
+//!
+//! See Chapter 3 of the [Versioned Chip Endorsement Key (VCEK) Certificate and
+//! KDS Interface Specification](https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/57230.pdf).
+
+use super::Certificate;
+
+use crate::{
+    error::{CertError, CertMismatchError},
+    firmware::{
+        guest::AttestationReport,
+        host::{CertType, TcbComponents, TcbVersion},
+    },
+};
+
+/// DER encoding of the 1.3.6.1.4.1.3704.1 arc shared by the AMD extensions.
+const AMD_OID_PREFIX: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x9c, 0x78, 0x01];
+
+/// Suffix of the productName extension (1.3.6.1.4.1.3704.1.2).
+const PRODUCT_NAME: &[u8] = &[0x02];
+
+/// Suffix of the hwID extension (1.3.6.1.4.1.3704.1.4).
+const HW_ID: &[u8] = &[0x04];
+
+/// Suffix of the csp_id extension (1.3.6.1.4.1.3704.1.5), found in VLEKs.
+const CSP_ID: &[u8] = &[0x05];
+
+/// Suffixes of the TCB SVN extensions (1.3.6.1.4.1.3704.1.3.x).
+const BL_SPL: &[u8] = &[0x03, 0x01];
+const TEE_SPL: &[u8] = &[0x03, 0x02];
+const SNP_SPL: &[u8] = &[0x03, 0x03];
+const UCODE_SPL: &[u8] = &[0x03, 0x08];
+const FMC_SPL: &[u8] = &[0x03, 0x09];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EXTENSIONS: u8 = 0xa3;
+
+/// The processor family named by the productName extension.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProductName {
+    /// Third generation EPYC.
+    Milan,
+
+    /// Fourth generation EPYC.
+    Genoa,
+
+    /// Fifth generation EPYC.
+    Turin,
+
+    /// Any other product, as named in the certificate.
+    Other(String),
+}
+
+impl ProductName {
+    /// Parses a productName such as "Milan-B0"; the stepping after the
+    /// dash is not part of the family.
+    fn parse(name: &str) -> Self {
+        match name.split('-').next() {
+            Some("Milan") => Self::Milan,
+            Some("Genoa") => Self::Genoa,
+            Some("Turin") => Self::Turin,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ProductName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Milan => write!(f, "Milan"),
+            Self::Genoa => write!(f, "Genoa"),
+            Self::Turin => write!(f, "Turin"),
+            Self::Other(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// The AMD extensions of a VCEK or VLEK certificate. Extensions absent from
+/// the certificate, e.g. the hwID of a VLEK, are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AmdCertExtensions {
+    /// The productName, e.g. "Milan-B0", stepping included.
+    pub product_name: Option<String>,
+
+    /// The processor family of the productName.
+    pub product: Option<ProductName>,
+
+    /// The bootloader SVN (blSPL).
+    pub bl_spl: Option<u8>,
+
+    /// The TEE SVN (teeSPL).
+    pub tee_spl: Option<u8>,
+
+    /// The SNP firmware SVN (snpSPL).
+    pub snp_spl: Option<u8>,
+
+    /// The microcode SVN (ucodeSPL).
+    pub ucode_spl: Option<u8>,
+
+    /// The FMC firmware SVN (fmcSPL), on Turin and later.
+    pub fmc_spl: Option<u8>,
+
+    /// The chip ID the VCEK was issued for (hwID).
+    pub hw_id: Option<Vec<u8>>,
+
+    /// The cloud service provider a VLEK was issued to (csp_id).
+    pub csp_id: Option<String>,
+}
+
+impl AmdCertExtensions {
+    /// The TCB the certificate was issued for, if it carries all four SVNs.
+    pub fn tcb(&self) -> Option<TcbVersion> {
+        Some(TcbVersion::new(
+            self.bl_spl?,
+            self.tee_spl?,
+            self.snp_spl?,
+            self.ucode_spl?,
+        ))
+    }
+}
+
+impl Certificate {
+    /// Parses the AMD extensions of the certificate.
+    ///
+    /// Certificates other than VCEKs and VLEKs, such as the ARK, parse
+    /// with every field `None`.
+    pub fn amd_extensions(&self) -> Result<AmdCertExtensions, CertError> {
+        let der = self.to_der().map_err(|_| CertError::MalformedCertificate)?;
+        let extensions = amd_extensions(&der).ok_or(CertError::MalformedCertificate)?;
+
+        let find = |suffix: &[u8]| {
+            extensions
+                .iter()
+                .find(|(oid, _)| *oid == suffix)
+                .map(|(_, value)| *value)
+        };
+        let svn = |suffix: &[u8]| {
+            find(suffix)
+                .map(|value| decode_svn(value).ok_or(CertError::MalformedCertificate))
+                .transpose()
+        };
+
+        let product_name = find(PRODUCT_NAME)
+            .map(|value| decode_ia5_string(value).ok_or(CertError::MalformedCertificate))
+            .transpose()?;
+        let csp_id = find(CSP_ID)
+            .map(|value| decode_ia5_string(value).ok_or(CertError::MalformedCertificate))
+            .transpose()?;
+
+        Ok(AmdCertExtensions {
+            product: product_name.as_deref().map(ProductName::parse),
+            product_name,
+            bl_spl: svn(BL_SPL)?,
+            tee_spl: svn(TEE_SPL)?,
+            snp_spl: svn(SNP_SPL)?,
+            ucode_spl: svn(UCODE_SPL)?,
+            fmc_spl: svn(FMC_SPL)?,
+            hw_id: find(HW_ID).map(<[u8]>::to_vec),
+            csp_id,
+        })
+    }
+}
+
+/// Check that the TCB extensions (bootloader, TEE, SNP and microcode SVNs,
+/// and the FMC SVN on Turin and later) of `vek` match the REPORTED_TCB of
+/// `report`, and that its hwID extension matches the CHIP_ID.
+///
+/// The REPORTED_TCB is decoded for the processor family given by the CPUID
+/// fields of the report (see [TcbComponents]).
+///
+/// VLEK certificates carry no hwID, and reports with a masked (all zero)
+/// CHIP_ID cannot be tied to a chip; the chip ID comparison is skipped in
+/// both cases.
+pub fn verify_cert_matches_report(
+    vek: &Certificate,
+    report: &AttestationReport,
+) -> Result<(), CertMismatchError> {
+    let extensions = vek
+        .amd_extensions()
+        .map_err(|_| CertMismatchError::MalformedCertificate)?;
+
+    check_tcb(&extensions, &report.into_latest().reported_tcb())?;
+
+    if let Some(hw_id) = extensions.hw_id {
+        if !report.chip_id_is_masked() && hw_id != report.chip_id.as_ref() {
+            return Err(CertMismatchError::HwIdMismatch);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the TCB extensions of a certificate against a decoded REPORTED_TCB.
+/// The fmcSPL is only required of TCBs with an FMC SVN.
+fn check_tcb(extensions: &AmdCertExtensions, tcb: &TcbComponents) -> Result<(), CertMismatchError> {
+    let fmc = tcb.fmc.map(|fmc| ("fmc", extensions.fmc_spl, fmc));
+    let components = [
+        ("bootloader", extensions.bl_spl, tcb.bootloader),
+        ("tee", extensions.tee_spl, tcb.tee),
+        ("snp", extensions.snp_spl, tcb.snp),
+        ("microcode", extensions.ucode_spl, tcb.microcode),
+    ];
+
+    for (field, cert_svn, report_svn) in fmc.into_iter().chain(components) {
+        let cert_svn = cert_svn.ok_or(CertMismatchError::MissingExtension(field))?;
+
+        if cert_svn != report_svn {
+            return Err(CertMismatchError::TcbMismatch {
+                field,
+                cert: cert_svn,
+                report: report_svn,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Tell a VCEK from a VLEK by its AMD extensions: only VCEKs carry a hwID,
+/// and only VLEKs a csp_id.
+pub(crate) fn vek_cert_type(vek: &Certificate) -> Option<CertType> {
+    let extensions = vek.amd_extensions().ok()?;
+
+    match (extensions.hw_id.is_some(), extensions.csp_id.is_some()) {
+        (true, false) => Some(CertType::VCEK),
+        (false, true) => Some(CertType::VLEK),
+        _ => None,
+    }
+}
+
+/// Split a DER TLV into its tag, contents and the remaining input.
+fn tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, mut input) = input.split_first()?;
+
+    let len = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || input.len() < count {
+            return None;
+        }
+
+        let (bytes, rest) = input.split_at(count);
+        input = rest;
+        bytes.iter().fold(0usize, |len, b| (len << 8) | *b as usize)
+    };
+
+    if input.len() < len {
+        return None;
+    }
+
+    let (value, rest) = input.split_at(len);
+    Some((tag, value, rest))
+}
+
+/// Collect the AMD extensions of a DER-encoded certificate as pairs of OID
+/// suffix (below 1.3.6.1.4.1.3704.1) and extension value.
+fn amd_extensions(der: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let (TAG_SEQUENCE, cert, _) = tlv(der)? else {
+        return None;
+    };
+    let (TAG_SEQUENCE, mut tbs, _) = tlv(cert)? else {
+        return None;
+    };
+
+    // The extensions are the explicitly tagged [3] field at the end of the
+    // TBSCertificate.
+    let mut extensions = loop {
+        let (tag, value, rest) = tlv(tbs)?;
+        if tag == TAG_EXTENSIONS {
+            let (TAG_SEQUENCE, extensions, _) = tlv(value)? else {
+                return None;
+            };
+            break extensions;
+        }
+        tbs = rest;
+    };
+
+    let mut found = vec![];
+
+    while !extensions.is_empty() {
+        let (TAG_SEQUENCE, extension, rest) = tlv(extensions)? else {
+            return None;
+        };
+        extensions = rest;
+
+        let (TAG_OID, oid, mut fields) = tlv(extension)? else {
+            return None;
+        };
+
+        // Skip the optional critical flag to reach the extnValue.
+        let value = loop {
+            let (tag, value, rest) = tlv(fields)?;
+            if tag == TAG_OCTET_STRING {
+                break value;
+            }
+            fields = rest;
+        };
+
+        if let Some(suffix) = oid.strip_prefix(AMD_OID_PREFIX) {
+            found.push((suffix, value));
+        }
+    }
+
+    Some(found)
+}
+
+/// Decode a string extension value, a DER IA5String.
+fn decode_ia5_string(value: &[u8]) -> Option<String> {
+    let (TAG_IA5_STRING, string, _) = tlv(value)? else {
+        return None;
+    };
+
+    match string.is_ascii() {
+        true => String::from_utf8(string.to_vec()).ok(),
+        false => None,
+    }
+}
+
+/// Decode a TCB SVN extension value, a DER INTEGER in the range 0-255.
+fn decode_svn(value: &[u8]) -> Option<u8> {
+    let (TAG_INTEGER, int, _) = tlv(value)? else {
+        return None;
+    };
+
+    match int {
+        [svn] if *svn < 0x80 => Some(*svn),
+        [0x00, svn] if *svn >= 0x80 => Some(*svn),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_name() {
+        assert_eq!(ProductName::parse("Milan-B0"), ProductName::Milan);
+        assert_eq!(ProductName::parse("Genoa"), ProductName::Genoa);
+        assert_eq!(ProductName::parse("Turin-C1"), ProductName::Turin);
+        assert_eq!(
+            ProductName::parse("Siena-A0"),
+            ProductName::Other("Siena-A0".to_string())
+        );
+        assert_eq!(ProductName::parse("Siena-A0").to_string(), "Siena-A0");
+    }
+
+    fn turin_extensions() -> AmdCertExtensions {
+        AmdCertExtensions {
+            product: Some(ProductName::Turin),
+            fmc_spl: Some(1),
+            bl_spl: Some(2),
+            tee_spl: Some(3),
+            snp_spl: Some(4),
+            ucode_spl: Some(5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_tcb_turin() {
+        // FMC, BL, TEE and SNP in bytes 0 to 3, microcode in byte 7.
+        let mut report = AttestationReport::default();
+        report.version = 3;
+        report.reported_tcb = TcbVersion::from_le_bytes([1, 2, 3, 4, 0, 0, 0, 5]);
+        report.set_v3_fields(0x1a, 0x02, 0x00, true);
+        let tcb = report.into_latest().reported_tcb();
+
+        assert_eq!(check_tcb(&turin_extensions(), &tcb), Ok(()));
+
+        let extensions = AmdCertExtensions {
+            snp_spl: Some(3),
+            ..turin_extensions()
+        };
+        assert_eq!(
+            check_tcb(&extensions, &tcb),
+            Err(CertMismatchError::TcbMismatch {
+                field: "snp",
+                cert: 3,
+                report: 4
+            })
+        );
+
+        let extensions = AmdCertExtensions {
+            fmc_spl: Some(0),
+            ..turin_extensions()
+        };
+        assert_eq!(
+            check_tcb(&extensions, &tcb),
+            Err(CertMismatchError::TcbMismatch {
+                field: "fmc",
+                cert: 0,
+                report: 1
+            })
+        );
+
+        let extensions = AmdCertExtensions {
+            fmc_spl: None,
+            ..turin_extensions()
+        };
+        assert_eq!(
+            check_tcb(&extensions, &tcb),
+            Err(CertMismatchError::MissingExtension("fmc"))
+        );
+    }
+
+    #[test]
+    fn test_check_tcb_milan() {
+        let mut report = AttestationReport::default();
+        report.reported_tcb = TcbVersion::new(2, 3, 4, 5);
+        let tcb = report.into_latest().reported_tcb();
+
+        // No fmcSPL is needed without an FMC SVN.
+        let extensions = AmdCertExtensions {
+            fmc_spl: None,
+            ..turin_extensions()
+        };
+        assert_eq!(check_tcb(&extensions, &tcb), Ok(()));
+    }
+}
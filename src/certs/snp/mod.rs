@@ -16,22 +16,102 @@ mod cert;
 #[cfg(feature = "crypto_nossl")]
 mod cert_nossl;
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+mod cache;
+
 #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 mod chain;
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+mod crl;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+mod extensions;
+
 #[cfg(feature = "openssl")]
 pub use cert::Certificate;
 #[cfg(feature = "crypto_nossl")]
 pub use cert_nossl::Certificate;
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub use cache::{verify_report_with_cache, CertCache, CertCacheKey, MemoryCertCache};
+
 #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 pub use chain::Chain;
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub use crl::{Crl, RevocationReason, RevokedCert};
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub(crate) use crl::UtcTime;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub use extensions::{verify_cert_matches_report, AmdCertExtensions, ProductName};
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub(crate) use extensions::vek_cert_type;
+
 use std::io::Result;
 
 #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 use std::io::{Error, ErrorKind};
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+use crate::error::CertFormatError;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+/// The encoding of a certificate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CertFormat {
+    /// PEM-encoded.
+    Pem,
+
+    /// DER-encoded.
+    Der,
+}
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl CertFormat {
+    /// Identifies the format of a certificate from the PEM "-----BEGIN"
+    /// marker, ignoring leading whitespace. Anything else is assumed DER.
+    pub fn identify(bytes: &[u8]) -> Self {
+        const PEM_START: &[u8] = b"-----BEGIN";
+
+        let start = bytes
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(bytes.len());
+
+        match bytes[start..].starts_with(PEM_START) {
+            true => Self::Pem,
+            false => Self::Der,
+        }
+    }
+}
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl std::fmt::Display for CertFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pem => write!(f, "pem"),
+            Self::Der => write!(f, "der"),
+        }
+    }
+}
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl std::str::FromStr for CertFormat {
+    type Err = CertFormatError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pem" => Ok(Self::Pem),
+            "der" => Ok(Self::Der),
+            _ => Err(CertFormatError::UnknownFormat),
+        }
+    }
+}
+
 #[cfg(feature = "openssl")]
 #[allow(dead_code)]
 struct Body;
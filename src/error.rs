@@ -11,7 +11,7 @@ use std::{
 
 use std::os::raw::c_int;
 
-#[cfg(feature = "openssl")]
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 /// Used for representing known errors when handling snp::Certificates.
 pub enum CertFormatError {
@@ -19,10 +19,10 @@ pub enum CertFormatError {
     UnknownFormat,
 }
 
-#[cfg(feature = "openssl")]
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 impl std::error::Error for CertFormatError {}
 
-#[cfg(feature = "openssl")]
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 impl std::fmt::Display for CertFormatError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -31,6 +31,50 @@ impl std::fmt::Display for CertFormatError {
     }
 }
 
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+/// Errors when verifying a certificate chain against a CRL.
+#[derive(Debug)]
+pub enum CrlError {
+    /// The chain, or the CRL's signature, failed to verify.
+    Verification(io::Error),
+
+    /// A certificate in the chain has been revoked.
+    Revoked(crate::certs::snp::RevokedCert),
+
+    /// The chain verified and nothing in it is revoked, but the CRL is past
+    /// its nextUpdate time. Callers tolerating stale revocation data may
+    /// treat this as a warning.
+    Expired {
+        /// When the next CRL was due.
+        next_update: std::time::SystemTime,
+    },
+}
+
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+impl std::fmt::Display for CrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CrlError::Verification(e) => write!(f, "CRL verification failed: {e}"),
+            CrlError::Revoked(revoked) => write!(f, "{revoked}"),
+            CrlError::Expired { next_update } => write!(
+                f,
+                "CRL expired: a new CRL was due at {}",
+                crate::certs::snp::UtcTime(*next_update)
+            ),
+        }
+    }
+}
+
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+impl std::error::Error for CrlError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CrlError::Verification(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 /// An error representingthe upper 32 bits of a SW_EXITINFO2 field set by the VMM.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum VmmError {
@@ -180,6 +224,69 @@ pub enum UserApiError {
     /// Invalid VMPL.
     VmplError,
 
+    /// An empty nonce was provided for the report data.
+    EmptyNonceError,
+
+    /// The data provided for the report does not fit in the 64 bytes of
+    /// report data.
+    ReportDataTooLong(usize),
+
+    /// The hypervisor kept asking for a larger certificate buffer.
+    CertBufferResizeLimit,
+
+    /// A raw firmware response buffer has the wrong size.
+    InvalidResponseSize {
+        /// The size of the response structure.
+        expected: usize,
+        /// The size of the provided buffer.
+        actual: usize,
+    },
+
+    /// The firmware reported an attestation report size which does not
+    /// fit the response buffer or is too small for a report.
+    InvalidReportSize(u32),
+
+    /// The firmware rejected a derived key request carrying a launch
+    /// mitigation vector.
+    LaunchMitVectorError,
+
+    /// A derived key request carries a launch mitigation vector but did not
+    /// go through [DerivedKey::checked](crate::firmware::guest::DerivedKey::checked).
+    LaunchMitVectorUnchecked,
+
+    /// A component of the requested reported TCB is newer than the
+    /// platform's current TCB.
+    ReportedTcbTooHigh,
+
+    /// The request uses a feature which the firmware is too old to support.
+    UnsupportedFirmware {
+        /// The feature, e.g. "the launch mitigation vector".
+        feature: &'static str,
+        /// The first firmware API version supporting the feature.
+        required: crate::Version,
+        /// The firmware API version present.
+        present: crate::Version,
+    },
+
+    /// The firmware completed the guest request with a non-success status.
+    FirmwareStatus {
+        /// The status in the firmware's response.
+        status: FirmwareStatus,
+        /// The VMPL explicitly requested, if any.
+        vmpl: Option<u32>,
+    },
+
+    /// The attestation report in the firmware's response could not be read.
+    ReportError(AttestationReportError),
+
+    /// The firmware did not answer within the given time.
+    #[cfg(feature = "tokio")]
+    Timeout(std::time::Duration),
+
+    /// The blocking task running the request panicked or was cancelled.
+    #[cfg(feature = "tokio")]
+    TaskError(tokio::task::JoinError),
+
     /// Unknown error
     Unknown,
 }
@@ -193,6 +300,21 @@ impl error::Error for UserApiError {
             Self::VmmError(vmm_error) => Some(vmm_error),
             Self::HashstickError(hashstick_error) => Some(hashstick_error),
             Self::VmplError => None,
+            Self::EmptyNonceError => None,
+            Self::ReportDataTooLong(_) => None,
+            Self::CertBufferResizeLimit => None,
+            Self::InvalidResponseSize { .. } => None,
+            Self::InvalidReportSize(_) => None,
+            Self::LaunchMitVectorError => None,
+            Self::LaunchMitVectorUnchecked => None,
+            Self::ReportedTcbTooHigh => None,
+            Self::UnsupportedFirmware { .. } => None,
+            Self::FirmwareStatus { .. } => None,
+            Self::ReportError(report_error) => Some(report_error),
+            #[cfg(feature = "tokio")]
+            Self::Timeout(_) => None,
+            #[cfg(feature = "tokio")]
+            Self::TaskError(task_error) => Some(task_error),
             Self::Unknown => None,
         }
     }
@@ -207,6 +329,57 @@ impl std::fmt::Display for UserApiError {
             Self::VmmError(error) => format!("VMM Error Encountered: {error}"),
             Self::HashstickError(error) => format!("VLEK Hashstick Error Encountered: {error}"),
             Self::VmplError => "Invalid VM Permission Level (VMPL)".to_string(),
+            Self::EmptyNonceError => "Report data nonce must not be empty".to_string(),
+            Self::ReportDataTooLong(len) => {
+                format!("Report data of {len} bytes exceeds the 64-byte limit")
+            }
+            Self::CertBufferResizeLimit => {
+                "The hypervisor kept requesting a larger certificate buffer".to_string()
+            }
+            Self::InvalidResponseSize { expected, actual } => {
+                format!("Invalid firmware response size: expected {expected} bytes, got {actual}")
+            }
+            Self::InvalidReportSize(size) => {
+                format!("Invalid attestation report size reported by the firmware: {size} bytes")
+            }
+            Self::LaunchMitVectorError => {
+                "Invalid parameters for the derived key request: the launch mitigation vector \
+                 requires SNP firmware 1.58 or newer"
+                    .to_string()
+            }
+            Self::LaunchMitVectorUnchecked => {
+                "The launch mitigation vector must be requested through DerivedKey::checked, \
+                 on a kernel passing it to the firmware"
+                    .to_string()
+            }
+            Self::ReportedTcbTooHigh => {
+                "The reported TCB must not exceed the platform's current TCB".to_string()
+            }
+            Self::UnsupportedFirmware {
+                feature,
+                required,
+                present,
+            } => format!(
+                "SNP firmware {present} does not support {feature}, which requires {required} \
+                 or newer"
+            ),
+            Self::FirmwareStatus {
+                status: FirmwareStatus::InvalidParam,
+                vmpl: Some(vmpl),
+            } => format!(
+                "Firmware rejected the request as invalid: check that the requested VMPL \
+                 ({vmpl}) is not more privileged (lower) than the VMPL the guest runs at"
+            ),
+            Self::FirmwareStatus { status, .. } => {
+                format!("Firmware rejected the request: {status}")
+            }
+            Self::ReportError(error) => format!("Attestation Report Error Encountered: {error}"),
+            #[cfg(feature = "tokio")]
+            Self::Timeout(timeout) => {
+                format!("The firmware did not answer within {timeout:?}")
+            }
+            #[cfg(feature = "tokio")]
+            Self::TaskError(error) => format!("The firmware request task failed: {error}"),
             Self::Unknown => "Unknown Error Encountered!".to_string(),
         };
         write!(f, "{err_msg}")
@@ -249,6 +422,12 @@ impl std::convert::From<CertError> for UserApiError {
     }
 }
 
+impl std::convert::From<AttestationReportError> for UserApiError {
+    fn from(report_error: AttestationReportError) -> Self {
+        Self::ReportError(report_error)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 /// Errors which may be encountered when handling Version Loaded Endorsement Keys
 /// (VLEK) Hashsticks.
@@ -259,6 +438,14 @@ pub enum HashstickError {
     /// No hashstick was provided
     EmptyHashstickBuffer,
 
+    /// The firmware could not unwrap the hashstick (INVALID_KEY), e.g. it
+    /// was wrapped for another platform or with another CSP key.
+    InvalidKey,
+
+    /// The TCB of the hashstick does not match the platform's reported TCB
+    /// (BAD_SVN).
+    TcbMismatch,
+
     /// Unknown Error.
     UnknownError,
 }
@@ -275,6 +462,14 @@ impl std::fmt::Display for HashstickError {
                 )
             }
             HashstickError::EmptyHashstickBuffer => write!(f, "Hashstick buffer is empty."),
+            HashstickError::InvalidKey => write!(
+                f,
+                "The firmware could not unwrap the VLEK hashstick; it was not wrapped for this platform."
+            ),
+            HashstickError::TcbMismatch => write!(
+                f,
+                "The VLEK hashstick was issued for a TCB other than the reported TCB."
+            ),
             HashstickError::UnknownError => {
                 write!(
                     f,
@@ -300,6 +495,25 @@ pub enum CertError {
     /// No certificates were set by the Host.
     EmptyCertBuffer,
 
+    /// A certificate, or one of its extensions, could not be parsed.
+    MalformedCertificate,
+
+    /// A certificate table holds more than one certificate of a type.
+    #[cfg(feature = "snp")]
+    DuplicateCertType(crate::firmware::host::CertType),
+
+    /// A certificate table does not fit in the buffer provided for it.
+    CertTableTooLarge {
+        /// The size of the table, in bytes.
+        needed: usize,
+        /// The size of the buffer, in bytes.
+        available: usize,
+    },
+
+    /// A certificate table does not parse back into the certificates it
+    /// was built from.
+    CertTableMismatch,
+
     /// Unknown Error.
     UnknownError,
 }
@@ -323,12 +537,141 @@ impl std::fmt::Display for CertError {
                     "No certificates were provided by the host, please contact your CSP."
                 )
             }
+            CertError::MalformedCertificate => {
+                write!(f, "Malformed certificate or certificate extension.")
+            }
+            #[cfg(feature = "snp")]
+            CertError::DuplicateCertType(cert_type) => write!(
+                f,
+                "Duplicate {} certificate in the certificate table.",
+                cert_type.to_string()
+            ),
+            CertError::CertTableTooLarge { needed, available } => write!(
+                f,
+                "Certificate table of {needed} bytes exceeds the {available} bytes available."
+            ),
+            CertError::CertTableMismatch => write!(
+                f,
+                "Certificate table does not parse back into its certificates."
+            ),
         }
     }
 }
 
 impl error::Error for CertError {}
 
+/// Errors reported in the status of a derived key response, or in
+/// expanding a derived key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivedKeyError {
+    /// The firmware rejected the request parameters (status 16h).
+    InvalidParameters,
+
+    /// Any other non-zero status.
+    UnknownStatus(u32),
+
+    /// The requested subkey is longer than HKDF-SHA-384 can produce;
+    /// holds the requested length.
+    SubkeyTooLong(usize),
+}
+
+impl std::fmt::Display for DerivedKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DerivedKeyError::InvalidParameters => {
+                write!(f, "Invalid parameters for the derived key request")
+            }
+            DerivedKeyError::UnknownStatus(status) => {
+                write!(f, "Unknown derived key response status {status:#x}")
+            }
+            DerivedKeyError::SubkeyTooLong(length) => {
+                write!(f, "Subkey of {length} bytes exceeds the HKDF-SHA-384 limit")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DerivedKeyError {}
+
+/// The status of a guest message response (e.g. MSG_REPORT_RSP or
+/// MSG_KEY_RSP) from the firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirmwareStatus {
+    /// The request succeeded.
+    Success, // 0x0000
+
+    /// The request was not permitted by the guest policy.
+    PolicyFailure, // 0x0007
+
+    /// An address in the request is invalid.
+    InvalidAddress, // 0x0009
+
+    /// A parameter of the request is invalid, e.g. a VMPL more privileged
+    /// than the guest's own.
+    InvalidParam, // 0x0016
+
+    /// The requested key is invalid, not present, or not allowed.
+    InvalidKey, // 0x0027
+
+    /// Any other status, carrying the raw code.
+    Other(u32),
+}
+
+impl FirmwareStatus {
+    /// Convert the status into a result, attaching the VMPL explicitly
+    /// requested, if any, to errors.
+    pub fn into_result(self, vmpl: Option<u32>) -> Result<(), UserApiError> {
+        match self {
+            FirmwareStatus::Success => Ok(()),
+            status => Err(UserApiError::FirmwareStatus { status, vmpl }),
+        }
+    }
+}
+
+impl From<u32> for FirmwareStatus {
+    fn from(status: u32) -> Self {
+        match status {
+            0x00 => FirmwareStatus::Success,
+            0x07 => FirmwareStatus::PolicyFailure,
+            0x09 => FirmwareStatus::InvalidAddress,
+            0x16 => FirmwareStatus::InvalidParam,
+            0x27 => FirmwareStatus::InvalidKey,
+            other => FirmwareStatus::Other(other),
+        }
+    }
+}
+
+impl From<FirmwareStatus> for u32 {
+    fn from(status: FirmwareStatus) -> Self {
+        match status {
+            FirmwareStatus::Success => 0x00,
+            FirmwareStatus::PolicyFailure => 0x07,
+            FirmwareStatus::InvalidAddress => 0x09,
+            FirmwareStatus::InvalidParam => 0x16,
+            FirmwareStatus::InvalidKey => 0x27,
+            FirmwareStatus::Other(other) => other,
+        }
+    }
+}
+
+impl std::fmt::Display for FirmwareStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FirmwareStatus::Success => write!(f, "Success"),
+            FirmwareStatus::PolicyFailure => write!(f, "Policy failure"),
+            FirmwareStatus::InvalidAddress => write!(f, "Provided address is invalid"),
+            FirmwareStatus::InvalidParam => write!(f, "Given parameter is invalid"),
+            FirmwareStatus::InvalidKey => {
+                write!(
+                    f,
+                    "The key requested is invalid, not present, or not allowed"
+                )
+            }
+            FirmwareStatus::Other(code) => write!(f, "Unknown status {code:#x}"),
+        }
+    }
+}
+
 /// Error conditions returned by the SEV platform or by layers above it
 /// (i.e., the Linux kernel).
 ///
@@ -787,6 +1130,117 @@ impl std::fmt::Display for SevHashError {
 
 impl std::error::Error for SevHashError {}
 
+/// Errors in issuing a [Challenge](crate::firmware::guest::challenge::Challenge)
+/// or checking that a report answers it.
+#[cfg(feature = "snp")]
+#[derive(Debug)]
+pub enum FreshnessError {
+    /// No random nonce could be read.
+    Rng(io::Error),
+
+    /// The REPORT_DATA does not bind the challenge.
+    ReportDataMismatch,
+
+    /// The challenge is older than allowed.
+    Expired {
+        /// The time since the challenge was issued.
+        age: std::time::Duration,
+        /// The greatest age allowed.
+        max_age: std::time::Duration,
+    },
+
+    /// The challenge was issued after the current time, or before the Unix
+    /// epoch.
+    IssuedInFuture,
+}
+
+#[cfg(feature = "snp")]
+impl std::fmt::Display for FreshnessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FreshnessError::Rng(e) => write!(f, "Unable to generate a nonce: {e}"),
+            FreshnessError::ReportDataMismatch => {
+                write!(f, "Report data does not bind the challenge")
+            }
+            FreshnessError::Expired { age, max_age } => write!(
+                f,
+                "Challenge issued {}s ago, exceeding the maximum age of {}s",
+                age.as_secs(),
+                max_age.as_secs()
+            ),
+            FreshnessError::IssuedInFuture => {
+                write!(f, "Challenge issued in the future, check the clocks")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "snp")]
+impl std::error::Error for FreshnessError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FreshnessError::Rng(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors in parsing or verifying a legacy SEV (non-SNP) launch
+/// measurement.
+#[cfg(feature = "sev")]
+#[derive(Debug)]
+pub enum LegacyMeasurementError {
+    /// The input is not the size of a measurement.
+    InvalidLength {
+        /// The size of a measurement, in bytes.
+        expected: usize,
+        /// The size of the input, in bytes.
+        actual: usize,
+    },
+
+    /// The measurement does not match the one computed with the TIK.
+    MeasurementMismatch,
+
+    /// The HMAC could not be computed.
+    #[cfg(feature = "openssl")]
+    Crypto(openssl::error::ErrorStack),
+}
+
+#[cfg(feature = "sev")]
+impl std::fmt::Display for LegacyMeasurementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LegacyMeasurementError::InvalidLength { expected, actual } => write!(
+                f,
+                "Invalid launch measurement size: expected {expected} bytes, got {actual}"
+            ),
+            LegacyMeasurementError::MeasurementMismatch => {
+                write!(f, "Launch measurement does not match")
+            }
+            #[cfg(feature = "openssl")]
+            LegacyMeasurementError::Crypto(e) => write!(f, "Unable to compute the HMAC: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "sev")]
+impl std::error::Error for LegacyMeasurementError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "openssl")]
+            LegacyMeasurementError::Crypto(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "sev", feature = "openssl"))]
+impl std::convert::From<openssl::error::ErrorStack> for LegacyMeasurementError {
+    fn from(value: openssl::error::ErrorStack) -> Self {
+        Self::Crypto(value)
+    }
+}
+
 /// Possible errors when working with the large array type
 #[derive(Debug)]
 pub enum LargeArrayError {
@@ -885,102 +1339,949 @@ impl std::convert::From<bincode::ErrorKind> for IdBlockError {
     }
 }
 
-/// Errors which may be encountered when calculating the guest measurement.
-#[derive(Debug)]
-pub enum MeasurementError {
-    /// TryFrom Slice Error handling
-    FromSliceError(TryFromSliceError),
+/// Errors which may be encountered when building a guest field selection.
+#[derive(Debug, PartialEq, Eq)]
+pub enum GuestFieldSelectError {
+    /// An unknown field name was provided.
+    UnknownField(String),
 
-    /// UUID Error handling
-    UUIDError(uuid::Error),
+    /// Reserved bits (7 to 63) are set.
+    InvalidBits(u64),
+}
 
-    /// Bincode Error Handling
-    BincodeError(bincode::ErrorKind),
+impl std::fmt::Display for GuestFieldSelectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GuestFieldSelectError::UnknownField(name) => write!(
+                f,
+                "Unknown guest field \"{name}\", valid fields are: guest_policy, image_id, \
+                 family_id, measurement, guest_svn, tcb_version, launch_mit_vector"
+            ),
+            GuestFieldSelectError::InvalidBits(bits) => {
+                write!(f, "Reserved guest field select bits are set: {bits:#x}")
+            }
+        }
+    }
+}
 
-    /// File Error Handling
-    FileError(std::io::Error),
+impl std::error::Error for GuestFieldSelectError {}
 
-    /// Vec from hex Error Handling
-    FromHexError(hex::FromHexError),
+/// Errors which may be encountered when building a guest policy.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PolicyError {
+    /// An unknown policy key was provided.
+    UnknownKey(String),
 
-    /// Guest Context Error Handling
-    GCTXError(GCTXError),
+    /// The value provided for a policy key is invalid.
+    InvalidValue(String, String),
 
-    /// OVMF Error Handling
-    OVMFError(OVMFError),
+    /// The ABI version provided for a policy key does not fit in a byte.
+    AbiOutOfRange(String, u64),
 
-    /// SEV Hash Error Handling
-    SevHashError(SevHashError),
+    /// Reserved bit 17 of the policy is not set.
+    ReservedBitUnset,
 
-    /// Id Block Error Handling
-    IdBlockError(IdBlockError),
+    /// Reserved must-be-zero bits of the policy are set.
+    ReservedBitsSet(u64),
+}
 
-    /// Invalid VCPU provided
-    InvalidVcpuTypeError(String),
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyError::UnknownKey(key) => write!(
+                f,
+                "Unknown guest policy key \"{key}\", valid keys are: abi_major, abi_minor, smt, \
+                 migrate_ma, debug, single_socket, cxl, mem_aes_256_xts, rapl_dis, \
+                 ciphertext_hiding"
+            ),
+            PolicyError::InvalidValue(key, value) => {
+                write!(
+                    f,
+                    "Invalid value \"{value}\" for guest policy key \"{key}\""
+                )
+            }
+            PolicyError::AbiOutOfRange(key, value) => {
+                write!(f, "Guest policy {key} {value} is out of range (0-255)")
+            }
+            PolicyError::ReservedBitUnset => {
+                write!(f, "Guest policy reserved bit 17 must be set")
+            }
+            PolicyError::ReservedBitsSet(bits) => {
+                write!(
+                    f,
+                    "Guest policy reserved bits must be zero (found {bits:#x})"
+                )
+            }
+        }
+    }
+}
 
-    /// Invalid VMM Provided
-    InvalidVmmError(String),
+impl std::error::Error for PolicyError {}
 
-    /// Invalid SEV Mode provided
-    InvalidSevModeError(String),
+/// Violations found when checking the guest policy of an attestation report
+/// against the policy required by a verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The guest ABI version is older than the required one.
+    AbiVersionTooLow {
+        /// The required (major, minor) ABI version.
+        required: (u8, u8),
+        /// The (major, minor) ABI version found in the report.
+        present: (u8, u8),
+    },
 
-    /// OVMF doesn't support kernel measurement
-    InvalidOvmfKernelError,
+    /// The guest allows host SMT usage, but it is not allowed.
+    SmtNotAllowed,
 
-    /// OVMF is missing required section with kernel specified
-    MissingSection(String),
+    /// The guest allows association with a migration agent, but it is not allowed.
+    MigrateMaNotAllowed,
+
+    /// The guest allows debugging, but it is not allowed.
+    DebugNotAllowed,
+
+    /// The guest can be activated on multiple sockets, but a single socket is required.
+    SingleSocketRequired,
+
+    /// The guest allows CXL devices or memory, but it is not allowed.
+    CxlNotAllowed,
+
+    /// The guest allows AES 128 XEX memory encryption, but AES 256 XTS is required.
+    AesKeyTooWeak,
+
+    /// The guest allows RAPL, but RAPL must be disabled.
+    RaplRequired,
+
+    /// The guest does not require ciphertext hiding, but it is required.
+    CiphertextHidingRequired,
 }
 
-impl std::fmt::Display for MeasurementError {
+impl std::fmt::Display for PolicyViolation {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            MeasurementError::FromSliceError(e) => write!(f, "Error converting slice: {e}"),
-            MeasurementError::UUIDError(e) => write!(f, "UUID Error encountered: {e}"),
-            MeasurementError::BincodeError(e) => write!(f, "Bincode error encountered: {e}"),
-            MeasurementError::FileError(e) => write!(f, "Failed handling file: {e}"),
-            MeasurementError::FromHexError(e) => write!(f, "Converting hex to vector error: {e}"),
-            MeasurementError::GCTXError(e) => write!(f, "GCTX Error Encountered: {e}"),
-            MeasurementError::OVMFError(e) => write!(f, "OVMF Error Encountered: {e}"),
-            MeasurementError::SevHashError(e) => write!(f, "Sev hash Error Encountered: {e}"),
-            MeasurementError::IdBlockError(e) => write!(f, "Id Block Error Encountered: {e}"),
-            MeasurementError::InvalidVcpuTypeError(value) => {
-                write!(f, "Invalid VCPU type value provided: {value}")
+            PolicyViolation::AbiVersionTooLow { required, present } => write!(
+                f,
+                "Guest ABI version {}.{} is lower than the required {}.{}",
+                present.0, present.1, required.0, required.1
+            ),
+            PolicyViolation::SmtNotAllowed => write!(f, "Guest policy allows SMT"),
+            PolicyViolation::MigrateMaNotAllowed => {
+                write!(f, "Guest policy allows a migration agent")
             }
-            MeasurementError::InvalidVmmError(value) => {
-                write!(f, "Invalid VMM type provided: {value}")
+            PolicyViolation::DebugNotAllowed => write!(f, "Guest policy allows debugging"),
+            PolicyViolation::SingleSocketRequired => {
+                write!(f, "Guest policy does not require a single socket")
             }
-            MeasurementError::InvalidSevModeError(value) => {
-                write!(f, "Invalid SEV mode provided: {value}")
+            PolicyViolation::CxlNotAllowed => write!(f, "Guest policy allows CXL"),
+            PolicyViolation::AesKeyTooWeak => {
+                write!(
+                    f,
+                    "Guest policy does not require AES 256 XTS memory encryption"
+                )
+            }
+            PolicyViolation::RaplRequired => {
+                write!(f, "Guest policy does not require RAPL to be disabled")
+            }
+            PolicyViolation::CiphertextHidingRequired => {
+                write!(f, "Guest policy does not require ciphertext hiding")
             }
-            MeasurementError::InvalidOvmfKernelError => write!(
-                f,
-                "Kernel specified but OVMF doesn't support kernel/initrd/cmdline measurement"
-            ),
-            MeasurementError::MissingSection(section) => write!(
-                f,
-                "Kernel specified but OVMF metadata doesn't include {section} section"
-            ),
         }
     }
 }
 
-impl std::error::Error for MeasurementError {}
+impl std::error::Error for PolicyViolation {}
 
-impl std::convert::From<TryFromSliceError> for MeasurementError {
-    fn from(value: TryFromSliceError) -> Self {
-        Self::FromSliceError(value)
-    }
-}
+/// A launch configuration field of an attestation report which differs from
+/// the expected value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchConfigError {
+    /// FAMILY_ID differs from the expected family ID.
+    FamilyIdMismatch,
 
-impl std::convert::From<uuid::Error> for MeasurementError {
-    fn from(value: uuid::Error) -> Self {
-        Self::UUIDError(value)
-    }
+    /// IMAGE_ID differs from the expected image ID.
+    ImageIdMismatch,
+
+    /// HOST_DATA differs from the expected host data.
+    HostDataMismatch,
 }
 
-impl std::convert::From<bincode::ErrorKind> for MeasurementError {
-    fn from(value: bincode::ErrorKind) -> Self {
-        Self::BincodeError(value)
+impl std::fmt::Display for LaunchConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LaunchConfigError::FamilyIdMismatch => {
+                write!(f, "Report FAMILY_ID does not match the expected value")
+            }
+            LaunchConfigError::ImageIdMismatch => {
+                write!(f, "Report IMAGE_ID does not match the expected value")
+            }
+            LaunchConfigError::HostDataMismatch => {
+                write!(f, "Report HOST_DATA does not match the expected value")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LaunchConfigError {}
+
+/// Errors which may be encountered when checking the signing key of an
+/// attestation report before verifying its signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportSignatureError {
+    /// The report was not signed by the firmware (SIGNING_KEY is 7).
+    SignatureMissing,
+
+    /// The report carries a reserved SIGNING_KEY value.
+    UnknownSigningKey(u32),
+}
+
+impl std::fmt::Display for ReportSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReportSignatureError::SignatureMissing => {
+                write!(f, "The attestation report is not signed")
+            }
+            ReportSignatureError::UnknownSigningKey(key) => {
+                write!(
+                    f,
+                    "The attestation report uses a reserved signing key ({key})"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReportSignatureError {}
+
+/// Errors which may be encountered when building the REPORT_DATA of a
+/// report request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportDataError {
+    /// The nonce does not fit in the 64 bytes of report data.
+    NonceTooLong(usize),
+}
+
+impl std::fmt::Display for ReportDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReportDataError::NonceTooLong(len) => {
+                write!(f, "Nonce of {len} bytes exceeds the 64-byte report data")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReportDataError {}
+
+/// Errors when strictly decoding an attestation report from JSON. Each
+/// shape error holds the JSON pointer (RFC 6901) of the offending value.
+#[cfg(feature = "serde_json")]
+#[derive(Debug)]
+pub enum JsonReportError {
+    /// The input is not valid JSON, or a value is out of range.
+    Json(serde_json::Error),
+
+    /// A field this version of the crate does not know.
+    UnknownField(String),
+
+    /// A field of the report is missing.
+    MissingField(String),
+
+    /// A value has the wrong JSON type.
+    TypeMismatch(String),
+
+    /// An array has the wrong number of elements.
+    InvalidLength {
+        /// The JSON pointer of the array.
+        pointer: String,
+        /// The expected number of elements.
+        expected: usize,
+        /// The number of elements found.
+        actual: usize,
+    },
+
+    /// The report VERSION is not one this crate knows.
+    UnsupportedVersion(u32),
+}
+
+#[cfg(feature = "serde_json")]
+impl JsonReportError {
+    /// The JSON pointer of the offending value, for shape errors.
+    pub fn pointer(&self) -> Option<&str> {
+        match self {
+            JsonReportError::UnknownField(pointer)
+            | JsonReportError::MissingField(pointer)
+            | JsonReportError::TypeMismatch(pointer)
+            | JsonReportError::InvalidLength { pointer, .. } => Some(pointer),
+            JsonReportError::Json(_) | JsonReportError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl std::fmt::Display for JsonReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JsonReportError::Json(e) => write!(f, "Invalid report JSON: {e}"),
+            JsonReportError::UnknownField(pointer) => write!(f, "Unknown field at {pointer}"),
+            JsonReportError::MissingField(pointer) => write!(f, "Missing field at {pointer}"),
+            JsonReportError::TypeMismatch(pointer) => {
+                write!(f, "Value at {pointer} has the wrong type")
+            }
+            JsonReportError::InvalidLength {
+                pointer,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Array at {pointer} has {actual} elements, expected {expected}"
+            ),
+            JsonReportError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported report version {version}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl std::error::Error for JsonReportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            JsonReportError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl std::convert::From<serde_json::Error> for JsonReportError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Errors when encoding or decoding an attestation report as CBOR.
+#[cfg(feature = "cbor")]
+#[derive(Debug)]
+pub enum CborError {
+    /// The report could not be encoded.
+    Encode(ciborium::ser::Error<std::io::Error>),
+
+    /// The input is not a well-formed CBOR byte string.
+    Decode(ciborium::de::Error<std::io::Error>),
+
+    /// The byte string is not the size of an attestation report.
+    InvalidLength(usize),
+
+    /// The report bytes could not be (de)serialized.
+    Report(AttestationReportError),
+}
+
+#[cfg(feature = "cbor")]
+impl std::fmt::Display for CborError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CborError::Encode(e) => write!(f, "CBOR encoding failed: {e}"),
+            CborError::Decode(e) => write!(f, "CBOR decoding failed: {e}"),
+            CborError::InvalidLength(len) => {
+                write!(
+                    f,
+                    "CBOR byte string of {len} bytes is not an attestation report"
+                )
+            }
+            CborError::Report(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl std::error::Error for CborError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CborError::Encode(e) => Some(e),
+            CborError::Decode(e) => Some(e),
+            CborError::InvalidLength(_) => None,
+            CborError::Report(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl std::convert::From<ciborium::ser::Error<std::io::Error>> for CborError {
+    fn from(value: ciborium::ser::Error<std::io::Error>) -> Self {
+        Self::Encode(value)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl std::convert::From<ciborium::de::Error<std::io::Error>> for CborError {
+    fn from(value: ciborium::de::Error<std::io::Error>) -> Self {
+        Self::Decode(value)
+    }
+}
+
+/// Errors when minting or verifying attestation tokens.
+#[cfg(feature = "tokens")]
+#[derive(Debug)]
+pub enum TokenError {
+    /// The key is not an ECDSA P-384 key, as ES384 requires.
+    UnsupportedKey,
+
+    /// The token is signed with an algorithm other than ES384.
+    UnsupportedAlgorithm(String),
+
+    /// The token does not have the structure of a JWT or COSE_Sign1.
+    Malformed(&'static str),
+
+    /// The signature of the token does not verify.
+    InvalidSignature,
+
+    /// A part of a JWT is not valid base64url.
+    Base64(base64::DecodeError),
+
+    /// The header or claims of a JWT are not valid JSON.
+    Json(serde_json::Error),
+
+    /// A COSE_Sign1 message is not valid CBOR.
+    Cbor(CborError),
+
+    /// An OpenSSL error while signing or verifying.
+    Crypto(openssl::error::ErrorStack),
+}
+
+#[cfg(feature = "tokens")]
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TokenError::UnsupportedKey => write!(f, "ES384 tokens require a P-384 key"),
+            TokenError::UnsupportedAlgorithm(alg) => {
+                write!(f, "Unsupported token algorithm {alg:?}, expected ES384")
+            }
+            TokenError::Malformed(reason) => write!(f, "Malformed token: {reason}"),
+            TokenError::InvalidSignature => write!(f, "Token signature does not verify"),
+            TokenError::Base64(e) => write!(f, "Invalid base64url in token: {e}"),
+            TokenError::Json(e) => write!(f, "Invalid JSON in token: {e}"),
+            TokenError::Cbor(e) => write!(f, "Invalid CBOR in token: {e}"),
+            TokenError::Crypto(e) => write!(f, "Token cryptography failed: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl std::error::Error for TokenError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TokenError::UnsupportedKey
+            | TokenError::UnsupportedAlgorithm(_)
+            | TokenError::Malformed(_)
+            | TokenError::InvalidSignature => None,
+            TokenError::Base64(e) => Some(e),
+            TokenError::Json(e) => Some(e),
+            TokenError::Cbor(e) => Some(e),
+            TokenError::Crypto(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl std::convert::From<base64::DecodeError> for TokenError {
+    fn from(value: base64::DecodeError) -> Self {
+        Self::Base64(value)
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl std::convert::From<serde_json::Error> for TokenError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl std::convert::From<CborError> for TokenError {
+    fn from(value: CborError) -> Self {
+        Self::Cbor(value)
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl std::convert::From<openssl::error::ErrorStack> for TokenError {
+    fn from(value: openssl::error::ErrorStack) -> Self {
+        Self::Crypto(value)
+    }
+}
+
+/// Errors when converting an SEV-SNP ECDSA signature to or from DER or
+/// raw r||s encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The r and s components do not form a P-384 signature.
+    InvalidComponents,
+
+    /// The DER input is not a P-384 ECDSA signature.
+    InvalidDer,
+
+    /// A component has more significant bytes than a P-384 scalar.
+    ComponentTooLong {
+        /// The component, `r` or `s`.
+        component: &'static str,
+        /// Its number of significant bytes.
+        len: usize,
+    },
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SignatureError::InvalidComponents => {
+                write!(f, "Signature components are not a valid P-384 signature")
+            }
+            SignatureError::InvalidDer => write!(f, "Invalid DER-encoded ECDSA P-384 signature"),
+            SignatureError::ComponentTooLong { component, len } => write!(
+                f,
+                "Signature component {component} has {len} significant bytes, more than the \
+                 48 of a P-384 scalar"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Errors when parsing or using a CHIP_ID.
+#[derive(Debug, PartialEq)]
+pub enum ChipIdError {
+    /// The chip ID is not valid hex.
+    InvalidHex(hex::FromHexError),
+
+    /// The chip ID does not decode to 64 bytes.
+    InvalidLength(usize),
+
+    /// The chip ID was masked (all zeroes) by the firmware, so it cannot
+    /// identify the chip.
+    ChipIdMasked,
+
+    /// The report does not identify a processor generation served by the
+    /// KDS.
+    UnknownProduct,
+}
+
+impl std::fmt::Display for ChipIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChipIdError::InvalidHex(e) => write!(f, "Invalid chip ID hex: {e}"),
+            ChipIdError::InvalidLength(len) => {
+                write!(f, "Chip ID is {len} bytes long, expected 64")
+            }
+            ChipIdError::ChipIdMasked => write!(f, "Chip ID is masked by the firmware"),
+            ChipIdError::UnknownProduct => {
+                write!(f, "Report does not identify a processor served by the KDS")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChipIdError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ChipIdError::InvalidHex(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::convert::From<hex::FromHexError> for ChipIdError {
+    fn from(value: hex::FromHexError) -> Self {
+        Self::InvalidHex(value)
+    }
+}
+
+/// Mismatches found when cross-checking the AMD extensions of a VCEK/VLEK
+/// certificate against an attestation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertMismatchError {
+    /// The certificate could not be encoded or its extensions are malformed.
+    MalformedCertificate,
+
+    /// A required AMD extension is missing from the certificate.
+    MissingExtension(&'static str),
+
+    /// A TCB component of the certificate does not match the report's REPORTED_TCB.
+    TcbMismatch {
+        /// The name of the TCB component.
+        field: &'static str,
+        /// The SVN found in the certificate.
+        cert: u8,
+        /// The SVN found in the report.
+        report: u8,
+    },
+
+    /// The certificate hwID does not match the report's CHIP_ID.
+    HwIdMismatch,
+}
+
+impl std::fmt::Display for CertMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CertMismatchError::MalformedCertificate => {
+                write!(f, "The certificate extensions could not be parsed")
+            }
+            CertMismatchError::MissingExtension(name) => {
+                write!(f, "The certificate is missing the {name} extension")
+            }
+            CertMismatchError::TcbMismatch {
+                field,
+                cert,
+                report,
+            } => write!(
+                f,
+                "Certificate {field} SVN {cert} does not match the reported TCB ({report})"
+            ),
+            CertMismatchError::HwIdMismatch => {
+                write!(f, "Certificate hwID does not match the report chip ID")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CertMismatchError {}
+
+/// Errors in finding an attestation report embedded in a larger blob.
+#[derive(Debug)]
+pub enum ReportSearchError {
+    /// No offset of the blob holds the header of a report.
+    NotFound,
+
+    /// The first candidate report failed strict parsing.
+    InvalidCandidate {
+        /// The offset of the candidate in the blob.
+        offset: usize,
+        /// The parsing error.
+        error: AttestationReportError,
+    },
+
+    /// The first candidate report is not signed by the given chain.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    InvalidSignature {
+        /// The offset of the candidate in the blob.
+        offset: usize,
+        /// The verification error.
+        error: io::Error,
+    },
+}
+
+impl std::fmt::Display for ReportSearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReportSearchError::NotFound => write!(f, "No attestation report found in the blob"),
+            ReportSearchError::InvalidCandidate { offset, error } => {
+                write!(
+                    f,
+                    "Invalid attestation report at offset {offset:#x}: {error}"
+                )
+            }
+            #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+            ReportSearchError::InvalidSignature { offset, error } => write!(
+                f,
+                "Attestation report at offset {offset:#x} does not verify: {error}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReportSearchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReportSearchError::NotFound => None,
+            ReportSearchError::InvalidCandidate { error, .. } => Some(error),
+            #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+            ReportSearchError::InvalidSignature { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Errors which may be encountered when handling attestation reports.
+#[derive(Debug)]
+pub enum AttestationReportError {
+    /// Bincode Error Handling
+    BincodeError(bincode::ErrorKind),
+
+    /// File Error Handling
+    IoError(std::io::Error),
+
+    /// AUTHOR_KEY_EN and AUTHOR_KEY_DIGEST disagree: the flag is set with a
+    /// zero digest, or clear with a non-zero one.
+    AuthorKeyInconsistent {
+        /// The value of AUTHOR_KEY_EN.
+        author_key_en: bool,
+    },
+
+    /// The report was not signed with the kind of key the certificate chain
+    /// ends in.
+    #[cfg(feature = "snp")]
+    KeyTypeMismatch {
+        /// The signing key given by the report's KEY_INFO.
+        report: crate::firmware::guest::SigningKey,
+        /// The kind of the chain's leaf certificate.
+        chain: crate::firmware::host::CertType,
+    },
+
+    /// The chain's leaf certificate is neither a VCEK nor a VLEK.
+    UnknownLeafCertificate,
+
+    /// The buffer holding the report is too short; holds its length.
+    BufferTooSmall(usize),
+
+    /// The report version does not define the requested field.
+    FieldUnavailable {
+        /// The name of the field.
+        field: &'static str,
+        /// The version of the report.
+        version: u32,
+    },
+
+    /// A reserved byte of the report is not zero.
+    ReservedNonZero {
+        /// The offset of the first non-zero reserved byte in the report.
+        offset: usize,
+    },
+
+    /// The input is not valid base64.
+    #[cfg(feature = "base64")]
+    Base64(base64::DecodeError),
+
+    /// The input ends before a whole report could be read.
+    UnexpectedEndOfData {
+        /// The size of a report, in bytes.
+        expected: usize,
+        /// The size of the input, in bytes.
+        actual: usize,
+    },
+
+    /// The report size given by the firmware exceeds the response buffer.
+    ReportSizeExceedsBuffer {
+        /// The size of the report given by the firmware, in bytes.
+        report_size: usize,
+        /// The size of the response buffer, in bytes.
+        buffer_size: usize,
+    },
+
+    /// The firmware rejected the report request.
+    StatusError(FirmwareStatus),
+
+    /// The report predates the layout of [AttestationReport](crate::firmware::guest::AttestationReport);
+    /// holds its version.
+    UnsupportedReportVersion(u32),
+}
+
+impl std::fmt::Display for AttestationReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AttestationReportError::BincodeError(e) => {
+                write!(f, "Bincode error encountered: {e}")
+            }
+            AttestationReportError::IoError(e) => write!(f, "Failed handling file: {e}"),
+            AttestationReportError::AuthorKeyInconsistent { author_key_en } => write!(
+                f,
+                "AUTHOR_KEY_EN is {} but the author key digest is {}",
+                u8::from(*author_key_en),
+                if *author_key_en { "zero" } else { "non-zero" }
+            ),
+            #[cfg(feature = "snp")]
+            AttestationReportError::KeyTypeMismatch { report, chain } => write!(
+                f,
+                "Report is signed by {report} but the certificate chain ends in a {}",
+                match chain {
+                    crate::firmware::host::CertType::VLEK => "VLEK",
+                    crate::firmware::host::CertType::VCEK => "VCEK",
+                    _ => "non-endorsement-key certificate",
+                }
+            ),
+            AttestationReportError::UnknownLeafCertificate => write!(
+                f,
+                "Certificate chain does not end in a VCEK or VLEK certificate"
+            ),
+            AttestationReportError::BufferTooSmall(len) => {
+                write!(f, "Report buffer of {len} bytes is too small")
+            }
+            AttestationReportError::FieldUnavailable { field, version } => {
+                write!(f, "Report version {version} does not define {field}")
+            }
+            AttestationReportError::ReservedNonZero { offset } => {
+                write!(f, "Reserved report byte at offset {offset:#x} is not zero")
+            }
+            #[cfg(feature = "base64")]
+            AttestationReportError::Base64(e) => write!(f, "Invalid base64 report: {e}"),
+            AttestationReportError::UnexpectedEndOfData { expected, actual } => write!(
+                f,
+                "Report truncated: expected {expected} bytes, got {actual}"
+            ),
+            AttestationReportError::ReportSizeExceedsBuffer {
+                report_size,
+                buffer_size,
+            } => write!(
+                f,
+                "Report size of {report_size} bytes exceeds the {buffer_size} byte response buffer"
+            ),
+            AttestationReportError::StatusError(status) => {
+                write!(f, "Firmware rejected the report request: {status}")
+            }
+            AttestationReportError::UnsupportedReportVersion(version) => {
+                write!(f, "Unsupported report version {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttestationReportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            AttestationReportError::BincodeError(e) => Some(e),
+            AttestationReportError::IoError(e) => Some(e),
+            AttestationReportError::AuthorKeyInconsistent { .. } => None,
+            #[cfg(feature = "snp")]
+            AttestationReportError::KeyTypeMismatch { .. } => None,
+            AttestationReportError::UnknownLeafCertificate => None,
+            AttestationReportError::BufferTooSmall(_) => None,
+            AttestationReportError::FieldUnavailable { .. } => None,
+            AttestationReportError::ReservedNonZero { .. } => None,
+            #[cfg(feature = "base64")]
+            AttestationReportError::Base64(e) => Some(e),
+            AttestationReportError::UnexpectedEndOfData { .. } => None,
+            AttestationReportError::ReportSizeExceedsBuffer { .. } => None,
+            AttestationReportError::StatusError(_) => None,
+            AttestationReportError::UnsupportedReportVersion(_) => None,
+        }
+    }
+}
+
+impl std::convert::From<bincode::ErrorKind> for AttestationReportError {
+    fn from(value: bincode::ErrorKind) -> Self {
+        Self::BincodeError(value)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl std::convert::From<base64::DecodeError> for AttestationReportError {
+    fn from(value: base64::DecodeError) -> Self {
+        Self::Base64(value)
+    }
+}
+
+impl std::convert::From<std::io::Error> for AttestationReportError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IoError(value)
+    }
+}
+
+/// Errors which may be encountered when calculating the guest measurement.
+#[derive(Debug)]
+pub enum MeasurementError {
+    /// TryFrom Slice Error handling
+    FromSliceError(TryFromSliceError),
+
+    /// UUID Error handling
+    UUIDError(uuid::Error),
+
+    /// Bincode Error Handling
+    BincodeError(bincode::ErrorKind),
+
+    /// File Error Handling
+    FileError(std::io::Error),
+
+    /// Vec from hex Error Handling
+    FromHexError(hex::FromHexError),
+
+    /// Guest Context Error Handling
+    GCTXError(GCTXError),
+
+    /// OVMF Error Handling
+    OVMFError(OVMFError),
+
+    /// SEV Hash Error Handling
+    SevHashError(SevHashError),
+
+    /// Id Block Error Handling
+    IdBlockError(IdBlockError),
+
+    /// Invalid VCPU provided
+    InvalidVcpuTypeError(String),
+
+    /// Invalid VMM Provided
+    InvalidVmmError(String),
+
+    /// Invalid SEV Mode provided
+    InvalidSevModeError(String),
+
+    /// OVMF doesn't support kernel measurement
+    InvalidOvmfKernelError,
+
+    /// OVMF is missing required section with kernel specified
+    MissingSection(String),
+
+    /// The measurement of an attestation report is not the expected one
+    Mismatch {
+        /// The expected measurement.
+        expected: [u8; 48],
+        /// The measurement found in the report.
+        actual: [u8; 48],
+    },
+}
+
+impl std::fmt::Display for MeasurementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MeasurementError::FromSliceError(e) => write!(f, "Error converting slice: {e}"),
+            MeasurementError::UUIDError(e) => write!(f, "UUID Error encountered: {e}"),
+            MeasurementError::BincodeError(e) => write!(f, "Bincode error encountered: {e}"),
+            MeasurementError::FileError(e) => write!(f, "Failed handling file: {e}"),
+            MeasurementError::FromHexError(e) => write!(f, "Converting hex to vector error: {e}"),
+            MeasurementError::GCTXError(e) => write!(f, "GCTX Error Encountered: {e}"),
+            MeasurementError::OVMFError(e) => write!(f, "OVMF Error Encountered: {e}"),
+            MeasurementError::SevHashError(e) => write!(f, "Sev hash Error Encountered: {e}"),
+            MeasurementError::IdBlockError(e) => write!(f, "Id Block Error Encountered: {e}"),
+            MeasurementError::InvalidVcpuTypeError(value) => {
+                write!(f, "Invalid VCPU type value provided: {value}")
+            }
+            MeasurementError::InvalidVmmError(value) => {
+                write!(f, "Invalid VMM type provided: {value}")
+            }
+            MeasurementError::InvalidSevModeError(value) => {
+                write!(f, "Invalid SEV mode provided: {value}")
+            }
+            MeasurementError::InvalidOvmfKernelError => write!(
+                f,
+                "Kernel specified but OVMF doesn't support kernel/initrd/cmdline measurement"
+            ),
+            MeasurementError::MissingSection(section) => write!(
+                f,
+                "Kernel specified but OVMF metadata doesn't include {section} section"
+            ),
+            MeasurementError::Mismatch { expected, actual } => write!(
+                f,
+                "Measurement mismatch: expected {}, found {}",
+                hex::encode(expected),
+                hex::encode(actual)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MeasurementError {}
+
+impl std::convert::From<TryFromSliceError> for MeasurementError {
+    fn from(value: TryFromSliceError) -> Self {
+        Self::FromSliceError(value)
+    }
+}
+
+impl std::convert::From<uuid::Error> for MeasurementError {
+    fn from(value: uuid::Error) -> Self {
+        Self::UUIDError(value)
+    }
+}
+
+impl std::convert::From<bincode::ErrorKind> for MeasurementError {
+    fn from(value: bincode::ErrorKind) -> Self {
+        Self::BincodeError(value)
     }
 }
 
@@ -1013,3 +2314,158 @@ impl std::convert::From<SevHashError> for MeasurementError {
         Self::SevHashError(value)
     }
 }
+
+/// Errors when running the guest side of
+/// [attest](crate::firmware::guest::attest::attest).
+#[cfg(feature = "snp")]
+#[derive(Debug)]
+pub enum AttestError {
+    /// The report request failed.
+    Firmware(UserApiError),
+
+    /// No random nonce could be generated.
+    Nonce(io::Error),
+
+    /// The report returned by the firmware could not be (de)serialized.
+    Report(AttestationReportError),
+}
+
+#[cfg(feature = "snp")]
+impl std::fmt::Display for AttestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AttestError::Firmware(e) => write!(f, "Report request failed: {e}"),
+            AttestError::Nonce(e) => write!(f, "Unable to generate a nonce: {e}"),
+            AttestError::Report(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "snp")]
+impl std::error::Error for AttestError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            AttestError::Firmware(e) => Some(e),
+            AttestError::Nonce(e) => Some(e),
+            AttestError::Report(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "snp")]
+impl std::convert::From<UserApiError> for AttestError {
+    fn from(value: UserApiError) -> Self {
+        Self::Firmware(value)
+    }
+}
+
+#[cfg(feature = "snp")]
+impl std::convert::From<AttestationReportError> for AttestError {
+    fn from(value: AttestationReportError) -> Self {
+        Self::Report(value)
+    }
+}
+
+/// Reasons an [AttestationBundle](crate::firmware::guest::attest::AttestationBundle)
+/// is rejected by
+/// [verify_bundle](crate::firmware::guest::attest::verify_bundle).
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+#[derive(Debug)]
+pub enum BundleError {
+    /// The raw report bytes do not parse.
+    Report(AttestationReportError),
+
+    /// The parsed report does not match the raw report bytes.
+    ReportMismatch,
+
+    /// The REPORT_DATA of the report is not the bundled nonce.
+    NonceMismatch,
+
+    /// The REPORT_DATA of the report is not the nonce the relying party
+    /// expects, e.g. because the report was replayed.
+    UnexpectedNonce,
+
+    /// The bundle holds no certificates to verify the report with.
+    MissingCertificates,
+
+    /// The certificate chain could not be built from the bundle.
+    Certificates(io::Error),
+
+    /// The chain does not end in one of AMD's root keys.
+    UntrustedArk,
+
+    /// The certificate chain or the report signature did not verify.
+    Signature(io::Error),
+
+    /// The guest policy of the report is not allowed.
+    Policy(PolicyViolation),
+
+    /// The reported TCB is below the minimum one, or has a component below
+    /// it.
+    TcbTooLow {
+        /// The minimum TCB accepted.
+        minimum: crate::firmware::host::TcbComponents,
+        /// The TCB reported by the report, decoded for its processor family.
+        reported: crate::firmware::host::TcbComponents,
+    },
+}
+
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+impl std::fmt::Display for BundleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BundleError::Report(e) => write!(f, "{e}"),
+            BundleError::ReportMismatch => {
+                write!(f, "Parsed report does not match the raw report bytes")
+            }
+            BundleError::NonceMismatch => write!(f, "Report data does not match the nonce"),
+            BundleError::UnexpectedNonce => {
+                write!(f, "Report data does not match the expected nonce")
+            }
+            BundleError::MissingCertificates => write!(f, "Bundle holds no certificates"),
+            BundleError::Certificates(e) => write!(f, "Invalid certificate chain: {e}"),
+            BundleError::UntrustedArk => write!(f, "Certificate chain does not end in an AMD ARK"),
+            BundleError::Signature(e) => write!(f, "Report verification failed: {e}"),
+            BundleError::Policy(e) => write!(f, "{e}"),
+            BundleError::TcbTooLow { minimum, reported } => {
+                let describe = |tcb: &crate::firmware::host::TcbComponents| {
+                    let fmc = tcb.fmc.map(|fmc| format!("FMC {fmc}, "));
+                    format!(
+                        "{}bootloader {}, TEE {}, SNP {}, microcode {}",
+                        fmc.unwrap_or_default(),
+                        tcb.bootloader,
+                        tcb.tee,
+                        tcb.snp,
+                        tcb.microcode
+                    )
+                };
+
+                write!(
+                    f,
+                    "Reported TCB ({}) does not meet the minimum ({})",
+                    describe(reported),
+                    describe(minimum)
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+impl std::error::Error for BundleError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BundleError::Report(e) => Some(e),
+            BundleError::Certificates(e) | BundleError::Signature(e) => Some(e),
+            BundleError::Policy(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+impl std::convert::From<PolicyViolation> for BundleError {
+    fn from(value: PolicyViolation) -> Self {
+        Self::Policy(value)
+    }
+}
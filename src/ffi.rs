@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A C FFI interface for parsing and verifying SEV-SNP attestation reports.
+//!
+//! The declarations are exported to `include/sev_snp.h` with `cbindgen`:
+//!
+//! `cbindgen --config cbindgen.toml --output include/sev_snp.h`
+//!
+//! Every function returns [SEV_SNP_OK] on success or one of the negative
+//! `SEV_SNP_ERR_*` codes, which [sev_snp_strerror] describes.
+
+use crate::firmware::guest::AttestationReport;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+use crate::certs::snp::{ca, Certificate, Chain, Verifiable};
+
+use std::{
+    convert::TryFrom,
+    os::raw::{c_char, c_int},
+    slice::from_raw_parts,
+};
+
+/// The call succeeded.
+pub const SEV_SNP_OK: c_int = 0;
+
+/// A required pointer argument was NULL.
+pub const SEV_SNP_ERR_NULL_POINTER: c_int = -1;
+
+/// The bytes could not be parsed as an attestation report.
+pub const SEV_SNP_ERR_INVALID_REPORT: c_int = -2;
+
+/// A certificate could not be parsed.
+pub const SEV_SNP_ERR_INVALID_CERTIFICATE: c_int = -3;
+
+/// The certificate chain or the report signature did not verify.
+pub const SEV_SNP_ERR_VERIFICATION: c_int = -4;
+
+/// An output buffer is too small for the requested field.
+pub const SEV_SNP_ERR_BUFFER_TOO_SMALL: c_int = -5;
+
+/// The library was built without a cryptographic backend.
+pub const SEV_SNP_ERR_UNSUPPORTED: c_int = -6;
+
+/// The length of the MEASUREMENT field.
+pub const SEV_SNP_MEASUREMENT_LEN: usize = 48;
+
+/// The length of the REPORT_DATA field.
+pub const SEV_SNP_REPORT_DATA_LEN: usize = 64;
+
+/// The length of the CHIP_ID field.
+pub const SEV_SNP_CHIP_ID_LEN: usize = 64;
+
+/// An opaque, parsed SEV-SNP attestation report.
+#[allow(non_camel_case_types)]
+pub struct sev_snp_report_t(AttestationReport);
+
+/// Describes an error code returned by this API.
+///
+/// The returned string is static and must not be freed.
+#[no_mangle]
+pub extern "C" fn sev_snp_strerror(code: c_int) -> *const c_char {
+    let msg: &'static [u8] = match code {
+        SEV_SNP_OK => b"success\0",
+        SEV_SNP_ERR_NULL_POINTER => b"null pointer argument\0",
+        SEV_SNP_ERR_INVALID_REPORT => b"invalid attestation report\0",
+        SEV_SNP_ERR_INVALID_CERTIFICATE => b"invalid certificate\0",
+        SEV_SNP_ERR_VERIFICATION => b"verification failed\0",
+        SEV_SNP_ERR_BUFFER_TOO_SMALL => b"output buffer too small\0",
+        SEV_SNP_ERR_UNSUPPORTED => b"not supported by this build\0",
+        _ => b"unknown error\0",
+    };
+
+    msg.as_ptr() as *const c_char
+}
+
+/// Parses `len` bytes at `bytes` as an attestation report.
+///
+/// On success, `*out` holds a report which must be released with
+/// [sev_snp_report_free].
+///
+/// # Safety
+///
+/// `bytes` must be valid for reads of `len` bytes and `out` must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_parse(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut sev_snp_report_t,
+) -> c_int {
+    if bytes.is_null() || out.is_null() {
+        return SEV_SNP_ERR_NULL_POINTER;
+    }
+
+    match AttestationReport::try_from(from_raw_parts(bytes, len)) {
+        Ok(report) => {
+            *out = Box::into_raw(Box::new(sev_snp_report_t(report)));
+
+            SEV_SNP_OK
+        }
+        Err(_) => SEV_SNP_ERR_INVALID_REPORT,
+    }
+}
+
+/// Releases a report returned by [sev_snp_report_parse]. NULL is ignored.
+///
+/// # Safety
+///
+/// `report` must be NULL or a pointer returned by [sev_snp_report_parse]
+/// which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_free(report: *mut sev_snp_report_t) {
+    if !report.is_null() {
+        drop(Box::from_raw(report));
+    }
+}
+
+/// Copies a report field into a caller-provided buffer.
+unsafe fn copy_field(field: &[u8], out: *mut u8, out_len: usize) -> c_int {
+    if out.is_null() {
+        return SEV_SNP_ERR_NULL_POINTER;
+    }
+
+    if out_len < field.len() {
+        return SEV_SNP_ERR_BUFFER_TOO_SMALL;
+    }
+
+    std::ptr::copy_nonoverlapping(field.as_ptr(), out, field.len());
+
+    SEV_SNP_OK
+}
+
+/// Copies the MEASUREMENT ([SEV_SNP_MEASUREMENT_LEN] bytes) into `out`.
+///
+/// # Safety
+///
+/// `report` must be a valid report and `out` must be valid for writes of
+/// `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_measurement(
+    report: *const sev_snp_report_t,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    match report.as_ref() {
+        Some(report) => copy_field(report.0.measurement.as_ref(), out, out_len),
+        None => SEV_SNP_ERR_NULL_POINTER,
+    }
+}
+
+/// Copies the REPORT_DATA ([SEV_SNP_REPORT_DATA_LEN] bytes) into `out`.
+///
+/// # Safety
+///
+/// `report` must be a valid report and `out` must be valid for writes of
+/// `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_report_data(
+    report: *const sev_snp_report_t,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    match report.as_ref() {
+        Some(report) => copy_field(&report.0.report_data, out, out_len),
+        None => SEV_SNP_ERR_NULL_POINTER,
+    }
+}
+
+/// Copies the CHIP_ID ([SEV_SNP_CHIP_ID_LEN] bytes) into `out`.
+///
+/// # Safety
+///
+/// `report` must be a valid report and `out` must be valid for writes of
+/// `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_chip_id(
+    report: *const sev_snp_report_t,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    match report.as_ref() {
+        Some(report) => copy_field(report.0.chip_id.as_ref(), out, out_len),
+        None => SEV_SNP_ERR_NULL_POINTER,
+    }
+}
+
+/// Verifies the report against a PEM-encoded ARK and ASK and a DER-encoded
+/// VCEK: the chain must verify and the VCEK must sign the report.
+///
+/// Returns [SEV_SNP_ERR_UNSUPPORTED] if the library was built without the
+/// `openssl` or `crypto_nossl` feature.
+///
+/// # Safety
+///
+/// `report` must be a valid report and each certificate pointer must be
+/// valid for reads of its length.
+#[no_mangle]
+pub unsafe extern "C" fn sev_snp_report_verify(
+    report: *const sev_snp_report_t,
+    ark_pem: *const u8,
+    ark_len: usize,
+    ask_pem: *const u8,
+    ask_len: usize,
+    vcek_der: *const u8,
+    vcek_len: usize,
+) -> c_int {
+    if report.is_null() || ark_pem.is_null() || ask_pem.is_null() || vcek_der.is_null() {
+        return SEV_SNP_ERR_NULL_POINTER;
+    }
+
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    {
+        let ark = from_raw_parts(ark_pem, ark_len);
+        let ask = from_raw_parts(ask_pem, ask_len);
+        let vcek = from_raw_parts(vcek_der, vcek_len);
+
+        let chain = match (ca::Chain::from_pem(ark, ask), Certificate::from_der(vcek)) {
+            (Ok(ca), Ok(vek)) => Chain { ca, vek },
+            _ => return SEV_SNP_ERR_INVALID_CERTIFICATE,
+        };
+
+        match (&chain, &(*report).0).verify() {
+            Ok(()) => SEV_SNP_OK,
+            Err(_) => SEV_SNP_ERR_VERIFICATION,
+        }
+    }
+
+    #[cfg(not(any(feature = "openssl", feature = "crypto_nossl")))]
+    {
+        let _ = (ark_len, ask_len, vcek_len);
+
+        SEV_SNP_ERR_UNSUPPORTED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::fixtures::milan_report_bytes;
+
+    use std::ffi::CStr;
+
+    #[test]
+    fn parse_and_accessors() {
+        let bytes = milan_report_bytes();
+        let expected = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let mut report: *mut sev_snp_report_t = std::ptr::null_mut();
+        let mut measurement = [0u8; SEV_SNP_MEASUREMENT_LEN];
+        let mut chip_id = [0u8; SEV_SNP_CHIP_ID_LEN];
+
+        unsafe {
+            assert_eq!(
+                sev_snp_report_parse(bytes.as_ptr(), bytes.len(), &mut report),
+                SEV_SNP_OK
+            );
+            assert_eq!(
+                sev_snp_report_measurement(report, measurement.as_mut_ptr(), measurement.len()),
+                SEV_SNP_OK
+            );
+            assert_eq!(
+                sev_snp_report_chip_id(report, chip_id.as_mut_ptr(), chip_id.len()),
+                SEV_SNP_OK
+            );
+            sev_snp_report_free(report);
+        }
+
+        assert_eq!(&measurement[..], expected.measurement.as_ref());
+        assert_eq!(&chip_id[..], expected.chip_id.as_ref());
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = milan_report_bytes();
+        let mut report: *mut sev_snp_report_t = std::ptr::null_mut();
+        let mut short = [0u8; 16];
+
+        unsafe {
+            assert_eq!(
+                sev_snp_report_parse(bytes.as_ptr(), 16, &mut report),
+                SEV_SNP_ERR_INVALID_REPORT
+            );
+            assert_eq!(
+                sev_snp_report_parse(std::ptr::null(), 0, &mut report),
+                SEV_SNP_ERR_NULL_POINTER
+            );
+
+            sev_snp_report_parse(bytes.as_ptr(), bytes.len(), &mut report);
+            assert_eq!(
+                sev_snp_report_report_data(report, short.as_mut_ptr(), short.len()),
+                SEV_SNP_ERR_BUFFER_TOO_SMALL
+            );
+            sev_snp_report_free(report);
+
+            let msg = CStr::from_ptr(sev_snp_strerror(SEV_SNP_ERR_BUFFER_TOO_SMALL));
+            assert_eq!(msg.to_str().unwrap(), "output buffer too small");
+        }
+    }
+}
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A one-call attestation flow: the guest requests a report bound to a
+//! nonce issued by the relying party and bundles it with its certificates
+//! for transport, and the relying party checks the bundle.
+//!
+//! # Example:
+//!
+//! ```ignore
+//! // In the guest, with the nonce received from the relying party.
+//! let mut fw = Firmware::open()?;
+//! let bundle = attest(&mut fw, AttestOptions::new(nonce))?;
+//! send(serde_json::to_vec(&bundle)?);
+//!
+//! // In the relying party.
+//! let bundle: AttestationBundle = serde_json::from_slice(&received)?;
+//! verify_bundle(&bundle, &nonce, &allowed_policy, &min_tcb)?;
+//! ```
+
+use super::*;
+
+use crate::error::AttestError;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+use crate::{
+    certs::snp::{builtin, Chain, Verifiable},
+    error::BundleError,
+    firmware::host::TcbComponents,
+};
+
+#[cfg(all(test, any(feature = "openssl", feature = "crypto_nossl")))]
+use crate::firmware::host::TcbVersion;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+use subtle::ConstantTimeEq;
+
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+
+use std::{convert::TryFrom, fs::File, io::Read};
+
+/// Where the REPORT_DATA of the requested report comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReportDataSource {
+    /// These exact bytes, e.g. a nonce issued by the relying party.
+    Explicit([u8; 64]),
+
+    /// 64 fresh random bytes, generated in the guest.
+    ///
+    /// These give no replay protection on their own: [verify_bundle] only
+    /// accepts the report if the relying party already expects the nonce.
+    RandomNonce,
+
+    /// The SHA-512 digest of a payload, e.g. a public key of the guest the
+    /// relying party should bind to the report.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    PayloadHash(Vec<u8>),
+}
+
+/// The options of an [attest] call.
+#[derive(Clone, Debug)]
+pub struct AttestOptions {
+    /// The source of the REPORT_DATA.
+    pub report_data: ReportDataSource,
+
+    /// The VMPL of the report, 0 if `None`.
+    pub vmpl: Option<u32>,
+
+    /// The guest message version, 1 if `None`.
+    pub message_version: Option<u8>,
+
+    /// Whether to request an extended report and bundle its certificates.
+    pub include_certificates: bool,
+}
+
+impl AttestOptions {
+    /// Options for a report carrying `nonce`, as issued by the relying party,
+    /// at VMPL 0 and with the certificates included.
+    pub fn new(nonce: [u8; 64]) -> Self {
+        Self {
+            report_data: ReportDataSource::Explicit(nonce),
+            vmpl: None,
+            message_version: None,
+            include_certificates: true,
+        }
+    }
+}
+
+/// An attestation report with everything a relying party needs to check
+/// it, ready to be serialized for transport.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AttestationBundle {
+    /// The report, as returned by the firmware.
+    #[serde(with = "serde_bytes")]
+    pub report_bytes: Vec<u8>,
+
+    /// The report, parsed.
+    pub report: AttestationReport,
+
+    /// The certificate table returned with an extended report; empty
+    /// unless requested.
+    pub certificates: Vec<CertTableEntry>,
+
+    /// The REPORT_DATA requested.
+    #[serde(with = "BigArray")]
+    pub nonce: [u8; 64],
+}
+
+/// Requests a report from `firmware` as described by `options`, and
+/// bundles it for transport.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(vmpl = ?options.vmpl, include_certificates = options.include_certificates)
+    )
+)]
+pub fn attest<F: GuestFirmware + ?Sized>(
+    firmware: &mut F,
+    options: AttestOptions,
+) -> Result<AttestationBundle, AttestError> {
+    let nonce = match options.report_data {
+        ReportDataSource::Explicit(data) => data,
+        ReportDataSource::RandomNonce => random_nonce()?,
+        #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+        ReportDataSource::PayloadHash(payload) => ReportData::from_hash_sha512(&payload),
+    };
+
+    let (report, report_bytes, certificates) = match options.include_certificates {
+        true => {
+            let (report_bytes, certificates) =
+                firmware.get_ext_report_raw(options.message_version, Some(nonce), options.vmpl)?;
+            let report = AttestationReport::try_from(report_bytes.as_slice())?;
+
+            (report, report_bytes, certificates.unwrap_or_default())
+        }
+        false => {
+            let report_bytes =
+                firmware.get_report_raw(options.message_version, Some(nonce), options.vmpl)?;
+            let report = AttestationReport::try_from(report_bytes.as_slice())?;
+
+            (report, report_bytes, vec![])
+        }
+    };
+
+    Ok(AttestationBundle {
+        report_bytes,
+        report,
+        certificates,
+        nonce,
+    })
+}
+
+fn random_nonce() -> Result<[u8; 64], AttestError> {
+    let mut nonce = [0u8; 64];
+
+    File::open("/dev/urandom")
+        .and_then(|mut urandom| urandom.read_exact(&mut nonce))
+        .map_err(AttestError::Nonce)?;
+
+    Ok(nonce)
+}
+
+/// Checks a bundle produced by [attest]:
+///
+/// 1. the parsed report matches the raw report bytes,
+/// 2. the REPORT_DATA is the bundled nonce,
+/// 3. the REPORT_DATA is `expected_nonce`, the nonce the relying party
+///    issued for this attestation, so that old reports cannot be replayed,
+/// 4. the bundled certificates (DER-encoded) chain up to one of AMD's
+///    builtin ARKs and their VCEK or VLEK signs the report,
+/// 5. the guest policy is allowed by `policy` (see
+///    [check_policy](AttestationReport::check_policy)),
+/// 6. no component of the reported TCB, decoded for the processor family
+///    of the report, is below `min_tcb`. Give the FMC SVN in `min_tcb` for
+///    Turin and later, and only then.
+///
+/// Checking the measurement is left to the caller.
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            report_len = bundle.report_bytes.len(),
+            certificates = bundle.certificates.len()
+        )
+    )
+)]
+pub fn verify_bundle(
+    bundle: &AttestationBundle,
+    expected_nonce: &[u8; 64],
+    policy: &GuestPolicy,
+    min_tcb: &TcbComponents,
+) -> Result<(), BundleError> {
+    let report =
+        AttestationReport::try_from(bundle.report_bytes.as_slice()).map_err(BundleError::Report)?;
+
+    if report != bundle.report {
+        return Err(BundleError::ReportMismatch);
+    }
+
+    if !bool::from(report.report_data.ct_eq(&bundle.nonce)) {
+        return Err(BundleError::NonceMismatch);
+    }
+
+    if !bool::from(report.report_data.ct_eq(expected_nonce)) {
+        return Err(BundleError::UnexpectedNonce);
+    }
+
+    if bundle.certificates.is_empty() {
+        return Err(BundleError::MissingCertificates);
+    }
+
+    let chain = Chain::from_cert_table_der(bundle.certificates.clone())
+        .map_err(BundleError::Certificates)?;
+
+    if !builtin::is_builtin_ark(&chain.ca.ark) {
+        return Err(BundleError::UntrustedArk);
+    }
+
+    (&chain, &report).verify().map_err(BundleError::Signature)?;
+
+    report.check_policy(policy)?;
+
+    check_min_tcb(&report, min_tcb)
+}
+
+/// Checks every component of the REPORTED_TCB, decoded for the processor
+/// family of the report, against `min_tcb`.
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+fn check_min_tcb(report: &AttestationReport, min_tcb: &TcbComponents) -> Result<(), BundleError> {
+    let reported = report.into_latest().reported_tcb();
+
+    match min_tcb.fits_within(&reported) {
+        true => Ok(()),
+        false => Err(BundleError::TcbTooLow {
+            minimum: *min_tcb,
+            reported,
+        }),
+    }
+}
+
+#[cfg(all(test, any(feature = "openssl", feature = "crypto_nossl")))]
+mod tests {
+    use super::*;
+
+    fn turin_report(reported_tcb: [u8; 8]) -> AttestationReport {
+        let mut report = AttestationReport::default();
+        report.version = 3;
+        report.reported_tcb = TcbVersion::from_le_bytes(reported_tcb);
+        report.set_v3_fields(0x1a, 0x02, 0x00, true);
+        report
+    }
+
+    #[test]
+    fn test_check_min_tcb_turin() {
+        let min_tcb = TcbComponents {
+            fmc: Some(1),
+            bootloader: 2,
+            tee: 3,
+            snp: 4,
+            microcode: 5,
+        };
+
+        let report = turin_report([1, 2, 3, 4, 0, 0, 0, 5]);
+        assert!(check_min_tcb(&report, &min_tcb).is_ok());
+
+        // Outdated SNP firmware, all else equal. The SNP SVN is in a byte
+        // TcbVersion reserves.
+        let report = turin_report([1, 2, 3, 3, 0, 0, 0, 5]);
+        assert!(matches!(
+            check_min_tcb(&report, &min_tcb),
+            Err(BundleError::TcbTooLow { reported, .. }) if reported.snp == 3
+        ));
+
+        // A Milan minimum does not apply to a Turin report.
+        let report = turin_report([9, 9, 9, 9, 0, 0, 0, 9]);
+        let milan_min = TcbComponents::from(TcbVersion::new(2, 3, 4, 5));
+        assert!(matches!(
+            check_min_tcb(&report, &milan_min),
+            Err(BundleError::TcbTooLow { .. })
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay protection for attestation reports: the relying party issues a
+//! [Challenge], the guest binds it into the REPORT_DATA of its report, and
+//! the relying party checks the binding and the age of the challenge.
+//!
+//! The REPORT_DATA of a challenge is its 32-byte nonce followed by the
+//! SHA-256 digest of its creation time (seconds since the Unix epoch, as a
+//! little-endian `u64`) concatenated with its context:
+//!
+//! ```text
+//! REPORT_DATA = nonce || SHA-256(issued_at || context)
+//! ```
+//!
+//! # Example:
+//!
+//! ```ignore
+//! // In the relying party.
+//! let challenge = Challenge::new(&mut File::open("/dev/urandom")?, b"session-42")?;
+//! send(serde_json::to_vec(&challenge)?);
+//!
+//! // In the guest.
+//! let challenge: Challenge = serde_json::from_slice(&received)?;
+//! let report = fw.get_report(None, Some(challenge.to_report_data()), None)?;
+//!
+//! // Back in the relying party, once the report signature is verified.
+//! challenge.verify_in(&report, Duration::from_secs(60))?;
+//! ```
+
+use super::{AttestationReport, ReportData};
+
+use crate::error::FreshnessError;
+
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+use std::{
+    io::Read,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A nonce issued by a relying party, with the time it was issued and the
+/// context it was issued for.
+///
+/// The serialized form, with the field names below, is stable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Challenge {
+    /// Random bytes, unique to this challenge.
+    pub nonce: [u8; 32],
+
+    /// When the challenge was issued, in seconds since the Unix epoch.
+    pub issued_at: u64,
+
+    /// Data of the relying party's choosing, e.g. a session identifier.
+    #[serde(with = "serde_bytes")]
+    pub context: Vec<u8>,
+}
+
+impl Challenge {
+    /// Issues a challenge for `context`, with a nonce read from `rng`
+    /// (e.g. `/dev/urandom`) and the current time.
+    pub fn new<R: Read>(rng: &mut R, context: &[u8]) -> Result<Self, FreshnessError> {
+        let mut nonce = [0u8; 32];
+        rng.read_exact(&mut nonce).map_err(FreshnessError::Rng)?;
+
+        Ok(Self {
+            nonce,
+            issued_at: unix_time(SystemTime::now())?,
+            context: context.to_vec(),
+        })
+    }
+
+    /// The REPORT_DATA binding this challenge, to be requested by the
+    /// guest.
+    pub fn to_report_data(&self) -> [u8; 64] {
+        let mut bound = self.issued_at.to_le_bytes().to_vec();
+        bound.extend_from_slice(&self.context);
+
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(&self.nonce);
+        data[32..].copy_from_slice(&ReportData::from_hash_sha256_padded(&bound)[..32]);
+
+        data
+    }
+
+    /// Checks that `report` binds this challenge and that the challenge was
+    /// issued no more than `max_age` ago.
+    ///
+    /// This does not verify the report signature, which must be checked
+    /// separately.
+    pub fn verify_in(
+        &self,
+        report: &AttestationReport,
+        max_age: Duration,
+    ) -> Result<(), FreshnessError> {
+        self.verify_in_at(report, max_age, SystemTime::now())
+    }
+
+    /// Like [verify_in](Self::verify_in), with the current time given by the
+    /// caller.
+    pub fn verify_in_at(
+        &self,
+        report: &AttestationReport,
+        max_age: Duration,
+        now: SystemTime,
+    ) -> Result<(), FreshnessError> {
+        if !bool::from(report.report_data.ct_eq(&self.to_report_data())) {
+            return Err(FreshnessError::ReportDataMismatch);
+        }
+
+        let now = unix_time(now)?;
+
+        let age = match now.checked_sub(self.issued_at) {
+            Some(age) => Duration::from_secs(age),
+            None => return Err(FreshnessError::IssuedInFuture),
+        };
+
+        match age <= max_age {
+            true => Ok(()),
+            false => Err(FreshnessError::Expired { age, max_age }),
+        }
+    }
+}
+
+fn unix_time(time: SystemTime) -> Result<u64, FreshnessError> {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .map_err(|_| FreshnessError::IssuedInFuture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::firmware::guest::testing::fake_v3_report;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            nonce: [0x11; 32],
+            issued_at: 1_700_000_000,
+            context: b"session-42".to_vec(),
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn report_for(challenge: &Challenge) -> AttestationReport {
+        let mut report = fake_v3_report();
+        report.report_data = challenge.to_report_data();
+        report
+    }
+
+    #[test]
+    fn report_data_layout() {
+        let data = challenge().to_report_data();
+
+        let mut bound = 1_700_000_000u64.to_le_bytes().to_vec();
+        bound.extend_from_slice(b"session-42");
+
+        assert_eq!(&data[..32], &[0x11; 32]);
+        assert_eq!(
+            &data[32..],
+            &ReportData::from_hash_sha256_padded(&bound)[..32]
+        );
+        assert_eq!(
+            hex::encode(&data[32..]),
+            "aac7ece00363e797245c7b8849f0cdbef53b4036f4d4540ededc7cd3cef00efc"
+        );
+    }
+
+    #[test]
+    fn verify_in() {
+        let challenge = challenge();
+        let report = report_for(&challenge);
+        let max_age = Duration::from_secs(60);
+
+        challenge
+            .verify_in_at(&report, max_age, at(1_700_000_030))
+            .unwrap();
+        challenge
+            .verify_in_at(&report, max_age, at(1_700_000_060))
+            .unwrap();
+
+        assert!(matches!(
+            challenge.verify_in_at(&report, max_age, at(1_700_000_061)),
+            Err(FreshnessError::Expired { age, .. }) if age == Duration::from_secs(61)
+        ));
+        assert!(matches!(
+            challenge.verify_in_at(&report, max_age, at(1_699_999_999)),
+            Err(FreshnessError::IssuedInFuture)
+        ));
+    }
+
+    #[test]
+    fn verify_in_wrong_binding() {
+        let challenge = challenge();
+        let now = at(1_700_000_000);
+        let max_age = Duration::from_secs(60);
+
+        let mut other = challenge.clone();
+        other.nonce[0] ^= 1;
+        assert!(matches!(
+            challenge.verify_in_at(&report_for(&other), max_age, now),
+            Err(FreshnessError::ReportDataMismatch)
+        ));
+
+        // The timestamp and context are bound too, so they cannot be
+        // changed to make a stale report look fresh.
+        let mut other = challenge.clone();
+        other.issued_at += 1;
+        assert!(matches!(
+            challenge.verify_in_at(&report_for(&other), max_age, now),
+            Err(FreshnessError::ReportDataMismatch)
+        ));
+
+        let mut other = challenge.clone();
+        other.context = b"session-43".to_vec();
+        assert!(matches!(
+            challenge.verify_in_at(&report_for(&other), max_age, now),
+            Err(FreshnessError::ReportDataMismatch)
+        ));
+    }
+
+    #[test]
+    fn new_and_serde() {
+        let mut rng: &[u8] = &[0x5a; 32];
+        let challenge = Challenge::new(&mut rng, b"ctx").unwrap();
+
+        assert_eq!(challenge.nonce, [0x5a; 32]);
+        assert!(challenge.issued_at > 1_700_000_000);
+
+        let mut short: &[u8] = &[0; 16];
+        assert!(matches!(
+            Challenge::new(&mut short, b"ctx"),
+            Err(FreshnessError::Rng(_))
+        ));
+
+        let bytes = bincode::serialize(&challenge).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Challenge>(&bytes).unwrap(),
+            challenge
+        );
+    }
+}
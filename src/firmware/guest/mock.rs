@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A stand-in for the SEV-SNP guest device, for testing attestation flows
+//! off-hardware.
+
+use super::*;
+
+use std::collections::VecDeque;
+
+/// The highest VMPL accepted in a report request.
+const MAX_VMPL: u32 = 3;
+
+/// A [GuestFirmware] returning caller-seeded reports and keys.
+///
+/// Reports are the seeded one with REPORT_DATA and VMPL taken from the
+/// request, so their signature will not verify. Errors queued with
+/// [push_error](Self::push_error) are returned, in order, by the next
+/// requests of any kind.
+///
+/// # Example:
+///
+/// ```ignore
+/// let mut fw = MockFirmware::new(report);
+///
+/// // The first request is throttled by the hypervisor.
+/// fw.push_error(VmmError::RateLimitRetryRequest.into());
+///
+/// assert!(fw.get_report(None, None, None).is_err());
+/// assert!(fw.get_report(None, None, None).is_ok());
+/// ```
+#[derive(Debug, Default)]
+pub struct MockFirmware {
+    report: AttestationReport,
+    certificates: Option<Vec<CertTableEntry>>,
+    derived_key: [u8; 32],
+    errors: VecDeque<UserApiError>,
+}
+
+impl MockFirmware {
+    /// Creates a mock returning `report` for every report request.
+    pub fn new(report: AttestationReport) -> Self {
+        Self {
+            report,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the certificates returned along with extended reports.
+    pub fn with_certificates(mut self, certificates: Vec<CertTableEntry>) -> Self {
+        self.certificates = Some(certificates);
+        self
+    }
+
+    /// Sets the key returned for every derived key request.
+    pub fn with_derived_key(mut self, key: [u8; 32]) -> Self {
+        self.derived_key = key;
+        self
+    }
+
+    /// Queues an error to be returned by the next request.
+    pub fn push_error(&mut self, error: UserApiError) {
+        self.errors.push_back(error);
+    }
+
+    fn next_error(&mut self) -> Result<(), UserApiError> {
+        match self.errors.pop_front() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn seeded_report(
+        &mut self,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError> {
+        self.next_error()?;
+
+        let vmpl = vmpl.unwrap_or(0);
+
+        if vmpl > MAX_VMPL {
+            return Err(UserApiError::VmplError);
+        }
+
+        let mut report = self.report;
+        report.report_data = data.unwrap_or([0; 64]);
+        report.vmpl = vmpl;
+
+        Ok(report)
+    }
+}
+
+impl GuestFirmware for MockFirmware {
+    fn get_report(
+        &mut self,
+        _message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError> {
+        self.seeded_report(data, vmpl)
+    }
+
+    fn get_ext_report(
+        &mut self,
+        _message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+        let report = self.seeded_report(data, vmpl)?;
+
+        Ok((report, self.certificates.clone()))
+    }
+
+    fn get_derived_key(
+        &mut self,
+        _message_version: Option<u8>,
+        derived_key_request: DerivedKey,
+    ) -> Result<SecretBytes<32>, UserApiError> {
+        derived_key_request.check_launch_mit_vector()?;
+        self.next_error()?;
+
+        Ok(self.derived_key.into())
+    }
+}
@@ -10,16 +10,37 @@ mod types;
 
 pub use types::*;
 
+pub mod attest;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub mod challenge;
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+pub mod seal;
+
+pub mod verify;
+
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+pub mod nonblocking;
+
+#[cfg(feature = "tokens")]
+pub mod tokens;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "mock")]
+mod mock;
+
+#[cfg(feature = "mock")]
+pub use mock::*;
+
+use crate::{error::UserApiError, firmware::host::CertTableEntry};
+
 #[cfg(target_os = "linux")]
 use crate::{
     error::*,
-    firmware::{
-        host::CertTableEntry,
-        linux::{
-            guest::{ioctl::*, types::*},
-            host as HostFFI,
-        },
-    },
+    firmware::linux::guest::{ioctl::*, types::*},
 };
 
 #[cfg(target_os = "linux")]
@@ -48,6 +69,138 @@ use std::fs::{File, OpenOptions};
 //     Ok(())
 // }
 
+/// Parses the report of a response, attaching the VMPL explicitly
+/// requested, if any, to a failed status.
+#[cfg(target_os = "linux")]
+fn parse_report(
+    response: &ReportRsp,
+    vmpl: Option<u32>,
+) -> Result<AttestationReport, UserApiError> {
+    response.parse_report().map_err(|e| match e {
+        AttestationReportError::StatusError(status) => {
+            UserApiError::FirmwareStatus { status, vmpl }
+        }
+        e => e.into(),
+    })
+}
+
+/// How many times an extended report request is issued before giving up on
+/// a hypervisor which keeps asking for a larger certificate buffer.
+#[cfg(target_os = "linux")]
+const EXT_REPORT_MAX_ATTEMPTS: usize = 4;
+
+/// Issues an extended report request through `ioctl`, growing the
+/// certificate buffer whenever the hypervisor reports it as too small.
+///
+/// `ioctl` returns the outcome of the call along with the raw `fw_err`
+/// value. On [InvalidCertificatePageLength](VmmError::InvalidCertificatePageLength)
+/// the hypervisor leaves the required length in `certs_len`, and the request
+/// is retried with a fresh, zeroed buffer of that size.
+#[cfg(target_os = "linux")]
+fn request_with_cert_buffer<F>(
+    request: &mut ExtReportReq,
+    mut ioctl: F,
+) -> Result<Vec<Page>, UserApiError>
+where
+    F: FnMut(&mut ExtReportReq) -> (Result<(), UserApiError>, u64),
+{
+    let mut page_count: usize = 1;
+
+    for _ in 0..EXT_REPORT_MAX_ATTEMPTS {
+        let (sized_request, pages) = request.with_cert_buffer(page_count)?;
+        *request = sized_request;
+
+        let (result, fw_err) = ioctl(request);
+
+        if fw_err == 0 {
+            result?;
+            return Ok(pages);
+        }
+
+        match fw_err.into() {
+            VmmError::InvalidCertificatePageLength => {
+                let required = (request.certs_len as usize + PAGE_SIZE - 1) / PAGE_SIZE;
+
+                if required <= page_count {
+                    return Err(VmmError::InvalidCertificatePageLength.into());
+                }
+
+                page_count = required;
+            }
+            VmmError::RateLimitRetryRequest => return Err(VmmError::RateLimitRetryRequest.into()),
+            _ => {
+                result?;
+
+                // This shouldn't be possible, but if it happens, throw an error.
+                return Err(UserApiError::FirmwareError(Error::InvalidConfig));
+            }
+        }
+    }
+
+    Err(UserApiError::CertBufferResizeLimit)
+}
+
+/// The guest requests served by the AMD Secure Processor.
+///
+/// Implemented by [Firmware] and, with the `mock` feature, by
+/// `MockFirmware`, so that attestation flows can be exercised without
+/// `/dev/sev-guest`.
+pub trait GuestFirmware {
+    /// See [Firmware::get_report].
+    fn get_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError>;
+
+    /// See [Firmware::get_report_raw].
+    ///
+    /// Defaults to the serialized [get_report](Self::get_report) result.
+    fn get_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<Vec<u8>, UserApiError> {
+        let report = self.get_report(message_version, data, vmpl)?;
+
+        bincode::serialize(&report).map_err(|_| UserApiError::Unknown)
+    }
+
+    /// See [Firmware::get_ext_report].
+    fn get_ext_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError>;
+
+    /// See [Firmware::get_ext_report_raw].
+    ///
+    /// Defaults to the serialized [get_ext_report](Self::get_ext_report)
+    /// result.
+    fn get_ext_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(Vec<u8>, Option<Vec<CertTableEntry>>), UserApiError> {
+        let (report, certificates) = self.get_ext_report(message_version, data, vmpl)?;
+
+        let report = bincode::serialize(&report).map_err(|_| UserApiError::Unknown)?;
+
+        Ok((report, certificates))
+    }
+
+    /// See [Firmware::get_derived_key].
+    fn get_derived_key(
+        &mut self,
+        message_version: Option<u8>,
+        derived_key_request: DerivedKey,
+    ) -> Result<SecretBytes<32>, UserApiError>;
+}
+
 /// A handle to the SEV-SNP guest device.
 #[cfg(target_os = "linux")]
 pub struct Firmware(File);
@@ -68,7 +221,7 @@ impl Firmware {
     }
 
     /// Requests an attestation report from the AMD Secure Processor. The `message_version` will default
-    /// to `1` if `None` is specified.
+    /// to `1` if `None` is specified, and the `vmpl` to `0`.
     ///
     /// # Example:
     ///
@@ -99,7 +252,55 @@ impl Firmware {
         data: Option<[u8; 64]>,
         vmpl: Option<u32>,
     ) -> Result<AttestationReport, UserApiError> {
-        let mut input = ReportReq::new(data, vmpl)?;
+        let input = ReportReq::new(data, vmpl)?;
+        let response = self.request_report(message_version, input, vmpl)?;
+
+        parse_report(&response, vmpl)
+    }
+
+    /// Requests an attestation report like [get_report](Self::get_report),
+    /// but returns the bytes exactly as produced by the firmware, of the
+    /// size it reported.
+    ///
+    /// Use this to forward the report to a verifier: re-serializing a
+    /// parsed report may not reproduce the signed bytes. The report can be
+    /// parsed from these bytes with `AttestationReport::try_from`.
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let mut fw: Firmware = Firmware::open().unwrap();
+    ///
+    /// let raw: Vec<u8> = fw.get_report_raw(None, Some(unique_data), None).unwrap();
+    /// let attestation_report = AttestationReport::try_from(raw.as_slice()).unwrap();
+    /// ```
+    pub fn get_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<Vec<u8>, UserApiError> {
+        let input = ReportReq::new(data, vmpl)?;
+        let response = self.request_report(message_version, input, vmpl)?;
+
+        Ok(response.report_bytes()?.to_vec())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_GET_REPORT", ?message_version, ?vmpl)
+        )
+    )]
+    fn request_report(
+        &mut self,
+        message_version: Option<u8>,
+        mut input: ReportReq,
+        vmpl: Option<u32>,
+    ) -> Result<ReportRsp, UserApiError> {
         let mut response = ReportRsp::default();
 
         let mut request: GuestRequest<ReportReq, ReportRsp> =
@@ -110,109 +311,185 @@ impl Firmware {
         // Disabled until upstream Linux kernel is patched.
         // check_fw_err(request.fw_err.into())?;
 
-        Ok(response.report)
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = response.status,
+            report_size = response.report_size,
+            "firmware response"
+        );
+
+        // The ioctl succeeds even when the firmware rejects the request.
+        response.check_status(vmpl)?;
+
+        Ok(response)
+    }
+
+    /// Request a standard attestation report whose report data is the SHA-512
+    /// digest of `nonce`, allowing challenges of arbitrary length to be bound
+    /// to the report.
+    ///
+    /// Requires either the `openssl` or the `crypto_nossl` feature.
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let mut fw: Firmware = Firmware::open().unwrap();
+    ///
+    /// let attestation_report: AttestationReport = fw.get_report_with_nonce(None, b"challenge", None).unwrap();
+    /// ```
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    pub fn get_report_with_nonce(
+        &mut self,
+        message_version: Option<u8>,
+        nonce: &[u8],
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError> {
+        let mut input = ReportReq::with_nonce(nonce)?;
+
+        if let Some(vmpl) = vmpl {
+            input.set_vmpl(vmpl)?;
+        }
+
+        let response = self.request_report(message_version, input, vmpl)?;
+
+        parse_report(&response, vmpl)
     }
 
     /// Request an extended attestation report from the AMD Secure Processor.
     /// The `message_version` will default to `1` if `None` is specified.
     ///
     /// Behaves the same as [get_report](crate::firmware::guest::Firmware::get_report).
+    ///
+    /// The certificate buffer is grown and the request retried whenever the
+    /// hypervisor reports it as too small, a bounded number of times.
     pub fn get_ext_report(
         &mut self,
         message_version: Option<u8>,
         data: Option<[u8; 64]>,
         vmpl: Option<u32>,
     ) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+        let (report_response, certificates) =
+            self.request_ext_report(message_version, data, vmpl)?;
+
+        let report = parse_report(&report_response, vmpl)?;
+
+        // Return both the Attestation Report, as well as the Cert Table.
+        Ok((report, certificates))
+    }
+
+    /// Requests an extended attestation report like
+    /// [get_ext_report](Self::get_ext_report), but returns the report bytes
+    /// exactly as produced by the firmware, as
+    /// [get_report_raw](Self::get_report_raw) does.
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let mut fw: Firmware = Firmware::open().unwrap();
+    ///
+    /// let (raw, certificates) = fw.get_ext_report_raw(None, Some(unique_data), None).unwrap();
+    /// let attestation_report = AttestationReport::try_from(raw.as_slice()).unwrap();
+    /// ```
+    pub fn get_ext_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(Vec<u8>, Option<Vec<CertTableEntry>>), UserApiError> {
+        let (report_response, certificates) =
+            self.request_ext_report(message_version, data, vmpl)?;
+
+        report_response.check_status(vmpl)?;
+
+        Ok((report_response.report_bytes()?.to_vec(), certificates))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_GET_EXT_REPORT", ?message_version, ?vmpl)
+        )
+    )]
+    fn request_ext_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(ReportRsp, Option<Vec<CertTableEntry>>), UserApiError> {
         let report_request = ReportReq::new(data, vmpl)?;
 
         let mut report_response = ReportRsp::default();
 
-        // Define a buffer to store the certificates in.
-        let mut certificate_bytes: Vec<u8>;
-
-        // Due to the complex buffer allocation, we will take the ReportReq
-        // provided by the caller, and create an extended report request object
-        // for them.
         let mut ext_report_request = ExtReportReq::new(&report_request);
 
-        // Construct the object needed to perform the IOCTL request.
-        // *NOTE:* This is __important__ because a fw_err value which matches
-        // [InvalidCertificatePageLength](crate::error::VmmError::InvalidCertificatePageLength) will indicate the buffer was not large
-        // enough.
-        let mut guest_request: GuestRequest<ExtReportReq, ReportRsp> = GuestRequest::new(
-            message_version,
-            &mut ext_report_request,
-            &mut report_response,
-        );
+        let pages = request_with_cert_buffer(&mut ext_report_request, |request| {
+            let mut guest_request: GuestRequest<ExtReportReq, ReportRsp> =
+                GuestRequest::new(message_version, request, &mut report_response);
 
-        // KEEP for Kernels before 47894e0f (5.19), as userspace broke at that hash.
-        if let Err(ioctl_error) = SNP_GET_EXT_REPORT.ioctl(&mut self.0, &mut guest_request) {
-            match guest_request.fw_err.into() {
-                VmmError::InvalidCertificatePageLength => (),
-                VmmError::RateLimitRetryRequest => {
-                    return Err(VmmError::RateLimitRetryRequest.into())
-                }
-                _ => return Err(ioctl_error.into()),
-            }
+            let result = SNP_GET_EXT_REPORT
+                .ioctl(&mut self.0, &mut guest_request)
+                .map(|_| ())
+                .map_err(UserApiError::from);
 
-            // Eventually the code below will be moved back into this scope.
-        }
+            (result, guest_request.fw_err)
+        })?;
 
-        // The kernel patch by pgonda@google.com in kernel hash 47894e0f
-        // changed the ioctl return to succeed instead of returning an
-        // error when encountering an invalid certificate length. This was
-        // done to keep the cryptography safe, so we will now just check
-        // the guest_request.fw_err for a new value.
-        //
-        // Check to see if the buffer needs to be resized. If it does, the
-        // we need to resize the buffer to the correct size, and
-        // re-request for the certificates.
-        if VmmError::InvalidCertificatePageLength == guest_request.fw_err.into() {
-            certificate_bytes = vec![0u8; ext_report_request.certs_len as usize];
-            ext_report_request.certs_address = certificate_bytes.as_mut_ptr() as u64;
-            let mut guest_request_retry: GuestRequest<ExtReportReq, ReportRsp> = GuestRequest::new(
-                message_version,
-                &mut ext_report_request,
-                &mut report_response,
-            );
-            SNP_GET_EXT_REPORT.ioctl(&mut self.0, &mut guest_request_retry)?;
-        } else if guest_request.fw_err != 0 {
-            // This shouldn't be possible, but if it happens, throw an error.
-            return Err(UserApiError::FirmwareError(Error::InvalidConfig));
-        }
+        let table: Vec<u8> = pages.iter().flat_map(|page| page.0).collect();
 
-        if ext_report_request.certs_len == 0 {
-            return Ok((report_response.report, None));
-        }
+        let mut certificates = CertTableEntry::parse_cert_table(&table)?;
+        certificates.sort();
 
-        let mut certificates: Vec<CertTableEntry>;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = report_response.status,
+            report_size = report_response.report_size,
+            certificates = certificates.len(),
+            "firmware response"
+        );
 
-        unsafe {
-            let entries = (ext_report_request.certs_address as *mut HostFFI::types::CertTableEntry)
-                .as_mut()
-                .ok_or(CertError::EmptyCertBuffer)?;
-            certificates = HostFFI::types::CertTableEntry::parse_table(entries)?;
-            certificates.sort();
+        if certificates.is_empty() {
+            return Ok((report_response, None));
         }
 
-        // Return both the Attestation Report, as well as the Cert Table.
-        Ok((report_response.report, Some(certificates)))
+        Ok((report_response, Some(certificates)))
     }
 
     /// Fetches a derived key from the AMD Secure Processor. The `message_version` will default to `1` if `None` is specified.
     ///
+    /// Fails with [UserApiError::LaunchMitVectorUnchecked] if the request
+    /// carries a launch mitigation vector but did not go through
+    /// [DerivedKey::checked], as older kernels silently drop the vector.
+    ///
     /// # Example:
     /// ```ignore
-    /// let request: DerivedKey = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0);
+    /// let request: DerivedKey = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0, None);
     ///
     /// let mut fw: Firmware = Firmware::open().unwrap();
-    /// let derived_key: DerivedKeyRsp = fw.get_derived_key(None, request).unwrap();
+    /// let derived_key: SecretBytes<32> = fw.get_derived_key(None, request).unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(
+                request = "SNP_GET_DERIVED_KEY",
+                ?message_version,
+                vmpl = derived_key_request.vmpl
+            )
+        )
+    )]
     pub fn get_derived_key(
         &mut self,
         message_version: Option<u8>,
         derived_key_request: DerivedKey,
-    ) -> Result<[u8; 32], UserApiError> {
+    ) -> Result<SecretBytes<32>, UserApiError> {
+        derived_key_request.check_launch_mit_vector()?;
+
         let mut ffi_derived_key_request: DerivedKeyReq = derived_key_request.into();
         let mut ffi_derived_key_response: DerivedKeyRsp = Default::default();
 
@@ -227,6 +504,123 @@ impl Firmware {
         // Disabled until upstream Linux kernel is patched.
         // check_fw_err(request.fw_err.into())?;
 
-        Ok(ffi_derived_key_response.key)
+        // Only the status: the key itself must never be logged.
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = ffi_derived_key_response.status,
+            "firmware response"
+        );
+
+        ffi_derived_key_response.check_status(&derived_key_request)?;
+
+        let key = ffi_derived_key_response
+            .derived_key()
+            .ok_or(UserApiError::Unknown)?;
+
+        Ok((*key).into())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl GuestFirmware for Firmware {
+    fn get_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError> {
+        Firmware::get_report(self, message_version, data, vmpl)
+    }
+
+    fn get_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<Vec<u8>, UserApiError> {
+        Firmware::get_report_raw(self, message_version, data, vmpl)
+    }
+
+    fn get_ext_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+        Firmware::get_ext_report(self, message_version, data, vmpl)
+    }
+
+    fn get_ext_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(Vec<u8>, Option<Vec<CertTableEntry>>), UserApiError> {
+        Firmware::get_ext_report_raw(self, message_version, data, vmpl)
+    }
+
+    fn get_derived_key(
+        &mut self,
+        message_version: Option<u8>,
+        derived_key_request: DerivedKey,
+    ) -> Result<SecretBytes<32>, UserApiError> {
+        Firmware::get_derived_key(self, message_version, derived_key_request)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    const CERT_PAGE_LENGTH_ERR: u64 = 1 << 32;
+
+    #[test]
+    fn test_request_with_cert_buffer_resize() {
+        let mut request = ExtReportReq::new(&ReportReq::default());
+        let mut calls = 0;
+
+        let pages = request_with_cert_buffer(&mut request, |request| {
+            calls += 1;
+
+            assert_eq!(request.certs_address % PAGE_SIZE as u64, 0);
+
+            if calls == 1 {
+                assert_eq!(request.certs_len, 4096);
+                request.certs_len = 8192;
+                return (Ok(()), CERT_PAGE_LENGTH_ERR);
+            }
+
+            assert_eq!(request.certs_len, 8192);
+            (Ok(()), 0)
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        assert_eq!(pages.len(), 2);
+        assert!(pages.iter().all(|page| page.0.iter().all(|b| *b == 0)));
+    }
+
+    #[test]
+    fn test_request_with_cert_buffer_limit() {
+        let mut request = ExtReportReq::new(&ReportReq::default());
+
+        let result = request_with_cert_buffer(&mut request, |request| {
+            request.certs_len += PAGE_SIZE as u32;
+            (Ok(()), CERT_PAGE_LENGTH_ERR)
+        });
+
+        assert!(matches!(result, Err(UserApiError::CertBufferResizeLimit)));
+    }
+
+    #[test]
+    fn test_request_with_cert_buffer_rate_limit() {
+        let mut request = ExtReportReq::new(&ReportReq::default());
+
+        let result = request_with_cert_buffer(&mut request, |_| (Ok(()), 2 << 32));
+
+        assert!(matches!(
+            result,
+            Err(UserApiError::VmmError(VmmError::RateLimitRetryRequest))
+        ));
     }
 }
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Guest requests for async runtimes.
+//!
+//! The `/dev/sev-guest` ioctls block until the AMD Secure Processor
+//! answers, which may take a while when the hypervisor throttles guest
+//! requests. These functions run them on tokio's blocking thread pool so
+//! that the runtime's workers stay free, and must be called from within a
+//! tokio runtime.
+//!
+//! # Cancellation
+//!
+//! An ioctl cannot be interrupted once issued. Dropping one of these
+//! futures, or hitting its timeout, only stops waiting for the result: the
+//! request still runs to completion on the blocking pool, and its result is
+//! then dropped. A derived key is wiped from memory at that point with the
+//! `zeroize` feature.
+//!
+//! The certificate buffer of an extended report request is owned by the
+//! blocking task, not by the future: it stays allocated while the kernel
+//! may write to it, and is freed as soon as the ioctl returns, whether or
+//! not anyone is still waiting for the result.
+//!
+//! # Example:
+//!
+//! ```ignore
+//! let request = DerivedKey::new(false, GuestFieldSelect::new().with_measurement(true), 0, 0, 0, None);
+//!
+//! let key = snp_derive_key_with_timeout(request, Duration::from_secs(5)).await?;
+//!
+//! let (report, certificates) = snp_get_extended_report(Some(nonce), None).await?;
+//! ```
+
+use super::*;
+
+use std::time::Duration;
+
+/// Requests an attestation report, as [Firmware::get_report] does,
+/// without blocking the runtime.
+pub async fn snp_get_report(
+    data: Option<[u8; 64]>,
+    vmpl: Option<u32>,
+) -> Result<AttestationReport, UserApiError> {
+    run_blocking(None, move || Firmware::open()?.get_report(None, data, vmpl)).await
+}
+
+/// Like [snp_get_report], failing with [UserApiError::Timeout] if the
+/// report is not returned within `timeout`.
+pub async fn snp_get_report_with_timeout(
+    data: Option<[u8; 64]>,
+    vmpl: Option<u32>,
+    timeout: Duration,
+) -> Result<AttestationReport, UserApiError> {
+    run_blocking(Some(timeout), move || {
+        Firmware::open()?.get_report(None, data, vmpl)
+    })
+    .await
+}
+
+/// Requests an attestation report along with the certificates provided by
+/// the hypervisor, as [Firmware::get_ext_report] does, without blocking the
+/// runtime.
+pub async fn snp_get_extended_report(
+    data: Option<[u8; 64]>,
+    vmpl: Option<u32>,
+) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+    run_blocking(None, move || {
+        Firmware::open()?.get_ext_report(None, data, vmpl)
+    })
+    .await
+}
+
+/// Like [snp_get_extended_report], failing with [UserApiError::Timeout] if
+/// the report is not returned within `timeout`.
+pub async fn snp_get_extended_report_with_timeout(
+    data: Option<[u8; 64]>,
+    vmpl: Option<u32>,
+    timeout: Duration,
+) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+    run_blocking(Some(timeout), move || {
+        Firmware::open()?.get_ext_report(None, data, vmpl)
+    })
+    .await
+}
+
+/// Requests a key derived from the VCEK or VMRK, as
+/// [Firmware::get_derived_key] does, without blocking the runtime.
+pub async fn snp_derive_key(request: DerivedKey) -> Result<SecretBytes<32>, UserApiError> {
+    run_blocking(None, move || {
+        Firmware::open()?.get_derived_key(None, request)
+    })
+    .await
+}
+
+/// Like [snp_derive_key], failing with [UserApiError::Timeout] if the key
+/// is not returned within `timeout`. See the
+/// [module documentation](self) for what happens to the request then.
+pub async fn snp_derive_key_with_timeout(
+    request: DerivedKey,
+    timeout: Duration,
+) -> Result<SecretBytes<32>, UserApiError> {
+    run_blocking(Some(timeout), move || {
+        Firmware::open()?.get_derived_key(None, request)
+    })
+    .await
+}
+
+/// Runs `request` on the blocking thread pool, waiting at most `timeout`
+/// for it to complete.
+async fn run_blocking<T, F>(timeout: Option<Duration>, request: F) -> Result<T, UserApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, UserApiError> + Send + 'static,
+{
+    let task = tokio::task::spawn_blocking(request);
+
+    let result = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| UserApiError::Timeout(timeout))?,
+        None => task.await,
+    };
+
+    result.map_err(UserApiError::TaskError)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::Error;
+
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn run_blocking_result() {
+        let rt = runtime();
+
+        assert_eq!(rt.block_on(run_blocking(None, || Ok(42))).unwrap(), 42);
+        assert!(matches!(
+            rt.block_on(run_blocking::<(), _>(None, || Err(
+                Error::InvalidParam.into()
+            ))),
+            Err(UserApiError::FirmwareError(Error::InvalidParam))
+        ));
+        assert!(matches!(
+            rt.block_on(run_blocking::<(), _>(None, || panic!("ioctl"))),
+            Err(UserApiError::TaskError(_))
+        ));
+    }
+
+    #[test]
+    fn run_blocking_timeout() {
+        let rt = runtime();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let request = {
+            let done = done.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(200));
+                done.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        };
+
+        assert!(matches!(
+            rt.block_on(run_blocking(Some(Duration::from_millis(10)), request)),
+            Err(UserApiError::Timeout(timeout)) if timeout == Duration::from_millis(10)
+        ));
+
+        // The request is not cancelled, and completes on the blocking pool.
+        drop(rt);
+        assert!(done.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn run_blocking_frees_request_state() {
+        let rt = runtime();
+        let buffer = Arc::new(vec![0u8; 4096]);
+
+        let request = {
+            let buffer = buffer.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(100));
+                Ok(buffer.len())
+            }
+        };
+
+        assert!(rt
+            .block_on(run_blocking(Some(Duration::from_millis(10)), request))
+            .is_err());
+
+        // Whatever the request owns, such as the certificate buffer of an
+        // extended report, is freed once it completes.
+        drop(rt);
+        assert_eq!(Arc::strong_count(&buffer), 1);
+    }
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keys derived from the firmware's derived key, for sealing guest secrets
+//! to the identity of the VM.
+//!
+//! The 32 bytes returned by `SNP_GET_DERIVED_KEY` are key material, not a
+//! key: expand them with [DerivedKey::derive_subkey] or [SealingKey::derive]
+//! rather than using them directly, e.g. for AES.
+//!
+//! Which sealed data can be unsealed again depends on the [DerivedKey]
+//! request the firmware key came from:
+//!
+//! - **Surviving reboot.** Derive from the VCEK (`root_key_select = false`).
+//!   The VCEK is fixed for a chip at a given TCB, so the key is the same
+//!   whenever the same guest is launched again on the same machine. Mixing
+//!   in the MEASUREMENT, GUEST_POLICY, IMAGE_ID or FAMILY_ID binds the key
+//!   to the launched image, and stays stable across reboots of that image.
+//!   Mix in TCB_VERSION with a fixed `tcb_version` (no newer than the
+//!   committed TCB) so the key also survives firmware updates; mix in
+//!   GUEST_SVN with a fixed `guest_svn` to let later guest versions unseal.
+//! - **Surviving migration.** Derive from the VMRK (`root_key_select =
+//!   true`), which the migration agent carries to the destination; the VCEK
+//!   differs on every chip, so VCEK-rooted data never survives migration.
+//!   Only guests launched with a migration agent have a VMRK. Leave
+//!   TCB_VERSION out unless every destination runs at or above the given
+//!   `tcb_version`.
+//!
+//! # Example:
+//!
+//! ```ignore
+//! let request = DerivedKey::new(false, GuestFieldSelect::new().with_measurement(true), 0, 0, 0, None);
+//! let firmware_key = fw.get_derived_key(None, request)?;
+//!
+//! let sealing_key = SealingKey::derive(firmware_key.expose_secret(), b"disk-0");
+//! ```
+
+use super::{DerivedKey, SecretBytes};
+
+use crate::error::DerivedKeyError;
+
+/// The HKDF salt of every key derived by this module, separating them from
+/// other uses of the same firmware key.
+const SUBKEY_SALT: &[u8] = b"virtee-sev derived key v1";
+
+const SHA384_SIZE: usize = 48;
+
+/// The longest output of HKDF-SHA-384.
+const MAX_SUBKEY_SIZE: usize = 255 * SHA384_SIZE;
+
+impl DerivedKey {
+    /// Expands a key returned by the firmware into `length` bytes of key
+    /// material with HKDF-SHA-384, under a salt specific to this crate.
+    ///
+    /// The HKDF info is `label`, a NUL byte, then `context`: distinct labels
+    /// yield independent keys from the same firmware key.
+    pub fn derive_subkey(
+        firmware_key: &[u8; 32],
+        label: &str,
+        context: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, DerivedKeyError> {
+        if length > MAX_SUBKEY_SIZE {
+            return Err(DerivedKeyError::SubkeyTooLong(length));
+        }
+
+        hkdf_sha384(
+            SUBKEY_SALT,
+            firmware_key,
+            &[label.as_bytes(), &[0], context],
+            length,
+        )
+    }
+}
+
+/// An AES-256-GCM key for sealing guest secrets, along with a prefix for
+/// its nonces.
+///
+/// Complete each 96-bit nonce with 8 bytes which never repeat under this
+/// key, e.g. a persisted counter.
+#[derive(Debug)]
+pub struct SealingKey {
+    key: SecretBytes<32>,
+    nonce_prefix: [u8; 4],
+}
+
+impl SealingKey {
+    /// The label under which sealing keys are derived.
+    pub const LABEL: &'static str = "sev-snp sealing key";
+
+    /// Derives the sealing key for `context` from a key returned by the
+    /// firmware. See the [module documentation](self) for which requests
+    /// make sealed data survive reboot or migration.
+    pub fn derive(firmware_key: &[u8; 32], context: &[u8]) -> Self {
+        let okm = DerivedKey::derive_subkey(firmware_key, Self::LABEL, context, 36)
+            .expect("36 bytes is within the HKDF-SHA-384 output limit");
+
+        let mut key = [0u8; 32];
+        let mut nonce_prefix = [0u8; 4];
+        key.copy_from_slice(&okm[..32]);
+        nonce_prefix.copy_from_slice(&okm[32..]);
+
+        let sealing_key = Self {
+            key: key.into(),
+            nonce_prefix,
+        };
+
+        // Wipe the copies left behind; the key itself lives on in SecretBytes.
+        #[cfg(feature = "zeroize")]
+        {
+            use zeroize::Zeroize;
+            let mut okm = okm;
+            okm.zeroize();
+            key.zeroize();
+        }
+
+        sealing_key
+    }
+
+    /// The AES-256-GCM key.
+    pub fn key(&self) -> &SecretBytes<32> {
+        &self.key
+    }
+
+    /// The first 4 bytes of every nonce used with [key](Self::key).
+    pub fn nonce_prefix(&self) -> [u8; 4] {
+        self.nonce_prefix
+    }
+}
+
+/// HKDF-SHA-384 (RFC 5869), with the concatenated `info` parts.
+fn hkdf_sha384(
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[&[u8]],
+    length: usize,
+) -> Result<Vec<u8>, DerivedKeyError> {
+    let mut okm = vec![0u8; length];
+
+    hkdf::Hkdf::<sha2::Sha384>::new(Some(salt), ikm)
+        .expand_multi_info(info, &mut okm)
+        .map_err(|_| DerivedKeyError::SubkeyTooLong(length))?;
+
+    Ok(okm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn firmware_key() -> [u8; 32] {
+        let mut key = [0u8; 32];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        key
+    }
+
+    #[test]
+    fn test_derive_subkey_known_answer() {
+        let okm = DerivedKey::derive_subkey(&firmware_key(), "test", b"context", 32).unwrap();
+        assert_eq!(
+            hex::encode(okm),
+            "2684d9b04ceb900983d87f75ac0b6b5e25b3cd5f232eb4d9584cbe1c3f5ee1dd"
+        );
+
+        // Longer outputs span several HKDF blocks and extend shorter ones.
+        let okm = DerivedKey::derive_subkey(&firmware_key(), "test", b"context", 100).unwrap();
+        assert_eq!(
+            hex::encode(okm),
+            "2684d9b04ceb900983d87f75ac0b6b5e25b3cd5f232eb4d9584cbe1c3f5ee1dd\
+             3a38a401e27481fe744c252b0c85b9a35e8d48cb5a03a0df4f5e9004e2eec6d1\
+             957890cb4c1127104e2f9b55321c1d453c4e13421e26b3c0917a0a7c15920380\
+             5dc3b9d9"
+        );
+    }
+
+    #[test]
+    fn test_derive_subkey_from_secret_bytes() {
+        // As returned by Firmware::get_derived_key.
+        let firmware_key = SecretBytes::from(firmware_key());
+
+        let okm = DerivedKey::derive_subkey(firmware_key.expose_secret(), "test", b"context", 32)
+            .unwrap();
+        assert_eq!(
+            hex::encode(okm),
+            "2684d9b04ceb900983d87f75ac0b6b5e25b3cd5f232eb4d9584cbe1c3f5ee1dd"
+        );
+    }
+
+    #[test]
+    fn test_derive_subkey_too_long() {
+        assert_eq!(
+            DerivedKey::derive_subkey(&firmware_key(), "test", &[], MAX_SUBKEY_SIZE + 1),
+            Err(DerivedKeyError::SubkeyTooLong(MAX_SUBKEY_SIZE + 1))
+        );
+        assert_eq!(
+            DerivedKey::derive_subkey(&firmware_key(), "test", &[], MAX_SUBKEY_SIZE)
+                .unwrap()
+                .len(),
+            MAX_SUBKEY_SIZE
+        );
+    }
+
+    #[test]
+    fn test_sealing_key() {
+        let key = SealingKey::derive(&firmware_key(), b"vm-1");
+        let okm =
+            hex::decode("83181dd707bd8406247fd45a3d2f726d694914b994b15373c1d248c71438df98a837e323")
+                .unwrap();
+
+        assert_eq!(&key.key().expose_secret()[..], &okm[..32]);
+        assert_eq!(&key.nonce_prefix()[..], &okm[32..]);
+
+        let other = SealingKey::derive(&firmware_key(), b"vm-2");
+        assert_ne!(key.key().expose_secret(), other.key().expose_secret());
+    }
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Synthetic attestation reports for tests.
+//!
+//! The reports are structurally valid: every reserved field is zero, the
+//! policy has its reserved bit set, fields are only set in the versions
+//! defining them and they parse back with
+//! [from_bytes_strict](AttestationReport::from_bytes_strict). They are not
+//! signed, so their signature will not verify.
+//!
+//! # Example:
+//!
+//! ```ignore
+//! let report = fake_v3_report_with_measurement([0x42; 48]);
+//!
+//! assert!(verify_measurement(&report, &[0x42; 48]).is_ok());
+//! ```
+
+use super::*;
+
+use crate::firmware::host::TcbVersion;
+
+/// The measurement of every fake report, unless given.
+pub const FAKE_MEASUREMENT: [u8; 48] = [0x4d; 48];
+
+/// The TCB of every fake report: current, committed, reported and launch.
+pub fn fake_tcb() -> TcbVersion {
+    TcbVersion::new(3, 0, 8, 115)
+}
+
+/// The CPUID family, model and stepping of fake version 3 reports: a
+/// Milan B1 processor.
+pub const FAKE_CPUID: (u8, u8, u8) = (0x19, 0x01, 0x01);
+
+/// The policy of every fake report, unless given: ABI 1.51 with SMT
+/// allowed.
+pub fn fake_policy() -> GuestPolicy {
+    "abi_major=1,abi_minor=51,smt".parse().unwrap()
+}
+
+/// A version 2 report, signed by the VCEK of an unmasked chip, at VMPL 0.
+pub fn fake_v2_report() -> AttestationReport {
+    let mut report = AttestationReport::default();
+
+    report.version = 2;
+    report.guest_svn = 1;
+    report.policy = fake_policy();
+    report.family_id = [0xfa; 16];
+    report.image_id = [0x1d; 16];
+    report.sig_algo = SigAlgo::EcdsaP384Sha384.into();
+    report.current_tcb = fake_tcb();
+    report.measurement = FAKE_MEASUREMENT.into();
+    report.report_id = [0x11; 32].into();
+    report.reported_tcb = fake_tcb();
+    report.chip_id = [0xc1; 64].into();
+    report.committed_tcb = fake_tcb();
+    report.current_major = 1;
+    report.current_minor = 55;
+    report.current_build = 21;
+    report.committed_major = 1;
+    report.committed_minor = 55;
+    report.committed_build = 21;
+    report.launch_tcb = fake_tcb();
+
+    report
+}
+
+/// A version 3 report, otherwise as [fake_v2_report], with the CPUID fields
+/// set to [FAKE_CPUID] and the alias check complete.
+pub fn fake_v3_report() -> AttestationReport {
+    let (family, model, stepping) = FAKE_CPUID;
+
+    let mut report = fake_v2_report();
+    report.version = NormalizedReport::V3;
+    report.set_v3_fields(family, model, stepping, true);
+
+    report
+}
+
+/// A [fake_v2_report] launched with `policy`.
+pub fn fake_v2_report_with_policy(policy: GuestPolicy) -> AttestationReport {
+    let mut report = fake_v2_report();
+    report.policy = policy;
+
+    report
+}
+
+/// A [fake_v3_report] launched with `policy`.
+pub fn fake_v3_report_with_policy(policy: GuestPolicy) -> AttestationReport {
+    let mut report = fake_v3_report();
+    report.policy = policy;
+
+    report
+}
+
+/// A [fake_v2_report] with the launch measurement `measurement`.
+pub fn fake_v2_report_with_measurement(measurement: [u8; 48]) -> AttestationReport {
+    let mut report = fake_v2_report();
+    report.measurement = measurement.into();
+
+    report
+}
+
+/// A [fake_v3_report] with the launch measurement `measurement`.
+pub fn fake_v3_report_with_measurement(measurement: [u8; 48]) -> AttestationReport {
+    let mut report = fake_v3_report();
+    report.measurement = measurement.into();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    fn strict_round_trip(report: &AttestationReport) -> AttestationReport {
+        let bytes = bincode::serialize(report).unwrap();
+
+        AttestationReport::from_bytes_strict(&bytes).unwrap()
+    }
+
+    #[test]
+    fn fake_reports_are_valid() {
+        for report in [fake_v2_report(), fake_v3_report()] {
+            assert_eq!(strict_round_trip(&report), report);
+            assert!(report.policy.verify_reserved().is_ok());
+            assert!(report.key_info().is_vcek_signed());
+            assert!(!report.chip_id.is_masked());
+        }
+
+        let bytes = bincode::serialize(&fake_v2_report()).unwrap();
+        assert_eq!(AttestationReport::try_from(&bytes[..]).unwrap().version, 2);
+    }
+
+    #[test]
+    fn fake_report_versions() {
+        let v2 = fake_v2_report().into_latest();
+        assert_eq!(v2.source_version(), 2);
+        assert_eq!(v2.cpuid_fam_id, None);
+        assert_eq!(v2.alias_check_complete, None);
+
+        let v3 = fake_v3_report().into_latest();
+        assert_eq!(v3.source_version(), 3);
+        assert_eq!(
+            (v3.cpuid_fam_id, v3.cpuid_mod_id, v3.cpuid_step),
+            (Some(0x19), Some(0x01), Some(0x01))
+        );
+        assert_eq!(v3.alias_check_complete, Some(true));
+    }
+
+    #[test]
+    fn fake_report_overrides() {
+        let mut policy = fake_policy();
+        policy.set_debug_allowed(1);
+
+        assert_eq!(fake_v2_report_with_policy(policy).policy, policy);
+        assert_eq!(fake_v3_report_with_policy(policy).version, 3);
+
+        let report = fake_v3_report_with_measurement([0x42; 48]);
+        assert!(report.measurement.verify_against_expected(&[0x42; 48]));
+        assert_eq!(strict_round_trip(&report), report);
+    }
+}
@@ -0,0 +1,467 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Portable tokens asserting the claims of a verified attestation report,
+//! so that downstream services need neither the report nor its
+//! certificate chain.
+//!
+//! Tokens are signed with an ES384 (ECDSA P-384, SHA-384) key of the
+//! verifier, as a JWT (RFC 7519) or a COSE_Sign1 message (RFC 9052). Both
+//! carry the same claims, those of [AttestationReport::to_claims] with two
+//! changes:
+//!
+//! - `chip_id` is replaced with `chip_id_sha384`, the hex SHA-384 digest of
+//!   the CHIP_ID, so that tokens do not spread the identity of the chip.
+//! - `iat` is the time the report was verified, in seconds since the Unix
+//!   epoch.
+//!
+//! # Example:
+//!
+//! ```ignore
+//! let verified = VerifiedReport::verify(&chain, report)?;
+//! let token = verified.to_jwt(&signing_key)?;
+//!
+//! // In a downstream service, with the verifier's public key:
+//! let claims = verify_jwt(&token, &verifying_key)?;
+//! ```
+
+use crate::{
+    certs::snp::{builtin, Chain, Verifiable},
+    error::TokenError,
+    firmware::guest::AttestationReport,
+};
+
+use std::{
+    collections::BTreeMap,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::value::Value as CborValue;
+use openssl::{
+    bn::BigNum,
+    ec::EcKeyRef,
+    ecdsa::EcdsaSig,
+    nid::Nid,
+    pkey::{HasPublic, Private, Public},
+    sha::sha384,
+};
+use serde_json::Value;
+
+/// The claims carried by a token.
+pub type Claims = BTreeMap<String, Value>;
+
+/// The COSE algorithm identifier of ES384.
+const COSE_ALG_ES384: i64 = -35;
+
+/// The COSE header label of the algorithm.
+const COSE_HEADER_ALG: i64 = 1;
+
+/// The CBOR tag of a COSE_Sign1 message.
+const COSE_SIGN1_TAG: u64 = 18;
+
+/// The size of each of the r and s components of a P-384 signature.
+const P384_COMPONENT_SIZE: i32 = 48;
+
+/// An attestation report whose signature has been verified against a
+/// certificate chain.
+///
+/// It can only be obtained through [VerifiedReport::verify], so that
+/// tokens are never minted from unverified reports.
+#[derive(Clone, Debug)]
+pub struct VerifiedReport {
+    report: AttestationReport,
+    verified_at: u64,
+}
+
+impl VerifiedReport {
+    /// Verifies that the report is signed by the VCEK or VLEK of `chain`,
+    /// and that the chain itself verifies and is rooted in one of AMD's
+    /// builtin ARKs.
+    pub fn verify(chain: &Chain, report: AttestationReport) -> io::Result<Self> {
+        if !builtin::is_builtin_ark(&chain.ca.ark) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the chain's ARK is not one of AMD's builtin ARKs",
+            ));
+        }
+
+        (chain, &report).verify()?;
+
+        let verified_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .as_secs();
+
+        Ok(Self {
+            report,
+            verified_at,
+        })
+    }
+
+    /// The verified report.
+    pub fn report(&self) -> &AttestationReport {
+        &self.report
+    }
+
+    /// When the report was verified, in seconds since the Unix epoch.
+    pub fn verified_at(&self) -> u64 {
+        self.verified_at
+    }
+
+    /// The claims embedded in tokens. See the [module documentation](self).
+    pub fn claims(&self) -> Claims {
+        let mut claims = self.report.to_claims();
+
+        claims.remove("chip_id");
+        claims.insert(
+            "chip_id_sha384".to_string(),
+            hex::encode(sha384(self.report.chip_id.as_ref())).into(),
+        );
+        claims.insert("iat".to_string(), self.verified_at.into());
+
+        claims
+    }
+
+    /// Mints a compact JWT of the claims, signed with the ES384 key
+    /// `signing_key`.
+    pub fn to_jwt(&self, signing_key: &EcKeyRef<Private>) -> Result<String, TokenError> {
+        let header = serde_json::json!({ "alg": "ES384", "typ": "JWT" });
+
+        let header = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?);
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&self.claims())?);
+        let signing_input = format!("{header}.{payload}");
+
+        let signature = sign(signing_key, signing_input.as_bytes())?;
+
+        Ok(format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Mints a tagged COSE_Sign1 message of the claims, encoded as a CBOR
+    /// map, signed with the ES384 key `signing_key`.
+    pub fn to_cose_sign1(&self, signing_key: &EcKeyRef<Private>) -> Result<Vec<u8>, TokenError> {
+        let protected = protected_header()?;
+        let payload = to_cbor(&self.claims())?;
+
+        let signature = sign(signing_key, &sig_structure(&protected, &payload)?)?;
+
+        let message = CborValue::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(CborValue::Array(vec![
+                CborValue::Bytes(protected),
+                CborValue::Map(vec![]),
+                CborValue::Bytes(payload),
+                CborValue::Bytes(signature),
+            ])),
+        );
+
+        to_cbor(&message)
+    }
+}
+
+/// Verifies a JWT minted by [VerifiedReport::to_jwt] against the public
+/// key of the verifier, returning its claims.
+pub fn verify_jwt(token: &str, verifying_key: &EcKeyRef<Public>) -> Result<Claims, TokenError> {
+    let mut parts = token.split('.');
+
+    let (header, payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(payload), Some(signature)) if parts.next().is_none() => {
+            (header, payload, signature)
+        }
+        _ => return Err(TokenError::Malformed("a JWT has three parts")),
+    };
+
+    let decoded: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+    match decoded.get("alg").and_then(Value::as_str) {
+        Some("ES384") => (),
+        alg => {
+            return Err(TokenError::UnsupportedAlgorithm(
+                alg.unwrap_or_default().to_string(),
+            ))
+        }
+    }
+
+    let signing_input = &token[..header.len() + 1 + payload.len()];
+    verify(
+        verifying_key,
+        signing_input.as_bytes(),
+        &URL_SAFE_NO_PAD.decode(signature)?,
+    )?;
+
+    Ok(serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)?)
+}
+
+/// Verifies a COSE_Sign1 message minted by [VerifiedReport::to_cose_sign1]
+/// against the public key of the verifier, returning its claims.
+pub fn verify_cose_sign1(
+    token: &[u8],
+    verifying_key: &EcKeyRef<Public>,
+) -> Result<Claims, TokenError> {
+    let message: CborValue = from_cbor(token)?;
+
+    let fields = match message {
+        CborValue::Tag(COSE_SIGN1_TAG, message) => match *message {
+            CborValue::Array(fields) => fields,
+            _ => return Err(TokenError::Malformed("COSE_Sign1 is not an array")),
+        },
+        _ => return Err(TokenError::Malformed("missing the COSE_Sign1 tag")),
+    };
+
+    let (protected, payload, signature) = match fields.as_slice() {
+        [CborValue::Bytes(protected), CborValue::Map(_), CborValue::Bytes(payload), CborValue::Bytes(signature)] => {
+            (protected, payload, signature)
+        }
+        _ => return Err(TokenError::Malformed("COSE_Sign1 has four fields")),
+    };
+
+    let header: CborValue = from_cbor(protected)?;
+    let alg = header.as_map().and_then(|header| {
+        header
+            .iter()
+            .find(|(label, _)| label.as_integer() == Some(COSE_HEADER_ALG.into()))
+            .and_then(|(_, alg)| alg.as_integer())
+    });
+
+    if alg != Some(COSE_ALG_ES384.into()) {
+        return Err(TokenError::UnsupportedAlgorithm(format!("{alg:?}")));
+    }
+
+    verify(
+        verifying_key,
+        &sig_structure(protected, payload)?,
+        signature,
+    )?;
+
+    from_cbor(payload)
+}
+
+/// The protected header of every COSE_Sign1 message: the ES384 algorithm.
+fn protected_header() -> Result<Vec<u8>, TokenError> {
+    to_cbor(&CborValue::Map(vec![(
+        COSE_HEADER_ALG.into(),
+        COSE_ALG_ES384.into(),
+    )]))
+}
+
+/// The Sig_structure signed in a COSE_Sign1 message, without external AAD.
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>, TokenError> {
+    to_cbor(&CborValue::Array(vec![
+        "Signature1".into(),
+        CborValue::Bytes(protected.to_vec()),
+        CborValue::Bytes(vec![]),
+        CborValue::Bytes(payload.to_vec()),
+    ]))
+}
+
+fn to_cbor<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, TokenError> {
+    let mut bytes = vec![];
+    ciborium::into_writer(value, &mut bytes).map_err(crate::error::CborError::from)?;
+
+    Ok(bytes)
+}
+
+fn from_cbor<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, TokenError> {
+    Ok(ciborium::from_reader(bytes).map_err(crate::error::CborError::from)?)
+}
+
+/// Fails unless `key` is on the P-384 curve, as ES384 requires.
+fn check_curve<T: HasPublic>(key: &EcKeyRef<T>) -> Result<(), TokenError> {
+    match key.group().curve_name() {
+        Some(Nid::SECP384R1) => Ok(()),
+        _ => Err(TokenError::UnsupportedKey),
+    }
+}
+
+/// Signs the SHA-384 digest of `data`, returning the raw r||s signature.
+fn sign(key: &EcKeyRef<Private>, data: &[u8]) -> Result<Vec<u8>, TokenError> {
+    check_curve(key)?;
+
+    let signature = EcdsaSig::sign(&sha384(data), key)?;
+
+    let mut raw = signature.r().to_vec_padded(P384_COMPONENT_SIZE)?;
+    raw.extend(signature.s().to_vec_padded(P384_COMPONENT_SIZE)?);
+
+    Ok(raw)
+}
+
+/// Verifies a raw r||s signature over the SHA-384 digest of `data`.
+fn verify(key: &EcKeyRef<Public>, data: &[u8], signature: &[u8]) -> Result<(), TokenError> {
+    check_curve(key)?;
+
+    if signature.len() != 2 * P384_COMPONENT_SIZE as usize {
+        return Err(TokenError::InvalidSignature);
+    }
+
+    let (r, s) = signature.split_at(P384_COMPONENT_SIZE as usize);
+    let signature =
+        EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?;
+
+    match signature.verify(&sha384(data), key)? {
+        true => Ok(()),
+        false => Err(TokenError::InvalidSignature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::fixtures::{milan_chain, milan_report, milan_report_bytes};
+
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        pkey::Private,
+    };
+
+    use std::convert::TryFrom;
+
+    fn verified_milan_report() -> VerifiedReport {
+        VerifiedReport::verify(&milan_chain(), milan_report()).unwrap()
+    }
+
+    fn key_pair(curve: Nid) -> (EcKey<Private>, EcKey<Public>) {
+        let group = EcGroup::from_curve_name(curve).unwrap();
+        let private = EcKey::generate(&group).unwrap();
+        let public = EcKey::from_public_key(&group, private.public_key()).unwrap();
+
+        (private, public)
+    }
+
+    #[test]
+    fn unverified_report_is_rejected() {
+        let mut bytes = milan_report_bytes();
+        bytes[0x90] ^= 0xff;
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert!(VerifiedReport::verify(&milan_chain(), report).is_err());
+    }
+
+    #[test]
+    fn self_made_chain_is_rejected() {
+        // A chain which verifies on its own, but is not rooted in AMD's ARKs.
+        let chain = Chain::from_pem(
+            include_bytes!("../../../tests/certs_data/crl/ark.pem"),
+            include_bytes!("../../../tests/certs_data/crl/ask.pem"),
+            include_bytes!("../../../tests/certs_data/crl/vcek.pem"),
+        )
+        .unwrap();
+        assert!(chain.verify().is_ok());
+
+        let err = VerifiedReport::verify(&chain, milan_report()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the chain's ARK is not one of AMD's builtin ARKs"
+        );
+    }
+
+    #[test]
+    fn claims_snapshot() {
+        let verified = verified_milan_report();
+        let mut claims = verified.claims();
+
+        assert_eq!(claims.remove("iat"), Some(verified.verified_at().into()));
+        assert!(!claims.contains_key("chip_id"));
+
+        let expected: Claims = serde_json::from_slice(include_bytes!(
+            "../../../tests/certs_data/report_milan_token_claims.json"
+        ))
+        .unwrap();
+        assert_eq!(claims, expected);
+    }
+
+    #[test]
+    fn jwt_round_trip() {
+        let verified = verified_milan_report();
+        let (private, public) = key_pair(Nid::SECP384R1);
+
+        let token = verified.to_jwt(&private).unwrap();
+        assert_eq!(token.split('.').count(), 3);
+        assert_eq!(verify_jwt(&token, &public).unwrap(), verified.claims());
+
+        // Another key, or any change to the payload, fails verification.
+        let (_, other) = key_pair(Nid::SECP384R1);
+        assert!(matches!(
+            verify_jwt(&token, &other),
+            Err(TokenError::InvalidSignature)
+        ));
+
+        let mut claims = verified.claims();
+        claims.insert("vmpl".to_string(), 3.into());
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = format!("{}.{forged_payload}.{}", parts[0], parts[2]);
+        assert!(matches!(
+            verify_jwt(&forged, &public),
+            Err(TokenError::InvalidSignature)
+        ));
+
+        assert!(matches!(
+            verify_jwt("a.b", &public),
+            Err(TokenError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn jwt_rejects_other_algorithms() {
+        let (_, public) = key_pair(Nid::SECP384R1);
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(b"{}");
+
+        assert!(matches!(
+            verify_jwt(&format!("{header}.{payload}."), &public),
+            Err(TokenError::UnsupportedAlgorithm(alg)) if alg == "none"
+        ));
+    }
+
+    #[test]
+    fn cose_sign1_round_trip() {
+        let verified = verified_milan_report();
+        let (private, public) = key_pair(Nid::SECP384R1);
+
+        let token = verified.to_cose_sign1(&private).unwrap();
+
+        // Tag 18, then an array of four fields.
+        assert_eq!(&token[..2], &[0xd2, 0x84]);
+        assert_eq!(
+            verify_cose_sign1(&token, &public).unwrap(),
+            verified.claims()
+        );
+
+        let (_, other) = key_pair(Nid::SECP384R1);
+        assert!(matches!(
+            verify_cose_sign1(&token, &other),
+            Err(TokenError::InvalidSignature)
+        ));
+
+        let mut tampered = token.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(
+            verify_cose_sign1(&tampered, &public),
+            Err(TokenError::InvalidSignature)
+        ));
+
+        assert!(matches!(
+            verify_cose_sign1(&token[1..], &public),
+            Err(TokenError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn non_p384_keys_are_rejected() {
+        let verified = verified_milan_report();
+        let (private, _) = key_pair(Nid::X9_62_PRIME256V1);
+
+        assert!(matches!(
+            verified.to_jwt(&private),
+            Err(TokenError::UnsupportedKey)
+        ));
+        assert!(matches!(
+            verified.to_cose_sign1(&private),
+            Err(TokenError::UnsupportedKey)
+        ));
+    }
+}
@@ -1,61 +1,162 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{certs::snp::ecdsa::Signature, firmware::host::TcbVersion, util::hexdump};
+use crate::{
+    certs::snp::ecdsa::Signature,
+    error::{
+        AttestationReportError, ChipIdError, GuestFieldSelectError, LaunchConfigError, PolicyError,
+        PolicyViolation, ReportDataError, ReportSearchError, UserApiError,
+    },
+    firmware::host::{FirmwareCapabilities, TcbComponents, TcbVersion},
+    util::{hexdump, TypeSave},
+    Generation, Version,
+};
 
 #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
-use crate::certs::snp::{Chain, Verifiable};
+use crate::{
+    certs::snp::{vek_cert_type, verify_cert_matches_report, Chain, Verifiable},
+    error::ReportSignatureError,
+    firmware::host::CertType,
+};
 
-use std::fmt::Display;
+#[cfg(feature = "serde_json")]
+use std::collections::BTreeMap;
 
-#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
 use std::{
     convert::TryFrom,
-    io::{self, Error, ErrorKind},
+    fmt::Display,
+    fs::File,
+    io::{Read, Write},
+    ops::Range,
+    path::Path,
+    str::FromStr,
 };
 
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+use std::io::{self, Error, ErrorKind};
+
+#[cfg(feature = "cbor")]
+use crate::error::CborError;
+
+#[cfg(feature = "serde_json")]
+use crate::error::JsonReportError;
+
 use bitfield::bitfield;
 
+/// Base URL of AMD's Key Distribution Service (KDS).
+const KDS_CERT_SITE: &str = "https://kdsintf.amd.com";
+
+/// The least privileged VMPL.
+const MAX_VMPL: u32 = 3;
+
 #[cfg(feature = "openssl")]
 use openssl::{ecdsa::EcdsaSig, sha::Sha384};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_big_array::BigArray;
+use static_assertions::const_assert;
+use subtle::{Choice, ConstantTimeEq};
 
 /// Structure of required data for fetching the derived key.
-#[derive(Copy, Clone, Debug)]
+///
+/// Requests can be read from configuration files: ROOT_KEY_SELECT is given
+/// as the boolean `root_key_select` (true for the VMRK), the field selection
+/// as named booleans, and omitted fields default to zero.
+///
+/// # Example:
+///
+/// ```ignore
+/// let request: DerivedKey = toml::from_str(
+///     r#"
+///     vmpl = 1
+///     guest_svn = 2
+///
+///     [guest_field_select]
+///     measurement = true
+///     guest_svn = true
+///     "#,
+/// )?;
+///
+/// let key = fw.get_derived_key(None, request)?;
+/// ```
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DerivedKey {
     /// Selects the root key to derive the key from.
     /// 0: Indicates VCEK.
     /// 1: Indicates VMRK.
+    #[serde(default, with = "root_key_select")]
     root_key_select: u32,
 
     /// Reserved, must be zero
+    #[serde(skip)]
     _reserved_0: u32,
 
     /// What data will be mixed into the derived key.
+    #[serde(default)]
     pub guest_field_select: GuestFieldSelect,
 
     /// The VMPL to mix into the derived key. Must be greater than or equal
     /// to the current VMPL.
+    #[serde(default)]
     pub vmpl: u32,
 
     /// The guest SVN to mix into the key. Must not exceed the guest SVN
     /// provided at launch in the ID block.
+    #[serde(default)]
     pub guest_svn: u32,
 
     /// The TCB version to mix into the derived key. Must not
     /// exceed CommittedTcb.
+    #[serde(default)]
     pub tcb_version: u64,
+
+    /// The mitigation vector value to mix into the derived key.
+    /// Requires SNP firmware 1.58 or newer and a kernel passing it on (see
+    /// [new](Self::new)); `None` leaves the field zeroed.
+    #[serde(default)]
+    pub launch_mit_vector: Option<u64>,
+
+    /// Set by [checked](Self::checked), which any request carrying a
+    /// launch mitigation vector must go through.
+    #[serde(skip)]
+    launch_mit_vector_checked: bool,
+}
+
+/// Serializes ROOT_KEY_SELECT as a boolean, true selecting the VMRK.
+mod root_key_select {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        (*value != 0).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        bool::deserialize(deserializer).map(u32::from)
+    }
 }
 
 impl DerivedKey {
     /// Create a new instance for requesting an DerivedKey.
+    ///
+    /// The `launch_mit_vector` is only honored by firmware supporting the
+    /// launch mitigation vector (1.58+). To mix it into the key, the
+    /// corresponding bit of the [GuestFieldSelect] must be set as well.
+    ///
+    /// The vector also needs a kernel whose `struct snp_derived_key_req`
+    /// has the `launch_mit_vector` field (40 bytes). Kernels with the
+    /// original 32-byte structure copy only those bytes and silently drop
+    /// the vector, deriving a key without it. The crate cannot tell the two
+    /// apart, so a request carrying a vector must go through
+    /// [checked](Self::checked), which is the caller's assertion that the
+    /// kernel passes it on; [Firmware::get_derived_key](crate::firmware::guest::Firmware::get_derived_key)
+    /// fails with [UserApiError::LaunchMitVectorUnchecked] otherwise.
     pub fn new(
         root_key_select: bool,
         guest_field_select: GuestFieldSelect,
         vmpl: u32,
         guest_svn: u32,
         tcb_version: u64,
+        launch_mit_vector: Option<u64>,
     ) -> Self {
         Self {
             root_key_select: u32::from(root_key_select),
@@ -64,6 +165,8 @@ impl DerivedKey {
             vmpl,
             guest_svn,
             tcb_version,
+            launch_mit_vector,
+            launch_mit_vector_checked: false,
         }
     }
 
@@ -71,8 +174,109 @@ impl DerivedKey {
     pub fn get_root_key_select(&self) -> u32 {
         self.root_key_select
     }
+
+    /// Obtain a copy of the launch mitigation vector value.
+    pub fn get_launch_mit_vector(&self) -> Option<u64> {
+        self.launch_mit_vector
+    }
+
+    /// Mix the given launch mitigation vector into the derived key.
+    ///
+    /// The corresponding bit of the [GuestFieldSelect] still has to be set
+    /// for the firmware to take the value into account, and the request
+    /// still has to go through [checked](Self::checked) (see [new](Self::new)).
+    pub fn with_launch_mit_vector(mut self, launch_mit_vector: u64) -> Self {
+        self.launch_mit_vector = Some(launch_mit_vector);
+        self
+    }
+
+    /// Checks the request against the features of the firmware, failing
+    /// with [UserApiError::UnsupportedFirmware] rather than having the
+    /// firmware reject it as invalid.
+    ///
+    /// Required of requests carrying a launch mitigation vector, which
+    /// must only be sent on kernels passing the vector to the firmware
+    /// (see [new](Self::new)).
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let request = DerivedKey::new(false, selection, 0, 0, 0, Some(vector))
+    ///     .checked(&report.firmware_capabilities())?;
+    /// ```
+    pub fn checked(self, capabilities: &FirmwareCapabilities) -> Result<Self, UserApiError> {
+        if self.uses_launch_mit_vector() {
+            capabilities.require(
+                "the launch mitigation vector",
+                FirmwareCapabilities::LAUNCH_MIT_VECTOR,
+            )?;
+        }
+
+        Ok(Self {
+            launch_mit_vector_checked: true,
+            ..self
+        })
+    }
+
+    /// Fails with [UserApiError::LaunchMitVectorUnchecked] if the request
+    /// carries a launch mitigation vector without having been
+    /// [checked](Self::checked).
+    pub(crate) fn check_launch_mit_vector(&self) -> Result<(), UserApiError> {
+        match self.launch_mit_vector.is_some() && !self.launch_mit_vector_checked {
+            true => Err(UserApiError::LaunchMitVectorUnchecked),
+            false => Ok(()),
+        }
+    }
+
+    /// Whether the request carries a launch mitigation vector or selects
+    /// one, either of which requires firmware 1.58 or newer.
+    pub(crate) fn uses_launch_mit_vector(&self) -> bool {
+        self.launch_mit_vector.is_some() || self.guest_field_select.get_launch_mit_vector() != 0
+    }
+}
+
+/// Secret key material, such as a key derived by the firmware.
+///
+/// The bytes are redacted from the [Debug] output and, with the `zeroize`
+/// feature, wiped from memory when dropped.
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    /// The raw secret bytes.
+    pub fn expose_secret(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for SecretBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> std::fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[REDACTED {N} bytes]")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::Zeroize for SecretBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
 }
 
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::ZeroizeOnDrop for SecretBytes<N> {}
+
 bitfield! {
     /// Data which will be mixed into the derived key.
     ///
@@ -84,7 +288,8 @@ bitfield! {
     /// |3|MEASUREMENT|Indicates the measurement of the guest during launch will be mixed into the key.|
     /// |4|GUEST_SVN|Indicates that the guest-provided SVN will be mixed into the key.|
     /// |5|TCB_VERSION|Indicates that the guest-provided TCB_VERSION will be mixed into the key.|
-    /// |63:6|\-|Reserved. Must be zero.|
+    /// |6|LAUNCH_MIT_VECTOR|Indicates that the guest-provided LAUNCH_MIT_VECTOR will be mixed into the key.|
+    /// |63:7|\-|Reserved. Must be zero.|
     #[repr(C)]
     #[derive(Default, Copy, Clone)]
     pub struct GuestFieldSelect(u64);
@@ -101,6 +306,238 @@ bitfield! {
     pub get_svn, set_svn: 4, 4;
     /// Check/Set tcb version inclusion in derived key.
     pub get_tcb_version, set_tcb_version: 5, 5;
+    /// Check/Set launch mitigation vector inclusion in derived key.
+    pub get_launch_mit_vector, set_launch_mit_vector: 6, 6;
+}
+
+impl GuestFieldSelect {
+    /// Field names, in bit order, used by the [Display] and [FromStr]
+    /// implementations.
+    const FIELD_NAMES: [&'static str; 7] = [
+        "guest_policy",
+        "image_id",
+        "family_id",
+        "measurement",
+        "guest_svn",
+        "tcb_version",
+        "launch_mit_vector",
+    ];
+
+    /// Field names, in bit order, as spelled in the SEV-SNP ABI
+    /// specification.
+    const SPEC_NAMES: [&'static str; 7] = [
+        "GUEST_POLICY",
+        "IMAGE_ID",
+        "FAMILY_ID",
+        "MEASUREMENT",
+        "GUEST_SVN",
+        "TCB_VERSION",
+        "LAUNCH_MIT_VECTOR",
+    ];
+
+    /// Creates an empty selection. Use the `with_*` methods to choose
+    /// which fields are mixed into the derived key.
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let selection = GuestFieldSelect::new()
+    ///     .with_measurement(true)
+    ///     .with_guest_policy(true);
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mask of the defined field bits; the remaining bits are reserved.
+    const FIELDS_MASK: u64 = (1 << Self::FIELD_NAMES.len()) - 1;
+
+    /// Selects every field, for a key bound as tightly as possible to the
+    /// guest.
+    ///
+    /// This includes the launch mitigation vector, which firmware older
+    /// than 1.58 rejects.
+    pub fn all() -> Self {
+        Self(Self::FIELDS_MASK)
+    }
+
+    /// Selects no field, for a key which only depends on the root key and
+    /// the VMPL.
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    /// Creates a selection from raw bits, rejecting reserved bits.
+    pub fn from_bits(bits: u64) -> Result<Self, GuestFieldSelectError> {
+        match bits & !Self::FIELDS_MASK {
+            0 => Ok(Self(bits)),
+            reserved => Err(GuestFieldSelectError::InvalidBits(reserved)),
+        }
+    }
+
+    /// The specification names of the selected fields, in bit order (e.g.
+    /// `["GUEST_POLICY", "MEASUREMENT"]`), for logging which fields a
+    /// derived key is bound to.
+    pub fn active_fields(&self) -> Vec<&'static str> {
+        Self::SPEC_NAMES
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.0 & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Select (or deselect) the guest policy.
+    pub fn with_guest_policy(mut self, enabled: bool) -> Self {
+        self.set_guest_policy(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the image ID.
+    pub fn with_image_id(mut self, enabled: bool) -> Self {
+        self.set_image_id(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the family ID.
+    pub fn with_family_id(mut self, enabled: bool) -> Self {
+        self.set_family_id(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the launch measurement.
+    pub fn with_measurement(mut self, enabled: bool) -> Self {
+        self.set_measurement(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the guest SVN.
+    pub fn with_guest_svn(mut self, enabled: bool) -> Self {
+        self.set_svn(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the TCB version.
+    pub fn with_tcb_version(mut self, enabled: bool) -> Self {
+        self.set_tcb_version(enabled.into());
+        self
+    }
+
+    /// Select (or deselect) the launch mitigation vector.
+    pub fn with_launch_mit_vector(mut self, enabled: bool) -> Self {
+        self.set_launch_mit_vector(enabled.into());
+        self
+    }
+}
+
+impl Display for GuestFieldSelect {
+    /// Prints the selected fields as a comma-separated list, or `none` if
+    /// no field is selected.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self
+            .active_fields()
+            .iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        match names.is_empty() {
+            true => write!(f, "none"),
+            false => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+/// The named fields of a [GuestFieldSelect], as serialized in
+/// human-readable formats. Omitted fields are not selected.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GuestFieldFlags {
+    guest_policy: bool,
+    image_id: bool,
+    family_id: bool,
+    measurement: bool,
+    guest_svn: bool,
+    tcb_version: bool,
+    launch_mit_vector: bool,
+}
+
+impl From<GuestFieldSelect> for GuestFieldFlags {
+    fn from(value: GuestFieldSelect) -> Self {
+        Self {
+            guest_policy: value.get_guest_policy() == 1,
+            image_id: value.get_image_id() == 1,
+            family_id: value.get_family_id() == 1,
+            measurement: value.get_measurement() == 1,
+            guest_svn: value.get_svn() == 1,
+            tcb_version: value.get_tcb_version() == 1,
+            launch_mit_vector: value.get_launch_mit_vector() == 1,
+        }
+    }
+}
+
+impl From<GuestFieldFlags> for GuestFieldSelect {
+    fn from(value: GuestFieldFlags) -> Self {
+        Self::new()
+            .with_guest_policy(value.guest_policy)
+            .with_image_id(value.image_id)
+            .with_family_id(value.family_id)
+            .with_measurement(value.measurement)
+            .with_guest_svn(value.guest_svn)
+            .with_tcb_version(value.tcb_version)
+            .with_launch_mit_vector(value.launch_mit_vector)
+    }
+}
+
+impl Serialize for GuestFieldSelect {
+    /// Serializes the selection as a struct of named booleans in
+    /// human-readable formats, and as the raw bits otherwise.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => GuestFieldFlags::from(*self).serialize(serializer),
+            false => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GuestFieldSelect {
+    /// Deserializes the representation written by [Serialize], rejecting
+    /// unknown field names and reserved bits.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.is_human_readable() {
+            true => GuestFieldFlags::deserialize(deserializer).map(Self::from),
+            false => {
+                Self::from_bits(u64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
+impl FromStr for GuestFieldSelect {
+    type Err = GuestFieldSelectError;
+
+    /// Parses a comma-separated list of field names (e.g.
+    /// `"measurement,tcb_version"`). Names are case-insensitive; an empty
+    /// string or `none` selects no field.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selection = Self::new();
+
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let name = name.to_ascii_lowercase();
+
+            if name == "none" {
+                continue;
+            }
+
+            let bit = Self::FIELD_NAMES
+                .iter()
+                .position(|field| *field == name)
+                .ok_or(GuestFieldSelectError::UnknownField(name))?;
+
+            selection.0 |= 1 << bit;
+        }
+
+        Ok(selection)
+    }
 }
 
 /// The guest can request that the firmware construct an attestation report. External entities can use an
@@ -123,7 +560,7 @@ bitfield! {
 /// The firmware guarantees that the ReportedTcb value is never greater than the installed TCB
 /// version
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct AttestationReport {
     /// Version number of this attestation report. Set to 2h for this specification.
     pub version: u32,
@@ -143,16 +580,15 @@ pub struct AttestationReport {
     pub current_tcb: TcbVersion,
     /// Information about the platform. See PlatformInfo
     pub plat_info: PlatformInfo,
-    /// Private variable as only the first bit is important.
-    /// See [author_key_en()](self::AttestationReport::author_key_en).
-    _author_key_en: u32,
-    _reserved_0: u32,
+    /// Information related to signing keys in the report. See KeyInfo
+    pub key_info: KeyInfo,
+    #[serde(with = "reserved_word")]
+    _reserved_0: [u8; 4],
     #[serde(with = "BigArray")]
     /// Guest-provided 512 Bits of Data
     pub report_data: [u8; 64],
-    #[serde(with = "BigArray")]
     /// The measurement calculated at launch.
-    pub measurement: [u8; 48],
+    pub measurement: Measurement,
     /// Data provided by the hypervisor at launch.
     pub host_data: [u8; 32],
     #[serde(with = "BigArray")]
@@ -164,16 +600,15 @@ pub struct AttestationReport {
     /// if provided in SNP_LAUNCH_FINSIH. Zeroes if AUTHOR_KEY_EN is 1.
     pub author_key_digest: [u8; 48],
     /// Report ID of this guest.
-    pub report_id: [u8; 32],
+    pub report_id: ReportId,
     /// Report ID of this guest's migration agent (if applicable).
-    pub report_id_ma: [u8; 32],
+    pub report_id_ma: ReportId,
     /// Reported TCB version used to derive the VCEK that signed this report.
     pub reported_tcb: TcbVersion,
     _reserved_1: [u8; 24],
-    #[serde(with = "BigArray")]
     /// If MaskChipId is set to 0, Identifier unique to the chip.
     /// Otherwise set to 0h.
-    pub chip_id: [u8; 64],
+    pub chip_id: ChipId,
     /// CommittedTCB
     pub committed_tcb: TcbVersion,
     /// The build number of CurrentVersion
@@ -199,323 +634,3654 @@ pub struct AttestationReport {
     pub signature: Signature,
 }
 
+// Report versions 2 and 3 share this 1184-byte wire layout; a change in
+// padding or field types must not silently alter it.
+const_assert!(std::mem::size_of::<Signature>() == 512);
+const_assert!(std::mem::size_of::<AttestationReport>() == 1184);
+
 impl AttestationReport {
     fn author_key_en(&self) -> bool {
-        self._author_key_en == 1
+        self.key_info.author_key_enabled()
     }
-}
 
-impl Default for AttestationReport {
-    fn default() -> Self {
-        Self {
-            version: Default::default(),
-            guest_svn: Default::default(),
-            policy: Default::default(),
-            family_id: Default::default(),
-            image_id: Default::default(),
-            vmpl: Default::default(),
-            sig_algo: Default::default(),
-            current_tcb: Default::default(),
-            plat_info: Default::default(),
-            _author_key_en: Default::default(),
-            _reserved_0: Default::default(),
-            report_data: [0; 64],
-            measurement: [0; 48],
-            host_data: Default::default(),
-            id_key_digest: [0; 48],
-            author_key_digest: [0; 48],
-            report_id: Default::default(),
-            report_id_ma: Default::default(),
-            reported_tcb: Default::default(),
-            _reserved_1: Default::default(),
-            chip_id: [0; 64],
-            committed_tcb: Default::default(),
-            current_build: Default::default(),
-            current_minor: Default::default(),
-            current_major: Default::default(),
-            _reserved_2: Default::default(),
-            committed_build: Default::default(),
-            committed_minor: Default::default(),
-            committed_major: Default::default(),
-            _reserved_3: Default::default(),
-            launch_tcb: Default::default(),
-            _reserved_4: [0; 168],
-            signature: Default::default(),
-        }
+    /// The key information of the report, to branch on the signing key
+    /// with [KeyInfo::signing_key_type].
+    pub fn key_info(&self) -> KeyInfo {
+        self.key_info
     }
-}
 
-impl Display for AttestationReport {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            r#"
-Attestation Report ({} bytes):
-Version:                      {}
-Guest SVN:                    {}
-{}
-Family ID:                    {}
-Image ID:                     {}
-VMPL:                         {}
-Signature Algorithm:          {}
-Current TCB:
-{}
-{}
-Author Key Encryption:        {}
-Report Data:                  {}
-Measurement:                  {}
-Host Data:                    {}
-ID Key Digest:                {}
-Author Key Digest:            {}
-Report ID:                    {}
-Report ID Migration Agent:    {}
-Reported TCB:                 {}
-Chip ID:                      {}
-Committed TCB:
-{}
-Current Build:                {}
-Current Minor:                {}
-Current Major:                {}
-Committed Build:              {}
-Committed Minor:              {}
-Committed Major:              {}
-Launch TCB:
-{}
-{}
-"#,
-            std::mem::size_of_val(self),
-            self.version,
-            self.guest_svn,
-            self.policy,
-            hexdump(&self.family_id),
-            hexdump(&self.image_id),
-            self.vmpl,
-            self.sig_algo,
-            self.current_tcb,
-            self.plat_info,
-            self.author_key_en(),
-            hexdump(&self.report_data),
-            hexdump(&self.measurement),
-            hexdump(&self.host_data),
-            hexdump(&self.id_key_digest),
-            hexdump(&self.author_key_digest),
-            hexdump(&self.report_id),
-            hexdump(&self.report_id_ma),
-            self.reported_tcb,
-            hexdump(&self.chip_id),
-            self.committed_tcb,
-            self.current_build,
-            self.current_minor,
-            self.current_major,
-            self.committed_build,
-            self.committed_minor,
-            self.committed_major,
-            self.launch_tcb,
-            self.signature
-        )
+    /// Decodes the SIG_ALGO field.
+    pub fn sig_algo_type(&self) -> SigAlgo {
+        self.sig_algo.into()
     }
-}
-
-#[cfg(feature = "openssl")]
-impl Verifiable for (&Chain, &AttestationReport) {
-    type Output = ();
-
-    fn verify(self) -> io::Result<Self::Output> {
-        let vcek = self.0.verify()?;
 
-        let sig = EcdsaSig::try_from(&self.1.signature)?;
-        let measurable_bytes: &[u8] = &bincode::serialize(self.1).map_err(|e| {
-            Error::new(
-                ErrorKind::Other,
-                format!("Unable to serialize bytes: {}", e),
-            )
-        })?[..0x2a0];
+    /// A human-readable name of the SIG_ALGO field, for diagnostics.
+    ///
+    /// Unknown encodings are all named `"Unknown"`; use
+    /// [sig_algo_type](Self::sig_algo_type) to get at their value.
+    pub fn sig_algo_name(&self) -> &'static str {
+        match self.sig_algo_type() {
+            SigAlgo::EcdsaP384Sha384 => "ECDSA P-384 SHA-384",
+            SigAlgo::Unknown(0) => "Reserved",
+            SigAlgo::Unknown(_) => "Unknown",
+        }
+    }
 
-        let mut hasher = Sha384::new();
-        hasher.update(measurable_bytes);
-        let base_digest = hasher.finish();
+    /// Whether the guest was launched with an author key: AUTHOR_KEY_EN is
+    /// set and AUTHOR_KEY_DIGEST is non-zero.
+    pub fn author_key_is_present(&self) -> bool {
+        self.author_key_en() && self.author_key_digest != [0u8; 48]
+    }
 
-        let ec = vcek.public_key()?.ec_key()?;
-        let signed = sig.verify(&base_digest, &ec)?;
+    /// Check that AUTHOR_KEY_EN and AUTHOR_KEY_DIGEST agree: the digest must
+    /// be non-zero exactly when the flag is set.
+    pub fn validate_author_key_consistency(&self) -> Result<(), AttestationReportError> {
+        let digest_present = self.author_key_digest != [0u8; 48];
 
-        match signed {
+        match self.author_key_en() == digest_present {
             true => Ok(()),
-            false => Err(Error::new(
-                ErrorKind::Other,
-                "VCEK does not sign the attestation report",
-            )),
+            false => Err(AttestationReportError::AuthorKeyInconsistent {
+                author_key_en: self.author_key_en(),
+            }),
         }
     }
-}
 
-#[cfg(feature = "crypto_nossl")]
-impl Verifiable for (&Chain, &AttestationReport) {
-    type Output = ();
+    /// Read a raw attestation report from a file, as produced by
+    /// [write_to_file](Self::write_to_file) or stored straight from the
+    /// firmware response.
+    ///
+    /// Exactly `size_of::<AttestationReport>()` (0x4A0) bytes are read;
+    /// any trailing data in the file is ignored.
+    pub fn from_file(path: &Path) -> Result<Self, AttestationReportError> {
+        let mut bytes = vec![0u8; std::mem::size_of::<Self>()];
+        File::open(path)?.read_exact(&mut bytes)?;
 
-    fn verify(self) -> io::Result<Self::Output> {
-        // According to Chapter 3 of the [Versioned Chip Endorsement Key (VCEK) Certificate and
-        // KDS Interface Specification][spec], the VCEK certificate certifies an ECDSA public key on curve P-384,
-        // and the signature hash algorithm is sha384.
-        // [spec]: https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/57230.pdf
+        Self::try_from(bytes.as_slice())
+    }
 
-        let vcek = self.0.verify()?;
+    /// Converts the report to a [NormalizedReport], decoding the fields of
+    /// later report versions where this version defines them.
+    pub fn into_latest(self) -> NormalizedReport {
+        self.into()
+    }
 
-        let sig = p384::ecdsa::Signature::try_from(&self.1.signature)?;
+    /// Whether the platform completed the alias check. No guest policy
+    /// exempts a guest from it, so the policy is not consulted.
+    ///
+    /// Fails for reports older than version 3, which do not carry
+    /// ALIAS_CHECK_COMPLETE.
+    pub fn platform_alias_check_satisfied(&self) -> Result<bool, AttestationReportError> {
+        let complete = self.into_latest().alias_check_complete.ok_or(
+            AttestationReportError::FieldUnavailable {
+                field: "ALIAS_CHECK_COMPLETE",
+                version: self.version,
+            },
+        )?;
 
-        let measurable_bytes: &[u8] = &bincode::serialize(self.1).map_err(|e| {
-            Error::new(
-                ErrorKind::Other,
-                format!("Unable to serialize bytes: {}", e),
-            )
-        })?[..0x2a0];
+        Ok(complete)
+    }
 
-        use sha2::Digest;
-        let base_digest = sha2::Sha384::new_with_prefix(measurable_bytes);
+    /// Parse a report like `AttestationReport::try_from`, but reject it if
+    /// any of its [reserved_regions](Self::reserved_regions) is not zero.
+    ///
+    /// Firmware newer than this crate may populate bytes which are reserved
+    /// here: lenient parsing ignores them, strict parsing lets a verifier
+    /// refuse reports it cannot fully interpret.
+    pub fn from_bytes_strict(bytes: &[u8]) -> Result<Self, AttestationReportError> {
+        let report = Self::try_from(bytes)?;
 
-        let verifying_key = p384::ecdsa::VerifyingKey::from_sec1_bytes(vcek.public_key_sec1())
-            .map_err(|e| {
-                io::Error::new(
-                    ErrorKind::Other,
-                    format!("failed to deserialize public key from sec1 bytes: {e:?}"),
-                )
-            })?;
+        for (range, region) in report.reserved_regions() {
+            if let Some(index) = region.iter().position(|b| *b != 0) {
+                return Err(AttestationReportError::ReservedNonZero {
+                    offset: range.start + index,
+                });
+            }
+        }
 
-        use p384::ecdsa::signature::DigestVerifier;
-        verifying_key.verify_digest(base_digest, &sig).map_err(|e| {
-            io::Error::new(
-                ErrorKind::Other,
-                format!("VCEK does not sign the attestation report: {e:?}"),
-            )
+        Ok(report)
+    }
+
+    /// Finds an attestation report embedded in a larger blob, such as a
+    /// vTPM NV index or an HCL report, and returns its offset along with the
+    /// parsed report.
+    ///
+    /// Every offset holding VERSION 2 or 3 and SIG_ALGO 1 (ECDSA P-384 with
+    /// SHA-384), followed by a full report, is a candidate; the first one
+    /// passing [from_bytes_strict](Self::from_bytes_strict) is returned.
+    ///
+    /// Fails with [ReportSearchError::NotFound] if there is no candidate, and
+    /// with the error of the first candidate otherwise.
+    pub fn find_in(blob: &[u8]) -> Result<(usize, Self), ReportSearchError> {
+        Self::find_in_with(blob, |_, _| Ok(()))
+    }
+
+    /// Like [find_in](Self::find_in), only accepting a candidate whose
+    /// signature verifies against `chain`. Fails with
+    /// [ReportSearchError::InvalidSignature] if the first candidate parsing
+    /// strictly is not signed by the chain's VCEK or VLEK.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    pub fn find_in_verified(
+        blob: &[u8],
+        chain: &Chain,
+    ) -> Result<(usize, Self), ReportSearchError> {
+        Self::find_in_with(blob, |offset, report| {
+            (chain, report)
+                .verify()
+                .map_err(|error| ReportSearchError::InvalidSignature { offset, error })
         })
     }
-}
 
-bitfield! {
-    /// The firmware associates each guest with a guest policy that the guest owner provides. The
-    /// firmware restricts what actions the hypervisor can take on this guest according to the guest policy.
-    /// The policy also indicates the minimum firmware version to for the guest.
+    fn find_in_with<F>(blob: &[u8], accept: F) -> Result<(usize, Self), ReportSearchError>
+    where
+        F: Fn(usize, &Self) -> Result<(), ReportSearchError>,
+    {
+        let size = std::mem::size_of::<Self>();
+        let word = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        let mut rejected = None;
+
+        for (offset, candidate) in blob.windows(size).enumerate() {
+            if !matches!(word(candidate), 2 | NormalizedReport::V3)
+                || word(&candidate[0x34..]) != u32::from(SigAlgo::EcdsaP384Sha384)
+            {
+                continue;
+            }
+
+            let report = Self::from_bytes_strict(candidate)
+                .map_err(|error| ReportSearchError::InvalidCandidate { offset, error })
+                .and_then(|report| accept(offset, &report).map(|_| report));
+
+            match report {
+                Ok(report) => return Ok((offset, report)),
+                Err(error) => {
+                    rejected.get_or_insert(error);
+                }
+            }
+        }
+
+        Err(rejected.unwrap_or(ReportSearchError::NotFound))
+    }
+
+    /// The reserved byte ranges of the report, by offset, with their
+    /// contents.
     ///
-    /// The guest owner provides the guest policy to the firmware during launch. The firmware then binds
-    /// the policy to the guest. The policy cannot be changed throughout the lifetime of the guest. The
-    /// policy is also migrated with the guest and enforced by the destination platform firmware.
+    /// Bytes which the report's version defines (e.g. the version 3 CPUID
+    /// fields) are not included.
+    pub fn reserved_regions(&self) -> Vec<(Range<usize>, &[u8])> {
+        let reserved_1 = match self.version >= NormalizedReport::V3 {
+            true => &self._reserved_1[3..],
+            false => &self._reserved_1[..],
+        };
+
+        let regions: [(usize, &[u8]); 6] = [
+            (0x4c, &self._reserved_0),
+            (0x1a0 - reserved_1.len(), reserved_1),
+            (0x1eb, std::slice::from_ref(&self._reserved_2)),
+            (0x1ef, std::slice::from_ref(&self._reserved_3)),
+            (0x1f8, &self._reserved_4),
+            (0x330, self.signature.reserved()),
+        ];
+
+        regions
+            .iter()
+            .map(|(offset, bytes)| (*offset..*offset + bytes.len(), *bytes))
+            .collect()
+    }
+
+    /// Whether any reserved byte of the report is set.
+    pub fn has_nonzero_reserved(&self) -> bool {
+        self.reserved_regions()
+            .iter()
+            .any(|(_, bytes)| bytes.iter().any(|b| *b != 0))
+    }
+
+    /// Whether the guest policy requires at least the given ABI version.
+    pub fn guest_policy_check_abi(&self, required_major: u8, required_minor: u8) -> bool {
+        let present = (self.policy.abi_major() as u8, self.policy.abi_minor() as u8);
+
+        present >= (required_major, required_minor)
+    }
+
+    /// The features of the firmware which produced the report, by its
+    /// CURRENT_MAJOR, CURRENT_MINOR and CURRENT_BUILD. Guests cannot query
+    /// the platform status, but learn the firmware version this way.
+    pub fn firmware_capabilities(&self) -> FirmwareCapabilities {
+        let version = Version {
+            major: self.current_major,
+            minor: self.current_minor,
+        };
+
+        FirmwareCapabilities::new(version, u32::from(self.current_build))
+    }
+
+    /// Whether the report was requested at exactly the `expected` VMPL.
     ///
-    /// | Bit(s) | Name              | Description                                                                                                        >
-    /// |--------|-------------------|-------------------------------------------------------------------------------------------------------------------->
-    /// | 7:0    | ABI_MINOR         | The minimum ABI minor version required for this guest to run.                                                      >
-    /// | 15:8   | ABI_MAJOR         | The minimum ABI major version required for this guest to run.                                                      >
-    /// | 16     | SMT               | 0: Host SMT usage is disallowed.<br>1: Host SMT usage is allowed.                                                  >
-    /// | 17     | -                 | Reserved. Must be one.                                                                                             >
-    /// | 18     | MIGRATE_MA        | 0: Association with a migration agent is disallowed.<br>1: Association with a migration agent is allowed           >
-    /// | 19     | DEBUG             | 0: Debugging is disallowed.<br>1: Debugging is allowed.                                                            >
-    /// | 20     | SINGLE_SOCKET     | 0: Guest can be activated on multiple sockets.<br>1: Guest can only be activated on one socket.                    >
-    /// | 21     | CXL_ALLOW         | 0: CXL cannot be populated with devices or memory.<br>1: CXL can be populated with devices or memory.              >
-    /// | 22     | MEM_AES_256_XTS   | 0: Allow either AES 128 XEX or AES 256 XTS for memory encryption.<br>1: Require AES 256 XTS for memory encryption. >
-    /// | 23     | RAPL_DIS          | 0: Allow Running Average Power Limit (RAPL).<br>1: RAPL must be disabled.                                          >
-    /// | 24     | CIPHERTEXT_HIDING | 0: Ciphertext hiding may be enabled or disabled.<br>1: Ciphertext hiding must be enabled.                          >
-    /// | 63:25  | -                 | Reserved. MBZ.                                                                                                     >
+    /// A report at VMPL 2 comes from a less privileged context than VMPL 1,
+    /// so a verifier expecting VMPL 1 must reject it. Out-of-range VMPLs
+    /// (above 3) never match.
+    pub fn vmpl_matches(&self, expected: u32) -> bool {
+        expected <= MAX_VMPL && self.vmpl == expected
+    }
+
+    /// Whether the report was requested at `maximum` or a more privileged
+    /// (lower) VMPL. Out-of-range VMPLs (above 3) never match.
+    pub fn vmpl_is_at_most(&self, maximum: u32) -> bool {
+        maximum <= MAX_VMPL && self.vmpl <= maximum
+    }
+
+    /// Whether REPORT_ID_MA is populated, i.e. the guest is associated
+    /// with a migration agent (MA).
     ///
-    #[derive(Default, Clone, Copy,Eq, PartialEq)]
-    #[derive(Deserialize, Serialize)]
-    #[repr(C)]
-    pub struct GuestPolicy(u64);
-    impl Debug;
-    /// ABI_MINOR field: Indicates the minor API version.
-    pub abi_minor, set_abi_minor: 7, 0;
-    /// ABI_MAJOR field: Indicates the minor API version.
-    pub abi_major, set_abi_major: 15, 8;
-    /// SMT_ALLOWED field: Indicates the if SMT should be permitted.
-    pub smt_allowed, set_smt_allowed: 16, 16;
-    /// MIGRATE_MA_ALLOWED field: Indicates the if migration is permitted with
-    /// the migration agent.
-    pub migrate_ma_allowed, set_migrate_ma_allowed: 18, 18;
-    /// DEBUG_ALLOWED field: Indicates the if debugging should is permitted.
-    pub debug_allowed, set_debug_allowed: 19, 19;
-    /// SINGLE_SOCKET_REQUIRED field: Indicates the if a single socket is required.
-    pub single_socket_required, set_single_socket_required: 20, 20;
-    /// CXL_ALLOW field: (1) can populate CXL devices/memory, (0) cannot populate CXL devices/memory
-    pub cxl_allowed, set_cxl_allowed: 21, 21;
-    /// MEM_AES_256_XTS field: (1) require AES 256 XTS encryption, (0) allows either AES 128 XEX or AES 256 XTS encryption
-    pub mem_aes_256_xts, set_mem_aes_256_xts: 22, 22;
-    /// RAPL_DIS field: (1) RAPL must be disabled, (0) allow RAPL
-    pub rapl_dis, set_rapl_dis: 23, 23;
-    /// CIPHERTEXT_HIDING field: (1) ciphertext hiding must be enabled, (0) ciphertext hiding may be enabled/disabled
-    pub ciphertext_hiding, set_ciphertext_hiding: 24, 24;
+    /// This only says the MA's report ID is present, not that the guest was
+    /// actually migrated during this boot. Whether an MA was permitted at
+    /// all is given by `policy.migrate_ma_allowed()`.
+    ///
+    /// The firmware reports an absent MA as all ones, so both all-zero and
+    /// all-ones values are treated as no MA.
+    pub fn is_migrated(&self) -> bool {
+        let id: &[u8] = self.report_id_ma.as_ref();
+
+        id.iter().any(|b| *b != 0) && id.iter().any(|b| *b != 0xff)
+    }
+
+    /// Check the guest policy embedded in the report against the policy
+    /// required by the caller, returning the first violation found.
+    ///
+    /// The guest ABI version must be at least the required one. Every
+    /// permission (SMT, migration agent, debug, CXL) granted by the report
+    /// must also be granted by `policy`, and every restriction (single
+    /// socket, AES 256 XTS, RAPL disabled, ciphertext hiding) required by
+    /// `policy` must also be required by the report.
+    pub fn check_policy(&self, policy: &GuestPolicy) -> Result<(), PolicyViolation> {
+        match self.policy.violations(policy).into_iter().next() {
+            Some(violation) => Err(violation),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether FAMILY_ID, IMAGE_ID and HOST_DATA all match the expected
+    /// launch configuration. See [check_launch_config](Self::check_launch_config).
+    pub fn matches_launch_config(
+        &self,
+        family_id: &[u8; 16],
+        image_id: &[u8; 16],
+        host_data: &[u8; 32],
+    ) -> bool {
+        self.check_launch_config(family_id, image_id, host_data)
+            .is_ok()
+    }
+
+    /// Compare FAMILY_ID, IMAGE_ID and HOST_DATA against the expected
+    /// launch configuration, returning every field which differs.
+    ///
+    /// All three fields are compared in constant time, whether or not an
+    /// earlier one differs.
+    pub fn check_launch_config(
+        &self,
+        family_id: &[u8; 16],
+        image_id: &[u8; 16],
+        host_data: &[u8; 32],
+    ) -> Result<(), Vec<LaunchConfigError>> {
+        let fields = [
+            (
+                self.family_id.ct_eq(family_id),
+                LaunchConfigError::FamilyIdMismatch,
+            ),
+            (
+                self.image_id.ct_eq(image_id),
+                LaunchConfigError::ImageIdMismatch,
+            ),
+            (
+                self.host_data.ct_eq(host_data),
+                LaunchConfigError::HostDataMismatch,
+            ),
+        ];
+
+        let mismatches: Vec<LaunchConfigError> = fields
+            .iter()
+            .filter(|(equal, _)| !bool::from(*equal))
+            .map(|(_, error)| *error)
+            .collect();
+
+        match mismatches.is_empty() {
+            true => Ok(()),
+            false => Err(mismatches),
+        }
+    }
+
+    /// Size of the region covered by the report signature (bytes 0h to 29Fh).
+    pub const SIGNED_LEN: usize = 0x2a0;
+
+    /// The raw report bytes covered by the signature, i.e. everything
+    /// preceding the signature field.
+    ///
+    /// This is the exact input to the SHA-384 digest signed by the VCEK or
+    /// VLEK, for use with external signers and verifiers. The length is
+    /// [SIGNED_LEN](Self::SIGNED_LEN) for the current report format and may
+    /// differ should a future version grow the signed region.
+    pub fn signed_bytes(&self) -> Result<Vec<u8>, AttestationReportError> {
+        // The report is laid out as on the wire (see the size assertions
+        // below its definition), so its memory is sliced as is.
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<Self>());
+        bytes.save(self)?;
+        bytes.truncate(Self::SIGNED_LEN);
+
+        Ok(bytes)
+    }
+
+    /// The KDS URL of the VCEK certifying this report, for the product,
+    /// chip and reported TCB found in the report.
+    ///
+    /// The product is decoded from the CPUID fields (see
+    /// [NormalizedReport::product]). Fails with
+    /// [ChipIdError::UnknownProduct] for reports older than version 3, which
+    /// do not carry them, and for processors the KDS does not serve; use
+    /// [kds_vcek_url_for](Self::kds_vcek_url_for) there.
+    ///
+    /// Fails with [ChipIdError::ChipIdMasked] if the firmware masked the
+    /// chip ID, as the KDS cannot look up a VCEK without it.
+    pub fn kds_vcek_url(&self) -> Result<String, ChipIdError> {
+        let product = self
+            .into_latest()
+            .product()
+            .ok_or(ChipIdError::UnknownProduct)?;
+
+        self.kds_vcek_url_for(product)
+    }
+
+    /// The KDS URL of the VCEK certifying this report, for the chip and
+    /// reported TCB found in the report, on the processor `product`.
+    ///
+    /// Only SEV-SNP capable generations (Milan, Genoa) are served by the
+    /// KDS.
+    ///
+    /// Fails with [ChipIdError::ChipIdMasked] if the firmware masked the
+    /// chip ID, as the KDS cannot look up a VCEK without it.
+    pub fn kds_vcek_url_for(&self, product: Generation) -> Result<String, ChipIdError> {
+        if self.chip_id_is_masked() {
+            return Err(ChipIdError::ChipIdMasked);
+        }
+
+        Ok(format!(
+            "{KDS_CERT_SITE}/vcek/v1/{}/{}?blSPL={:02}&teeSPL={:02}&snpSPL={:02}&ucodeSPL={:02}",
+            product.titlecase(),
+            self.chip_id_hex(),
+            self.reported_tcb.bootloader,
+            self.reported_tcb.tee,
+            self.reported_tcb.snp,
+            self.reported_tcb.microcode,
+        ))
+    }
+
+    /// Whether CHIP_ID is all zeroes, as the firmware reports it when the
+    /// hypervisor set MaskChipId.
+    ///
+    /// A masked chip ID cannot identify the VCEK: when this is true,
+    /// [kds_vcek_url](Self::kds_vcek_url) fails, and the VCEK must be
+    /// obtained from the host, e.g. through an extended report.
+    pub fn chip_id_is_masked(&self) -> bool {
+        self.chip_id.is_masked()
+    }
+
+    /// The chip ID as a continuous lowercase hex string, as expected by
+    /// the KDS and most tooling.
+    pub fn chip_id_hex(&self) -> String {
+        hex::encode(self.chip_id)
+    }
+
+    /// The MEASUREMENT as lowercase hex, without separators.
+    pub fn measurement_as_hex(&self) -> String {
+        hex::encode(self.measurement)
+    }
+
+    /// The CHIP_ID as lowercase hex, without separators, as expected by the
+    /// KDS. Same as [chip_id_hex](Self::chip_id_hex).
+    pub fn chip_id_as_hex(&self) -> String {
+        self.chip_id_hex()
+    }
+
+    /// The REPORT_ID as lowercase hex, without separators.
+    pub fn report_id_as_hex(&self) -> String {
+        hex::encode(self.report_id)
+    }
+
+    /// The REPORT_ID_MA as lowercase hex, without separators.
+    pub fn report_id_ma_as_hex(&self) -> String {
+        hex::encode(self.report_id_ma)
+    }
+
+    /// The ID_KEY_DIGEST as lowercase hex, without separators.
+    pub fn id_key_digest_as_hex(&self) -> String {
+        hex::encode(self.id_key_digest)
+    }
+
+    /// The AUTHOR_KEY_DIGEST as lowercase hex, without separators.
+    pub fn author_key_digest_as_hex(&self) -> String {
+        hex::encode(self.author_key_digest)
+    }
+
+    /// The HOST_DATA as lowercase hex, without separators.
+    pub fn host_data_as_hex(&self) -> String {
+        hex::encode(self.host_data)
+    }
+
+    /// The KDS URL of the ASVK/ARK chain certifying VLEKs on the product
+    /// found in the report.
+    ///
+    /// VLEKs are not tied to a chip and are provisioned by the cloud
+    /// provider rather than downloaded, so only the chain above them can be
+    /// fetched from the KDS. Fails with [ChipIdError::UnknownProduct] like
+    /// [kds_vcek_url](Self::kds_vcek_url).
+    pub fn kds_vlek_url(&self) -> Result<String, ChipIdError> {
+        let product = self
+            .into_latest()
+            .product()
+            .ok_or(ChipIdError::UnknownProduct)?;
+
+        Ok(Self::kds_vlek_url_for(product))
+    }
+
+    /// The KDS URL of the ASVK/ARK chain certifying VLEKs for `product`,
+    /// which is all the URL depends on.
+    pub fn kds_vlek_url_for(product: Generation) -> String {
+        format!("{KDS_CERT_SITE}/vlek/v1/{}/cert_chain", product.titlecase())
+    }
+
+    /// List the fields whose values differ between two reports, including
+    /// the reserved ones. Byte arrays are rendered as hex.
+    ///
+    /// Useful for golden-report regression tests, where `assert_eq!` alone
+    /// does not tell which field changed.
+    pub fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        fn bytes<T: AsRef<[u8]>>(value: &T) -> String {
+            hex::encode(value)
+        }
+
+        fn debug<T: std::fmt::Debug>(value: &T) -> String {
+            format!("{value:?}")
+        }
+
+        fn hex_word<T: Copy + Into<u64>>(value: &T) -> String {
+            format!("{:#x}", (*value).into())
+        }
+
+        let mut diffs = vec![];
+
+        macro_rules! diff_fields {
+            ($($field:ident => $render:expr),* $(,)?) => {
+                $(
+                    if self.$field != other.$field {
+                        diffs.push(FieldDiff {
+                            field: stringify!($field),
+                            left: $render(&self.$field),
+                            right: $render(&other.$field),
+                        });
+                    }
+                )*
+            };
+        }
+
+        diff_fields!(
+            version => debug,
+            guest_svn => debug,
+            policy => |p: &GuestPolicy| hex_word(&p.0),
+            family_id => bytes,
+            image_id => bytes,
+            vmpl => debug,
+            sig_algo => debug,
+            current_tcb => debug,
+            plat_info => |p: &PlatformInfo| hex_word(&p.0),
+            key_info => |k: &KeyInfo| hex_word(&k.0),
+            _reserved_0 => |r: &[u8; 4]| hex_word(&u32::from_le_bytes(*r)),
+            report_data => bytes,
+            measurement => bytes,
+            host_data => bytes,
+            id_key_digest => bytes,
+            author_key_digest => bytes,
+            report_id => bytes,
+            report_id_ma => bytes,
+            reported_tcb => debug,
+            _reserved_1 => bytes,
+            chip_id => bytes,
+            committed_tcb => debug,
+            current_build => debug,
+            current_minor => debug,
+            current_major => debug,
+            _reserved_2 => hex_word,
+            committed_build => debug,
+            committed_minor => debug,
+            committed_major => debug,
+            _reserved_3 => hex_word,
+            launch_tcb => debug,
+            _reserved_4 => bytes,
+            signature => |s: &Signature| format!("{}{}", hex::encode(s.r()), hex::encode(s.s())),
+        );
+
+        diffs
+    }
+
+    /// The report formatted as `snpguest report display` prints it.
+    ///
+    /// `snpguest` prints the [Display] output of this crate, so the two are
+    /// the same; this pins that format so that it only changes knowingly.
+    pub fn pretty_print(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serialize the report to a JSON string.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Deserialize a report from a JSON string, as produced by
+    /// [to_json](Self::to_json).
+    #[cfg(feature = "serde_json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Deserialize a report from JSON like [from_json](Self::from_json),
+    /// but reject anything this version of the crate would not produce:
+    /// unknown or missing fields, arrays of the wrong length and unknown
+    /// report versions. Errors name the offending JSON pointer.
+    ///
+    /// Services exchanging reports can negotiate on
+    /// [REPORT_JSON_SCHEMA_VERSION] first.
+    #[cfg(feature = "serde_json")]
+    pub fn from_json_strict(json: &str) -> Result<Self, JsonReportError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let schema = serde_json::to_value(Self::default())?;
+
+        check_json_shape(&schema, &value, "")?;
+
+        let report: Self = serde_json::from_value(value)?;
+
+        if snp_attestation_report_size(report.version).is_none() {
+            return Err(JsonReportError::UnsupportedVersion(report.version));
+        }
+
+        Ok(report)
+    }
+
+    /// Encode the report as a CBOR byte string holding the raw report
+    /// bytes, for compact transport in attestation tokens.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Result<Vec<u8>, CborError> {
+        let bytes = bincode::serialize(self).map_err(|e| CborError::Report((*e).into()))?;
+
+        let mut cbor = Vec::with_capacity(bytes.len() + 3);
+        ciborium::into_writer(&serde_bytes::Bytes::new(&bytes), &mut cbor)?;
+
+        Ok(cbor)
+    }
+
+    /// Decode a report from CBOR, as produced by [to_cbor](Self::to_cbor).
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(cbor: &[u8]) -> Result<Self, CborError> {
+        let bytes: serde_bytes::ByteBuf = ciborium::from_reader(cbor)?;
+
+        if bytes.len() != std::mem::size_of::<Self>() {
+            return Err(CborError::InvalidLength(bytes.len()));
+        }
+
+        Self::try_from(bytes.as_slice()).map_err(CborError::Report)
+    }
+
+    /// Flatten the report into claims for policy engines such as OPA or CEL.
+    ///
+    /// Bitfields are expanded into individual claims and byte arrays are
+    /// encoded as lowercase hex. The signature is not included. TCBs are
+    /// decoded for the processor family (see [NormalizedReport]), and claims
+    /// a report version or processor does not define are absent rather than
+    /// null. Keys are stable:
+    ///
+    /// | Key | Value |
+    /// |-----|-------|
+    /// | `report_version`, `guest_svn`, `vmpl`, `sig_algo` | integer |
+    /// | `policy.abi_major`, `policy.abi_minor` | integer |
+    /// | `policy.smt_allowed`, `policy.migrate_ma_allowed`, `policy.debug_allowed`, `policy.single_socket_required`, `policy.cxl_allowed`, `policy.mem_aes_256_xts`, `policy.rapl_dis`, `policy.ciphertext_hiding` | boolean |
+    /// | `platform_info.smt_enabled`, `platform_info.tsme_enabled`, `platform_info.ecc_enabled`, `platform_info.rapl_disabled`, `platform_info.ciphertext_hiding_enabled` | boolean |
+    /// | `key_info.author_key_en`, `key_info.mask_chip_key` | boolean |
+    /// | `key_info.signing_key` | integer |
+    /// | `tcb.{current,reported,committed,launch}.{bootloader,tee,snp,microcode}` | integer |
+    /// | `tcb.{current,reported,committed,launch}.fmc` (Turin and later) | integer |
+    /// | `cpuid_fam_id`, `cpuid_mod_id`, `cpuid_step` (version 3 and later) | integer |
+    /// | `platform_info.alias_check_complete` (version 3 and later) | boolean |
+    /// | `firmware.{current,committed}.{major,minor,build}` | integer |
+    /// | `family_id`, `image_id`, `report_data`, `measurement`, `host_data`, `id_key_digest`, `author_key_digest`, `report_id`, `report_id_ma`, `chip_id` | hex string |
+    #[cfg(feature = "serde_json")]
+    pub fn to_claims(&self) -> BTreeMap<String, serde_json::Value> {
+        use serde_json::Value;
+
+        fn claim<V: Into<Value>>(claims: &mut BTreeMap<String, Value>, key: &str, value: V) {
+            claims.insert(key.to_string(), value.into());
+        }
+
+        fn tcb(claims: &mut BTreeMap<String, Value>, name: &str, tcb: &TcbComponents) {
+            if let Some(fmc) = tcb.fmc {
+                claim(claims, &format!("tcb.{name}.fmc"), fmc);
+            }
+            claim(claims, &format!("tcb.{name}.bootloader"), tcb.bootloader);
+            claim(claims, &format!("tcb.{name}.tee"), tcb.tee);
+            claim(claims, &format!("tcb.{name}.snp"), tcb.snp);
+            claim(claims, &format!("tcb.{name}.microcode"), tcb.microcode);
+        }
+
+        let latest = self.into_latest();
+        let mut claims = BTreeMap::new();
+        let c = &mut claims;
+
+        claim(c, "report_version", self.version);
+        claim(c, "guest_svn", self.guest_svn);
+        claim(c, "vmpl", self.vmpl);
+        claim(c, "sig_algo", self.sig_algo);
+
+        let policy = &self.policy;
+        claim(c, "policy.abi_major", policy.abi_major());
+        claim(c, "policy.abi_minor", policy.abi_minor());
+        claim(c, "policy.smt_allowed", policy.smt_allowed() == 1);
+        claim(
+            c,
+            "policy.migrate_ma_allowed",
+            policy.migrate_ma_allowed() == 1,
+        );
+        claim(c, "policy.debug_allowed", policy.debug_allowed() == 1);
+        claim(
+            c,
+            "policy.single_socket_required",
+            policy.single_socket_required() == 1,
+        );
+        claim(c, "policy.cxl_allowed", policy.cxl_allowed() == 1);
+        claim(c, "policy.mem_aes_256_xts", policy.mem_aes_256_xts() == 1);
+        claim(c, "policy.rapl_dis", policy.rapl_dis() == 1);
+        claim(
+            c,
+            "policy.ciphertext_hiding",
+            policy.ciphertext_hiding() == 1,
+        );
+
+        let plat_info = &self.plat_info;
+        claim(c, "platform_info.smt_enabled", plat_info.is_smt_enabled());
+        claim(c, "platform_info.tsme_enabled", plat_info.is_tsme_enabled());
+        claim(c, "platform_info.ecc_enabled", plat_info.is_ecc_enabled());
+        claim(
+            c,
+            "platform_info.rapl_disabled",
+            plat_info.is_rapl_disabled(),
+        );
+        claim(
+            c,
+            "platform_info.ciphertext_hiding_enabled",
+            plat_info.is_ciphertext_hiding_enabled(),
+        );
+        if let Some(complete) = latest.alias_check_complete {
+            claim(c, "platform_info.alias_check_complete", complete);
+        }
+
+        let cpuid = [
+            ("cpuid_fam_id", latest.cpuid_fam_id),
+            ("cpuid_mod_id", latest.cpuid_mod_id),
+            ("cpuid_step", latest.cpuid_step),
+        ];
+        for (key, value) in cpuid {
+            if let Some(value) = value {
+                claim(c, key, value);
+            }
+        }
+
+        claim(c, "key_info.author_key_en", self.author_key_en());
+        claim(
+            c,
+            "key_info.mask_chip_key",
+            self.key_info.mask_chip_key() == 1,
+        );
+        claim(c, "key_info.signing_key", self.key_info.signing_key());
+
+        tcb(c, "current", &latest.current_tcb());
+        tcb(c, "reported", &latest.reported_tcb());
+        tcb(c, "committed", &latest.committed_tcb());
+        tcb(c, "launch", &latest.launch_tcb());
+
+        claim(c, "firmware.current.major", self.current_major);
+        claim(c, "firmware.current.minor", self.current_minor);
+        claim(c, "firmware.current.build", self.current_build);
+        claim(c, "firmware.committed.major", self.committed_major);
+        claim(c, "firmware.committed.minor", self.committed_minor);
+        claim(c, "firmware.committed.build", self.committed_build);
+
+        claim(c, "family_id", hex::encode(self.family_id));
+        claim(c, "image_id", hex::encode(self.image_id));
+        claim(c, "report_data", hex::encode(self.report_data));
+        claim(c, "measurement", hex::encode(self.measurement));
+        claim(c, "host_data", hex::encode(self.host_data));
+        claim(c, "id_key_digest", hex::encode(self.id_key_digest));
+        claim(c, "author_key_digest", hex::encode(self.author_key_digest));
+        claim(c, "report_id", hex::encode(self.report_id));
+        claim(c, "report_id_ma", hex::encode(self.report_id_ma));
+        claim(c, "chip_id", self.chip_id_hex());
+
+        claims
+    }
+
+    /// Write the raw attestation report bytes to a file.
+    pub fn write_to_file(&self, path: &Path) -> Result<(), AttestationReportError> {
+        let bytes = bincode::serialize(self).map_err(|e| *e)?;
+        File::create(path)?.write_all(&bytes)?;
+
+        Ok(())
+    }
 }
 
-impl Display for GuestPolicy {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            r#"
-    Guest Policy ({}):
-    ABI Major:     {}
-    ABI Minor:     {}
-    SMT Allowed:   {}
-    Migrate MA:    {}
-    Debug Allowed: {}
-    Single Socket: {}"#,
-            self.0,
-            self.abi_major(),
-            self.abi_minor(),
-            self.smt_allowed(),
-            self.migrate_ma_allowed(),
-            self.debug_allowed(),
-            self.single_socket_required()
-        )
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl AttestationReport {
+    /// Check that the report was signed with the kind of key the chain
+    /// ends in (VCEK or VLEK), before verifying the signature.
+    ///
+    /// A mismatch otherwise only surfaces as a failed signature check.
+    pub fn signing_key_matches_cert_chain(
+        &self,
+        chain: &Chain,
+    ) -> Result<(), AttestationReportError> {
+        let report = self.key_info.signing_key_type();
+        let chain =
+            vek_cert_type(&chain.vek).ok_or(AttestationReportError::UnknownLeafCertificate)?;
+
+        match (report, &chain) {
+            (SigningKey::Vcek, CertType::VCEK) | (SigningKey::Vlek, CertType::VLEK) => Ok(()),
+            _ => Err(AttestationReportError::KeyTypeMismatch { report, chain }),
+        }
+    }
+
+    /// Name of the endorsement key the report claims to be signed with.
+    ///
+    /// Reports without a signature, or with a reserved signing key, are
+    /// rejected up front with a [ReportSignatureError] rather than running
+    /// ECDSA over a zeroed signature.
+    fn signing_key_name(&self) -> io::Result<&'static str> {
+        match self.key_info.signing_key_type() {
+            SigningKey::Vcek => Ok("VCEK"),
+            SigningKey::Vlek => Ok("VLEK"),
+            SigningKey::None => Err(Error::new(
+                ErrorKind::InvalidData,
+                ReportSignatureError::SignatureMissing,
+            )),
+            SigningKey::Reserved(key) => Err(Error::new(
+                ErrorKind::InvalidData,
+                ReportSignatureError::UnknownSigningKey(key),
+            )),
+        }
     }
 }
 
-impl From<GuestPolicy> for u64 {
-    fn from(value: GuestPolicy) -> Self {
-        value.0
+/// A field which differs between two attestation reports, as returned by
+/// [AttestationReport::diff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The rendered value in the report `diff` was called on.
+    pub left: String,
+    /// The rendered value in the report passed to `diff`.
+    pub right: String,
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.left, self.right)
     }
 }
 
-bitfield! {
-    /// A structure with a bit-field unsigned 64 bit integer:
-    /// Bit 0 representing the status of SMT enablement.
-    /// Bit 1 representing the status of TSME enablement.
-    /// Bit 2 indicates if ECC memory is used.
-    /// Bit 3 indicates if RAPL is disabled.
-    /// Bit 4 indicates if ciphertext hiding is enabled
-    /// Bits 5-63 are reserved.
-    #[derive(Default, Clone, Copy)]
-    #[derive(Deserialize, Serialize)]
-    #[repr(C)]
-    pub struct PlatformInfo(u64);
-    impl Debug;
-    /// Returns the bit state of SMT
-    pub smt_enabled, _: 0, 0;
-    /// Returns the bit state of TSME.
-    pub tsme_enabled, _: 1, 1;
-    /// Indicates that the platform is currently using ECC memory
-    pub ecc_enabled, _: 2, 2;
-    /// Indicates that the RAPL feature is disabled
-    pub rapl_disabled, _: 3, 3;
-    /// Indicates that ciphertext hiding is enabled
-    pub ciphertext_hiding_enabled, _: 4, 4;
-    /// reserved
-    reserved, _: 5, 63;
+/// Serializes a reserved 4-byte field as the little-endian `u32` it is
+/// defined as, keeping the encoding of earlier releases.
+mod reserved_word {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error> {
+        u32::from_le_bytes(*bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 4], D::Error> {
+        u32::deserialize(deserializer).map(u32::to_le_bytes)
+    }
 }
 
-impl Display for PlatformInfo {
+/// An [AttestationReport] with the fields added by later report versions
+/// decoded, so that consumers can handle every version alike.
+///
+/// Fields which the source report version does not define are `None`:
+/// they are never read from what that version reserves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NormalizedReport {
+    /// The report as parsed, holding the fields common to every version.
+    pub report: AttestationReport,
+    /// CPUID family ID of the CPU the guest runs on (version 3 and later).
+    pub cpuid_fam_id: Option<u8>,
+    /// CPUID model ID of the CPU the guest runs on (version 3 and later).
+    pub cpuid_mod_id: Option<u8>,
+    /// CPUID stepping of the CPU the guest runs on (version 3 and later).
+    pub cpuid_step: Option<u8>,
+    /// Whether the alias check has completed, from PLATFORM_INFO
+    /// (version 3 and later).
+    pub alias_check_complete: Option<bool>,
+}
+
+impl NormalizedReport {
+    /// The first report version defining the CPUID and alias check fields.
+    pub const V3: u32 = 3;
+
+    /// The version of the report this was converted from.
+    pub fn source_version(&self) -> u32 {
+        self.report.version
+    }
+
+    /// Whether the TCB fields use the Turin layout, with an FMC SVN. Only
+    /// known from version 3, which carries the CPUID family.
+    pub fn has_fmc_tcb(&self) -> bool {
+        self.cpuid_fam_id
+            .is_some_and(|family| family >= TcbComponents::FMC_FAMILY)
+    }
+
+    fn tcb_components(&self, tcb: TcbVersion) -> TcbComponents {
+        TcbComponents::from_raw(u64::from_le_bytes(tcb.to_le_bytes()), self.has_fmc_tcb())
+    }
+
+    /// CURRENT_TCB, decoded for the processor family.
+    pub fn current_tcb(&self) -> TcbComponents {
+        self.tcb_components(self.report.current_tcb)
+    }
+
+    /// REPORTED_TCB, decoded for the processor family.
+    pub fn reported_tcb(&self) -> TcbComponents {
+        self.tcb_components(self.report.reported_tcb)
+    }
+
+    /// COMMITTED_TCB, decoded for the processor family.
+    pub fn committed_tcb(&self) -> TcbComponents {
+        self.tcb_components(self.report.committed_tcb)
+    }
+
+    /// LAUNCH_TCB, decoded for the processor family.
+    pub fn launch_tcb(&self) -> TcbComponents {
+        self.tcb_components(self.report.launch_tcb)
+    }
+
+    /// The processor generation the guest runs on, as named by the KDS,
+    /// decoded from the CPUID family and model.
+    ///
+    /// `None` for reports older than version 3, which do not carry the
+    /// CPUID fields, and for processors the KDS does not serve.
+    pub fn product(&self) -> Option<Generation> {
+        match (self.cpuid_fam_id?, self.cpuid_mod_id?) {
+            (0x19, 0x00..=0x0f) => Some(Generation::Milan),
+            (0x19, 0x10..=0x1f | 0xa0..=0xaf) => Some(Generation::Genoa),
+            _ => None,
+        }
+    }
+
+    /// A human-readable name of the processor the guest runs on, decoded
+    /// from the CPUID family, model and stepping, e.g.
+    /// `"AMD EPYC 9004 Series (Genoa)"`.
+    ///
+    /// Unknown processors are named `"Unknown (fam=0x.. mod=0x.. step=0x..)"`.
+    /// Reports older than version 3 do not carry the CPUID fields and are
+    /// always named as unknown.
+    pub fn cpuid_model_string(&self) -> String {
+        let (family, model, stepping) =
+            match (self.cpuid_fam_id, self.cpuid_mod_id, self.cpuid_step) {
+                (Some(family), Some(model), Some(stepping)) => (family, model, stepping),
+                _ => {
+                    return format!(
+                        "Unknown (report version {} has no CPUID fields)",
+                        self.source_version()
+                    )
+                }
+            };
+
+        let name = match (family, model) {
+            (0x17, 0x00..=0x0f) => "AMD EPYC 7001 Series (Naples)",
+            (0x17, 0x30..=0x3f) => "AMD EPYC 7002 Series (Rome)",
+            (0x19, 0x00..=0x0f) => "AMD EPYC 7003 Series (Milan)",
+            (0x19, 0x10..=0x1f) => "AMD EPYC 9004 Series (Genoa)",
+            (0x19, 0xa0..=0xaf) => "AMD EPYC 97x4/8004 Series (Bergamo/Siena)",
+            (0x1a, 0x00..=0x1f) => "AMD EPYC 9005 Series (Turin)",
+            _ => {
+                return format!("Unknown (fam={family:#04x} mod={model:#04x} step={stepping:#04x})")
+            }
+        };
+
+        name.to_string()
+    }
+}
+
+impl AttestationReport {
+    /// Sets the CPUID fields and the alias check bit defined by version 3,
+    /// for building synthetic reports.
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn set_v3_fields(
+        &mut self,
+        family: u8,
+        model: u8,
+        stepping: u8,
+        alias_check_complete: bool,
+    ) {
+        self._reserved_1[..3].copy_from_slice(&[family, model, stepping]);
+        self.plat_info.0 = (self.plat_info.0 & !(1 << 5)) | (alias_check_complete as u64) << 5;
+    }
+}
+
+impl From<AttestationReport> for NormalizedReport {
+    fn from(report: AttestationReport) -> Self {
+        let v3 = report.version >= Self::V3;
+        let field = |value| if v3 { Some(value) } else { None };
+
+        Self {
+            cpuid_fam_id: field(report._reserved_1[0]),
+            cpuid_mod_id: field(report._reserved_1[1]),
+            cpuid_step: field(report._reserved_1[2]),
+            alias_check_complete: v3.then(|| report.plat_info.is_alias_check_complete()),
+            report,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for AttestationReport {
+    type Error = AttestationReportError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let expected = std::mem::size_of::<Self>();
+
+        if bytes.len() < expected {
+            return Err(AttestationReportError::UnexpectedEndOfData {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        bincode::deserialize(bytes).map_err(|e| (*e).into())
+    }
+}
+
+/// A version 2 report with every other field zeroed, e.g. for test fixtures
+/// and mock builders deriving `Default`.
+impl Default for AttestationReport {
+    fn default() -> Self {
+        Self {
+            version: 2,
+            guest_svn: Default::default(),
+            policy: Default::default(),
+            family_id: Default::default(),
+            image_id: Default::default(),
+            vmpl: Default::default(),
+            sig_algo: Default::default(),
+            current_tcb: Default::default(),
+            plat_info: Default::default(),
+            key_info: Default::default(),
+            _reserved_0: Default::default(),
+            report_data: [0; 64],
+            measurement: Default::default(),
+            host_data: Default::default(),
+            id_key_digest: [0; 48],
+            author_key_digest: [0; 48],
+            report_id: Default::default(),
+            report_id_ma: Default::default(),
+            reported_tcb: Default::default(),
+            _reserved_1: Default::default(),
+            chip_id: Default::default(),
+            committed_tcb: Default::default(),
+            current_build: Default::default(),
+            current_minor: Default::default(),
+            current_major: Default::default(),
+            _reserved_2: Default::default(),
+            committed_build: Default::default(),
+            committed_minor: Default::default(),
+            committed_major: Default::default(),
+            _reserved_3: Default::default(),
+            launch_tcb: Default::default(),
+            _reserved_4: [0; 168],
+            signature: Default::default(),
+        }
+    }
+}
+
+/// A structurally valid report of version 2 or 3, for fuzzing verifiers:
+/// reserved fields are zero, bitfields only use defined bits, fields added
+/// by version 3 are only set in version 3 reports and AUTHOR_KEY_DIGEST is
+/// only set along with AUTHOR_KEY_EN. The signature is not valid.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for AttestationReport {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let version = *u.choose(&[2, NormalizedReport::V3])?;
+        let key_info: KeyInfo = u.arbitrary()?;
+        let mut plat_info: PlatformInfo = u.arbitrary()?;
+        let mut reserved_1 = [0u8; 24];
+
+        if version >= NormalizedReport::V3 {
+            u.fill_buffer(&mut reserved_1[..3])?;
+        } else {
+            plat_info.0 &= !(1 << 5);
+        }
+
+        let mut author_key_digest = [0u8; 48];
+        if key_info.author_key_enabled() {
+            u.fill_buffer(&mut author_key_digest)?;
+            author_key_digest[0] |= 1;
+        }
+
+        Ok(Self {
+            version,
+            guest_svn: u.arbitrary()?,
+            policy: u.arbitrary()?,
+            family_id: u.arbitrary()?,
+            image_id: u.arbitrary()?,
+            vmpl: u.int_in_range(0..=3)?,
+            sig_algo: SigAlgo::EcdsaP384Sha384.into(),
+            current_tcb: u.arbitrary()?,
+            plat_info,
+            key_info,
+            report_data: u.arbitrary()?,
+            measurement: u.arbitrary::<[u8; 48]>()?.into(),
+            host_data: u.arbitrary()?,
+            id_key_digest: u.arbitrary()?,
+            author_key_digest,
+            report_id: u.arbitrary::<[u8; 32]>()?.into(),
+            report_id_ma: u.arbitrary::<[u8; 32]>()?.into(),
+            reported_tcb: u.arbitrary()?,
+            _reserved_1: reserved_1,
+            chip_id: u.arbitrary::<[u8; 64]>()?.into(),
+            committed_tcb: u.arbitrary()?,
+            current_build: u.arbitrary()?,
+            current_minor: u.arbitrary()?,
+            current_major: u.arbitrary()?,
+            committed_build: u.arbitrary()?,
+            committed_minor: u.arbitrary()?,
+            committed_major: u.arbitrary()?,
+            launch_tcb: u.arbitrary()?,
+            signature: u.arbitrary()?,
+            ..Default::default()
+        })
+    }
+}
+
+impl Display for AttestationReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             r#"
-Platform Info ({}):
-  SMT Enabled:               {}
-  TSME Enabled:              {}
-  ECC Enabled:               {}
-  RAPL Disabled:             {}
-  Ciphertext Hiding Enabled: {}
+Attestation Report ({} bytes):
+Version:                      {}
+Guest SVN:                    {}
+{}
+Family ID:                    {}
+Image ID:                     {}
+VMPL:                         {}
+Signature Algorithm:          {}
+Current TCB:
+{}
+{}
+Author Key Encryption:        {}
+{}
+Report Data:                  {}
+Measurement:                  {}
+Host Data:                    {}
+ID Key Digest:                {}
+Author Key Digest:            {}
+Report ID:                    {}
+Report ID Migration Agent:    {}
+Reported TCB:                 {}
+Chip ID:                      {}
+Committed TCB:
+{}
+Current Build:                {}
+Current Minor:                {}
+Current Major:                {}
+Committed Build:              {}
+Committed Minor:              {}
+Committed Major:              {}
+Launch TCB:
+{}
+{}
 "#,
-            self.0,
-            self.smt_enabled(),
-            self.tsme_enabled(),
-            self.ecc_enabled(),
-            self.rapl_disabled(),
-            self.ciphertext_hiding_enabled(),
+            std::mem::size_of_val(self),
+            self.version,
+            self.guest_svn,
+            self.policy,
+            hexdump(&self.family_id),
+            hexdump(&self.image_id),
+            self.vmpl,
+            self.sig_algo,
+            self.current_tcb,
+            self.plat_info,
+            self.author_key_en(),
+            self.key_info,
+            hexdump(&self.report_data),
+            hexdump(self.measurement.as_ref()),
+            hexdump(&self.host_data),
+            hexdump(&self.id_key_digest),
+            hexdump(&self.author_key_digest),
+            hexdump(self.report_id.as_ref()),
+            hexdump(self.report_id_ma.as_ref()),
+            self.reported_tcb,
+            hexdump(self.chip_id.as_ref()),
+            self.committed_tcb,
+            self.current_build,
+            self.current_minor,
+            self.current_major,
+            self.committed_build,
+            self.committed_minor,
+            self.committed_major,
+            self.launch_tcb,
+            self.signature
+        )
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl AttestationReport {
+    /// Verify the certificate chain and the report signature, without
+    /// cross-checking the TCB extensions of the VCEK/VLEK against the report.
+    ///
+    /// Use this when the certificate was fetched for a different TCB on
+    /// purpose; otherwise prefer [Verifiable] on `(&Chain, &AttestationReport)`.
+    pub fn verify_signature(&self, chain: &Chain) -> io::Result<()> {
+        // The chain is the same for either key: ARK -> ASK -> VCEK, or
+        // ARK -> ASVK -> VLEK, with the ASVK taking the place of the ASK.
+        let key_name = self.signing_key_name()?;
+        let vek = chain.verify()?;
+
+        let sig = EcdsaSig::try_from(&self.signature)?;
+        let measurable_bytes: &[u8] = &self.signed_bytes().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("Unable to serialize bytes: {}", e),
+            )
+        })?;
+
+        let mut hasher = Sha384::new();
+        hasher.update(measurable_bytes);
+        let base_digest = hasher.finish();
+
+        let ec = vek.public_key()?.ec_key()?;
+        let signed = sig.verify(&base_digest, &ec)?;
+
+        match signed {
+            true => Ok(()),
+            false => Err(Error::new(
+                ErrorKind::Other,
+                format!("{key_name} does not sign the attestation report"),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "crypto_nossl")]
+impl AttestationReport {
+    /// Verify the certificate chain and the report signature, without
+    /// cross-checking the TCB extensions of the VCEK/VLEK against the report.
+    ///
+    /// Use this when the certificate was fetched for a different TCB on
+    /// purpose; otherwise prefer [Verifiable] on `(&Chain, &AttestationReport)`.
+    pub fn verify_signature(&self, chain: &Chain) -> io::Result<()> {
+        // According to Chapter 3 of the [Versioned Chip Endorsement Key (VCEK) Certificate and
+        // KDS Interface Specification][spec], the VCEK certificate certifies an ECDSA public key on curve P-384,
+        // and the signature hash algorithm is sha384.
+        // [spec]: https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/57230.pdf
+
+        let key_name = self.signing_key_name()?;
+        let vek = chain.verify()?;
+
+        let sig = p384::ecdsa::Signature::try_from(&self.signature)?;
+
+        let measurable_bytes: &[u8] = &self.signed_bytes().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("Unable to serialize bytes: {}", e),
+            )
+        })?;
+
+        use sha2::Digest;
+        let base_digest = sha2::Sha384::new_with_prefix(measurable_bytes);
+
+        let verifying_key = p384::ecdsa::VerifyingKey::from_sec1_bytes(vek.public_key_sec1())
+            .map_err(|e| {
+                io::Error::new(
+                    ErrorKind::Other,
+                    format!("failed to deserialize public key from sec1 bytes: {e:?}"),
+                )
+            })?;
+
+        use p384::ecdsa::signature::DigestVerifier;
+        verifying_key.verify_digest(base_digest, &sig).map_err(|e| {
+            io::Error::new(
+                ErrorKind::Other,
+                format!("{key_name} does not sign the attestation report: {e:?}"),
+            )
+        })
+    }
+}
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl Verifiable for (&Chain, &AttestationReport) {
+    type Output = ();
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "verify_report",
+            level = "debug",
+            skip_all,
+            fields(version = self.1.version, vmpl = self.1.vmpl)
         )
+    )]
+    fn verify(self) -> io::Result<Self::Output> {
+        self.1.verify_signature(self.0)?;
+
+        verify_cert_matches_report(&self.0.vek, self.1)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Defines a fixed-size byte array newtype for an [AttestationReport] field.
+///
+/// Equality, including `==`, runs in constant time so that verifiers do not
+/// leak how much of an expected value matched.
+macro_rules! report_bytes {
+    ($(#[$meta:meta])* $name:ident, $len:expr) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+        pub struct $name(#[serde(with = "BigArray")] [u8; $len]);
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self([0; $len])
+            }
+        }
+
+        impl ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> Choice {
+                self.0.ct_eq(&other.0)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.ct_eq(other).into()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl std::hash::Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(value: [u8; $len]) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", hex::encode(self.0))
+            }
+        }
+    };
+}
+
+report_bytes!(
+    /// The launch measurement of a guest.
+    Measurement,
+    48
+);
+
+report_bytes!(
+    /// The report ID of a guest, or of its migration agent.
+    ReportId,
+    32
+);
+
+report_bytes!(
+    /// The unique identifier of the chip, or zeroes when MaskChipId is set.
+    ChipId,
+    64
+);
+
+impl Measurement {
+    /// Compare the measurement against an expected value in constant time.
+    pub fn verify_against_expected(&self, expected: &[u8; 48]) -> bool {
+        self.0.ct_eq(expected).into()
+    }
+}
+
+impl ChipId {
+    /// Whether the chip ID was masked (all zeroes) by the firmware.
+    pub fn is_masked(&self) -> bool {
+        self.ct_eq(&Self::default()).into()
+    }
+}
+
+/// Read the VERSION of a raw attestation report from its first 4 bytes,
+/// without parsing the rest, e.g. to pick which cached reports to parse.
+pub fn snp_report_version_from_bytes(bytes: &[u8]) -> Result<u32, AttestationReportError> {
+    match bytes.get(..4) {
+        Some(version) => Ok(u32::from_le_bytes([
+            version[0], version[1], version[2], version[3],
+        ])),
+        None => Err(AttestationReportError::BufferTooSmall(bytes.len())),
+    }
+}
+
+/// The size in bytes of an attestation report of the given VERSION, or
+/// `None` for versions this crate does not know.
+///
+/// Versions 2 and 3 are both 1184 bytes. Size buffers with this rather
+/// than hardcoding 1184, in case a future version changes the layout.
+pub fn snp_attestation_report_size(version: u32) -> Option<usize> {
+    match version {
+        2 | NormalizedReport::V3 => Some(std::mem::size_of::<AttestationReport>()),
+        _ => None,
+    }
+}
+
+/// Decode a base64-encoded attestation report (standard alphabet, with
+/// padding), as returned by some cloud metadata endpoints.
+#[cfg(feature = "base64")]
+pub fn attestation_report_from_base64(
+    s: &str,
+) -> Result<AttestationReport, AttestationReportError> {
+    use base64::Engine;
+
+    let bytes = base64::engine::general_purpose::STANDARD.decode(s.trim())?;
+
+    AttestationReport::try_from(bytes.as_slice())
+}
+
+/// Encode an attestation report as base64 (standard alphabet, with
+/// padding).
+#[cfg(feature = "base64")]
+pub fn attestation_report_to_base64(
+    report: &AttestationReport,
+) -> Result<String, AttestationReportError> {
+    use base64::Engine;
+
+    let bytes = bincode::serialize(report).map_err(|e| AttestationReportError::from(*e))?;
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// The version of the JSON layout produced by
+/// [to_json](AttestationReport::to_json) and checked by
+/// [from_json_strict](AttestationReport::from_json_strict). Bumped whenever
+/// a field is added, removed or resized.
+#[cfg(feature = "serde_json")]
+pub const REPORT_JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Checks that `value` has the same shape as `schema`: the same object
+/// keys, array lengths and JSON types, recursively.
+#[cfg(feature = "serde_json")]
+fn check_json_shape(
+    schema: &serde_json::Value,
+    value: &serde_json::Value,
+    pointer: &str,
+) -> Result<(), JsonReportError> {
+    use serde_json::Value;
+
+    match (schema, value) {
+        (Value::Object(fields), Value::Object(given)) => {
+            let escape =
+                |key: &str| format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+
+            if let Some(unknown) = given.keys().find(|key| !fields.contains_key(*key)) {
+                return Err(JsonReportError::UnknownField(escape(unknown)));
+            }
+
+            for (key, field) in fields {
+                match given.get(key) {
+                    Some(v) => check_json_shape(field, v, &escape(key))?,
+                    None => return Err(JsonReportError::MissingField(escape(key))),
+                }
+            }
+
+            Ok(())
+        }
+        (Value::Array(elements), Value::Array(given)) => {
+            if elements.len() != given.len() {
+                return Err(JsonReportError::InvalidLength {
+                    pointer: pointer.to_string(),
+                    expected: elements.len(),
+                    actual: given.len(),
+                });
+            }
+
+            for (i, (element, v)) in elements.iter().zip(given).enumerate() {
+                check_json_shape(element, v, &format!("{pointer}/{i}"))?;
+            }
+
+            Ok(())
+        }
+        (Value::Number(_), Value::Number(_)) => Ok(()),
+        _ => Err(JsonReportError::TypeMismatch(pointer.to_string())),
+    }
+}
+
+/// Parse a hex-encoded chip ID, as printed by
+/// [chip_id_hex](AttestationReport::chip_id_hex). An optional `0x` prefix
+/// and mixed case are accepted.
+pub fn parse_chip_id(hex_id: &str) -> Result<[u8; 64], ChipIdError> {
+    let digits = hex_id
+        .strip_prefix("0x")
+        .or_else(|| hex_id.strip_prefix("0X"))
+        .unwrap_or(hex_id);
+
+    let bytes = hex::decode(digits)?;
+
+    <[u8; 64]>::try_from(bytes.as_slice()).map_err(|_| ChipIdError::InvalidLength(bytes.len()))
+}
+
+/// Helpers for filling the 64-byte REPORT_DATA of a report request.
+pub struct ReportData;
+
+impl ReportData {
+    /// Place a nonce at the start of the report data, zero-padded.
+    pub fn from_nonce(nonce: &[u8]) -> Result<[u8; 64], ReportDataError> {
+        let mut data = [0u8; 64];
+
+        if nonce.len() > data.len() {
+            return Err(ReportDataError::NonceTooLong(nonce.len()));
+        }
+
+        data[..nonce.len()].copy_from_slice(nonce);
+
+        Ok(data)
+    }
+
+    /// Whether the report data past the first `data_len` bytes is all
+    /// zeroes, i.e. nothing beyond the caller's data would be signed into
+    /// the report.
+    pub fn is_zero_padded(report_data: &[u8; 64], data_len: usize) -> bool {
+        report_data
+            .get(data_len..)
+            .map_or(true, |unused| unused.iter().all(|b| *b == 0))
+    }
+
+    /// Fill the report data with the SHA-512 digest of `payload`.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    pub fn from_hash_sha512(payload: &[u8]) -> [u8; 64] {
+        #[cfg(feature = "openssl")]
+        let data: [u8; 64] = openssl::sha::sha512(payload);
+
+        #[cfg(all(feature = "crypto_nossl", not(feature = "openssl")))]
+        let data: [u8; 64] = {
+            use sha2::Digest;
+            sha2::Sha512::digest(payload).into()
+        };
+
+        data
+    }
+
+    /// Place the SHA-256 digest of `payload` in the first 32 bytes of the
+    /// report data, followed by zeroes.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    pub fn from_hash_sha256_padded(payload: &[u8]) -> [u8; 64] {
+        #[cfg(feature = "openssl")]
+        let digest: [u8; 32] = openssl::sha::sha256(payload);
+
+        #[cfg(all(feature = "crypto_nossl", not(feature = "openssl")))]
+        let digest: [u8; 32] = {
+            use sha2::Digest;
+            sha2::Sha256::digest(payload).into()
+        };
+
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(&digest);
+
+        data
+    }
+}
+
+/// How a payload was bound into the REPORT_DATA of a report.
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportDataScheme {
+    /// See [ReportData::from_nonce].
+    Nonce,
+
+    /// See [ReportData::from_hash_sha512].
+    Sha512,
+
+    /// See [ReportData::from_hash_sha256_padded].
+    Sha256Padded,
+}
+
+#[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+impl AttestationReport {
+    /// Whether the report data binds `payload` under `scheme`. The
+    /// comparison runs in constant time.
+    pub fn report_data_matches_payload(&self, payload: &[u8], scheme: ReportDataScheme) -> bool {
+        let expected = match scheme {
+            ReportDataScheme::Nonce => match ReportData::from_nonce(payload) {
+                Ok(data) => data,
+                Err(_) => return false,
+            },
+            ReportDataScheme::Sha512 => ReportData::from_hash_sha512(payload),
+            ReportDataScheme::Sha256Padded => ReportData::from_hash_sha256_padded(payload),
+        };
+
+        self.report_data.ct_eq(&expected).into()
+    }
+}
+
+bitfield! {
+    /// The firmware associates each guest with a guest policy that the guest owner provides. The
+    /// firmware restricts what actions the hypervisor can take on this guest according to the guest policy.
+    /// The policy also indicates the minimum firmware version to for the guest.
+    ///
+    /// The guest owner provides the guest policy to the firmware during launch. The firmware then binds
+    /// the policy to the guest. The policy cannot be changed throughout the lifetime of the guest. The
+    /// policy is also migrated with the guest and enforced by the destination platform firmware.
+    ///
+    /// | Bit(s) | Name              | Description                                                                                                        >
+    /// |--------|-------------------|-------------------------------------------------------------------------------------------------------------------->
+    /// | 7:0    | ABI_MINOR         | The minimum ABI minor version required for this guest to run.                                                      >
+    /// | 15:8   | ABI_MAJOR         | The minimum ABI major version required for this guest to run.                                                      >
+    /// | 16     | SMT               | 0: Host SMT usage is disallowed.<br>1: Host SMT usage is allowed.                                                  >
+    /// | 17     | -                 | Reserved. Must be one.                                                                                             >
+    /// | 18     | MIGRATE_MA        | 0: Association with a migration agent is disallowed.<br>1: Association with a migration agent is allowed           >
+    /// | 19     | DEBUG             | 0: Debugging is disallowed.<br>1: Debugging is allowed.                                                            >
+    /// | 20     | SINGLE_SOCKET     | 0: Guest can be activated on multiple sockets.<br>1: Guest can only be activated on one socket.                    >
+    /// | 21     | CXL_ALLOW         | 0: CXL cannot be populated with devices or memory.<br>1: CXL can be populated with devices or memory.              >
+    /// | 22     | MEM_AES_256_XTS   | 0: Allow either AES 128 XEX or AES 256 XTS for memory encryption.<br>1: Require AES 256 XTS for memory encryption. >
+    /// | 23     | RAPL_DIS          | 0: Allow Running Average Power Limit (RAPL).<br>1: RAPL must be disabled.                                          >
+    /// | 24     | CIPHERTEXT_HIDING | 0: Ciphertext hiding may be enabled or disabled.<br>1: Ciphertext hiding must be enabled.                          >
+    /// | 63:25  | -                 | Reserved. MBZ.                                                                                                     >
+    ///
+    #[derive(Default, Clone, Copy, Eq, PartialEq, Hash)]
+    #[derive(Deserialize, Serialize)]
+    #[repr(C)]
+    pub struct GuestPolicy(u64);
+    impl Debug;
+    /// ABI_MINOR field: Indicates the minor API version.
+    pub abi_minor, set_abi_minor: 7, 0;
+    /// ABI_MAJOR field: Indicates the minor API version.
+    pub abi_major, set_abi_major: 15, 8;
+    /// SMT_ALLOWED field: Indicates the if SMT should be permitted.
+    pub smt_allowed, set_smt_allowed: 16, 16;
+    /// MIGRATE_MA_ALLOWED field: Indicates the if migration is permitted with
+    /// the migration agent.
+    pub migrate_ma_allowed, set_migrate_ma_allowed: 18, 18;
+    /// DEBUG_ALLOWED field: Indicates the if debugging should is permitted.
+    pub debug_allowed, set_debug_allowed: 19, 19;
+    /// SINGLE_SOCKET_REQUIRED field: Indicates the if a single socket is required.
+    pub single_socket_required, set_single_socket_required: 20, 20;
+    /// CXL_ALLOW field: (1) can populate CXL devices/memory, (0) cannot populate CXL devices/memory
+    pub cxl_allowed, set_cxl_allowed: 21, 21;
+    /// MEM_AES_256_XTS field: (1) require AES 256 XTS encryption, (0) allows either AES 128 XEX or AES 256 XTS encryption
+    pub mem_aes_256_xts, set_mem_aes_256_xts: 22, 22;
+    /// RAPL_DIS field: (1) RAPL must be disabled, (0) allow RAPL
+    pub rapl_dis, set_rapl_dis: 23, 23;
+    /// CIPHERTEXT_HIDING field: (1) ciphertext hiding must be enabled, (0) ciphertext hiding may be enabled/disabled
+    pub ciphertext_hiding, set_ciphertext_hiding: 24, 24;
+}
+
+/// An arbitrary policy with reserved bit 17 set and bits 63:25 clear.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for GuestPolicy {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bits: u64 = u.arbitrary()?;
+
+        Ok(Self((bits & !Self::RESERVED_MBZ) | Self::RESERVED_MBO))
+    }
+}
+
+impl GuestPolicy {
+    /// Reserved bit 17, which must always be set.
+    const RESERVED_MBO: u64 = 1 << 17;
+
+    /// Reserved bits 63:25, which must always be zero.
+    const RESERVED_MBZ: u64 = !((1 << 25) - 1);
+
+    /// Checks the reserved bits of the policy: bit 17 must be set and bits
+    /// 63:25 must be zero. Useful to reject malformed policies taken from
+    /// untrusted report blobs early.
+    pub fn verify_reserved(&self) -> Result<(), PolicyError> {
+        if self.0 & Self::RESERVED_MBO == 0 {
+            return Err(PolicyError::ReservedBitUnset);
+        }
+
+        if self.0 & Self::RESERVED_MBZ != 0 {
+            return Err(PolicyError::ReservedBitsSet(self.0 & Self::RESERVED_MBZ));
+        }
+
+        Ok(())
+    }
+
+    /// Whether a platform enforcing `platform` satisfies this guest policy,
+    /// e.g. before migrating the guest to it. See
+    /// [compatibility_violations](Self::compatibility_violations).
+    pub fn is_compatible_with(&self, platform: &GuestPolicy) -> bool {
+        self.compatibility_violations(platform).is_empty()
+    }
+
+    /// Every way in which a platform enforcing `platform` fails this guest
+    /// policy: its ABI version is below the minimum required by the guest,
+    /// it grants a permission (SMT, migration agent, debug, CXL) the guest
+    /// does not, or it does not enforce a restriction (single socket, AES
+    /// 256 XTS, RAPL disabled, ciphertext hiding) the guest requires.
+    pub fn compatibility_violations(&self, platform: &GuestPolicy) -> Vec<PolicyViolation> {
+        platform.violations(self)
+    }
+
+    /// Every violation of this policy against the `allowed` one: the ABI
+    /// version must be at least the allowed one, permissions a subset of
+    /// the allowed ones and restrictions a superset of the allowed ones.
+    fn violations(&self, allowed: &GuestPolicy) -> Vec<PolicyViolation> {
+        let mut violations = vec![];
+
+        let required = (allowed.abi_major() as u8, allowed.abi_minor() as u8);
+        let present = (self.abi_major() as u8, self.abi_minor() as u8);
+
+        if present < required {
+            violations.push(PolicyViolation::AbiVersionTooLow { required, present });
+        }
+
+        let permissions = [
+            (
+                self.smt_allowed(),
+                allowed.smt_allowed(),
+                PolicyViolation::SmtNotAllowed,
+            ),
+            (
+                self.migrate_ma_allowed(),
+                allowed.migrate_ma_allowed(),
+                PolicyViolation::MigrateMaNotAllowed,
+            ),
+            (
+                self.debug_allowed(),
+                allowed.debug_allowed(),
+                PolicyViolation::DebugNotAllowed,
+            ),
+            (
+                self.cxl_allowed(),
+                allowed.cxl_allowed(),
+                PolicyViolation::CxlNotAllowed,
+            ),
+        ];
+
+        for (present, allowed, violation) in permissions {
+            if present > allowed {
+                violations.push(violation);
+            }
+        }
+
+        let restrictions = [
+            (
+                self.single_socket_required(),
+                allowed.single_socket_required(),
+                PolicyViolation::SingleSocketRequired,
+            ),
+            (
+                self.mem_aes_256_xts(),
+                allowed.mem_aes_256_xts(),
+                PolicyViolation::AesKeyTooWeak,
+            ),
+            (
+                self.rapl_dis(),
+                allowed.rapl_dis(),
+                PolicyViolation::RaplRequired,
+            ),
+            (
+                self.ciphertext_hiding(),
+                allowed.ciphertext_hiding(),
+                PolicyViolation::CiphertextHidingRequired,
+            ),
+        ];
+
+        for (present, required, violation) in restrictions {
+            if present < required {
+                violations.push(violation);
+            }
+        }
+
+        violations
+    }
+
+    /// Boolean policy keys, and their bit, accepted by the [FromStr]
+    /// implementation.
+    const FLAGS: [(&'static str, u64); 8] = [
+        ("smt", 16),
+        ("migrate_ma", 18),
+        ("debug", 19),
+        ("single_socket", 20),
+        ("cxl", 21),
+        ("mem_aes_256_xts", 22),
+        ("rapl_dis", 23),
+        ("ciphertext_hiding", 24),
+    ];
+
+    /// Renders the policy in the comma-separated `key=value` syntax
+    /// accepted by the [FromStr] implementation, so that
+    /// `policy.to_flag_string().parse()` yields the same policy.
+    ///
+    /// # Example:
+    ///
+    /// ```ignore
+    /// let policy: GuestPolicy = "abi_major=1,abi_minor=51,smt,debug=off".parse().unwrap();
+    ///
+    /// assert!(policy.to_flag_string().starts_with("abi_major=1,abi_minor=51,smt=on"));
+    /// ```
+    pub fn to_flag_string(&self) -> String {
+        let mut flags = vec![
+            format!("abi_major={}", self.abi_major()),
+            format!("abi_minor={}", self.abi_minor()),
+        ];
+
+        for (key, bit) in Self::FLAGS {
+            let value = match self.0 & (1 << bit) {
+                0 => "off",
+                _ => "on",
+            };
+            flags.push(format!("{key}={value}"));
+        }
+
+        flags.join(",")
+    }
+}
+
+impl FromStr for GuestPolicy {
+    type Err = PolicyError;
+
+    /// Parses a comma-separated list of policy keys, such as
+    /// `"abi_major=1,abi_minor=51,smt,debug=off,single_socket"`.
+    ///
+    /// `abi_major` and `abi_minor` take a value between 0 and 255. Every
+    /// other key is a flag which is enabled when given bare, or set
+    /// explicitly with `=on`/`=off` or `=1`/`=0`. Keys which are not
+    /// present are left cleared, and the reserved bit 17 is always set.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = GuestPolicy(Self::RESERVED_MBO);
+
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (key, value) = match entry.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (entry, None),
+            };
+
+            let invalid =
+                || PolicyError::InvalidValue(key.to_string(), value.unwrap_or("").to_string());
+
+            match key {
+                "abi_major" | "abi_minor" => {
+                    let version: u64 = value.ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+
+                    if version > u8::MAX as u64 {
+                        return Err(PolicyError::AbiOutOfRange(key.to_string(), version));
+                    }
+
+                    match key {
+                        "abi_major" => policy.set_abi_major(version),
+                        _ => policy.set_abi_minor(version),
+                    }
+                }
+                _ => {
+                    let (_, bit) = Self::FLAGS
+                        .iter()
+                        .find(|(flag, _)| *flag == key)
+                        .ok_or_else(|| PolicyError::UnknownKey(key.to_string()))?;
+
+                    match value {
+                        None | Some("on") | Some("1") => policy.0 |= 1 << bit,
+                        Some("off") | Some("0") => policy.0 &= !(1 << bit),
+                        Some(_) => return Err(invalid()),
+                    }
+                }
+            }
+        }
+
+        Ok(policy)
+    }
+}
+
+impl Display for GuestPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"
+    Guest Policy ({}):
+    ABI Major:         {}
+    ABI Minor:         {}
+    SMT Allowed:       {}
+    Migrate MA:        {}
+    Debug Allowed:     {}
+    Single Socket:     {}
+    CXL Allowed:       {}
+    AES 256 XTS:       {}
+    RAPL Disabled:     {}
+    Ciphertext Hiding: {}"#,
+            self.0,
+            self.abi_major(),
+            self.abi_minor(),
+            self.smt_allowed(),
+            self.migrate_ma_allowed(),
+            self.debug_allowed(),
+            self.single_socket_required(),
+            self.cxl_allowed(),
+            self.mem_aes_256_xts(),
+            self.rapl_dis(),
+            self.ciphertext_hiding()
+        )
+    }
+}
+
+impl From<GuestPolicy> for u64 {
+    fn from(value: GuestPolicy) -> Self {
+        value.0
+    }
+}
+
+bitfield! {
+    /// Information about the keys used by the firmware for this report.
+    ///
+    /// | Bit(s) | Name          | Description                                                                          |
+    /// |--------|---------------|--------------------------------------------------------------------------------------|
+    /// | 0      | AUTHOR_KEY_EN | Indicates that the digest of the author key is present in AUTHOR_KEY_DIGEST.         |
+    /// | 1      | MASK_CHIP_KEY | The value of MaskChipKey.                                                            |
+    /// | 4:2    | SIGNING_KEY   | Encodes the key used to sign this report.<br>0: VCEK.<br>1: VLEK.<br>2-6: Reserved.<br>7: None. |
+    /// | 31:5   | -             | Reserved. MBZ.                                                                       |
+    #[derive(Default, Clone, Copy, Eq, PartialEq, Hash)]
+    #[derive(Deserialize, Serialize)]
+    #[repr(C)]
+    pub struct KeyInfo(u32);
+    impl Debug;
+    /// AUTHOR_KEY_EN field: Indicates that the digest of the author key is
+    /// present in AUTHOR_KEY_DIGEST.
+    pub author_key_en, _: 0, 0;
+    /// MASK_CHIP_KEY field: The value of MaskChipKey.
+    pub mask_chip_key, _: 1, 1;
+    /// SIGNING_KEY field: Encodes the key used to sign this report.
+    /// See [signing_key_type()](self::KeyInfo::signing_key_type).
+    pub signing_key, _: 4, 2;
+}
+
+/// Arbitrary AUTHOR_KEY_EN and MASK_CHIP_KEY bits, and a SIGNING_KEY of
+/// VCEK, VLEK or None.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for KeyInfo {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let flags: u32 = u.int_in_range(0..=0b11)?;
+        let signing_key: u32 = *u.choose(&[0, 1, 7])?;
+
+        Ok(Self(flags | signing_key << 2))
+    }
+}
+
+impl KeyInfo {
+    /// Whether AUTHOR_KEY_EN is set, i.e. the ID key was certified by an
+    /// author key at launch.
+    pub fn author_key_enabled(&self) -> bool {
+        self.author_key_en() == 1
+    }
+
+    /// Decodes the SIGNING_KEY field.
+    pub fn signing_key_type(&self) -> SigningKey {
+        self.signing_key().into()
+    }
+
+    /// Whether the report was signed by the VCEK.
+    pub fn is_vcek_signed(&self) -> bool {
+        self.signing_key_type() == SigningKey::Vcek
+    }
+
+    /// Whether the report was signed by the VLEK.
+    pub fn is_vlek_signed(&self) -> bool {
+        self.signing_key_type() == SigningKey::Vlek
+    }
+
+    /// Whether the report was left unsigned by the firmware.
+    pub fn is_unsigned(&self) -> bool {
+        self.signing_key_type() == SigningKey::None
+    }
+}
+
+impl Display for KeyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"
+Key Information ({}):
+  Author Key Enabled: {}
+  Mask Chip Key:      {}
+  Signing Key:        {}
+"#,
+            self.0,
+            self.author_key_en(),
+            self.mask_chip_key(),
+            self.signing_key_type(),
+        )
+    }
+}
+
+impl From<KeyInfo> for u32 {
+    fn from(value: KeyInfo) -> Self {
+        value.0
+    }
+}
+
+/// The key used by the firmware to sign an attestation report, as encoded
+/// in the SIGNING_KEY field of [KeyInfo].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SigningKey {
+    /// Versioned Chip Endorsement Key (0h).
+    Vcek,
+
+    /// Versioned Loaded Endorsement Key (1h).
+    Vlek,
+
+    /// The report is not signed (7h).
+    None,
+
+    /// Reserved encodings (2h-6h).
+    Reserved(u32),
+}
+
+impl Display for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigningKey::Vcek => write!(f, "VCEK"),
+            SigningKey::Vlek => write!(f, "VLEK"),
+            SigningKey::None => write!(f, "None"),
+            SigningKey::Reserved(value) => write!(f, "Reserved ({value})"),
+        }
+    }
+}
+
+impl From<u32> for SigningKey {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => SigningKey::Vcek,
+            1 => SigningKey::Vlek,
+            7 => SigningKey::None,
+            other => SigningKey::Reserved(other),
+        }
+    }
+}
+
+/// The algorithm used to sign an attestation report, as encoded in its
+/// SIG_ALGO field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SigAlgo {
+    /// ECDSA P-384 with SHA-384 (1h).
+    EcdsaP384Sha384,
+
+    /// Reserved (0h) or undefined encodings.
+    Unknown(u32),
+}
+
+impl Display for SigAlgo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigAlgo::EcdsaP384Sha384 => write!(f, "ECDSA P-384 SHA-384"),
+            SigAlgo::Unknown(value) => write!(f, "Unknown({value})"),
+        }
+    }
+}
+
+impl From<u32> for SigAlgo {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => SigAlgo::EcdsaP384Sha384,
+            other => SigAlgo::Unknown(other),
+        }
+    }
+}
+
+impl From<SigAlgo> for u32 {
+    fn from(value: SigAlgo) -> Self {
+        match value {
+            SigAlgo::EcdsaP384Sha384 => 1,
+            SigAlgo::Unknown(value) => value,
+        }
+    }
+}
+
+bitfield! {
+    /// A structure with a bit-field unsigned 64 bit integer:
+    /// Bit 0 representing the status of SMT enablement.
+    /// Bit 1 representing the status of TSME enablement.
+    /// Bit 2 indicates if ECC memory is used.
+    /// Bit 3 indicates if RAPL is disabled.
+    /// Bit 4 indicates if ciphertext hiding is enabled
+    /// Bit 5 indicates if the alias check has completed (report version 3 and later).
+    /// Bits 6-63 are reserved.
+    #[derive(Default, Clone, Copy, Eq, PartialEq, Hash)]
+    #[derive(Deserialize, Serialize)]
+    #[repr(C)]
+    pub struct PlatformInfo(u64);
+    impl Debug;
+    /// Returns the bit state of SMT
+    pub smt_enabled, _: 0, 0;
+    /// Returns the bit state of TSME.
+    pub tsme_enabled, _: 1, 1;
+    /// Indicates that the platform is currently using ECC memory
+    pub ecc_enabled, _: 2, 2;
+    /// Indicates that the RAPL feature is disabled
+    pub rapl_disabled, _: 3, 3;
+    /// Indicates that ciphertext hiding is enabled
+    pub ciphertext_hiding_enabled, _: 4, 4;
+    /// Indicates that the alias check has completed. Only defined by
+    /// report version 3 and later.
+    pub alias_check_complete, _: 5, 5;
+    /// reserved
+    reserved, _: 6, 63;
+}
+
+/// Arbitrary values for the defined bits 5:0, with the reserved bits clear.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PlatformInfo {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self(u.int_in_range(0..=0b11_1111)?))
+    }
+}
+
+impl PlatformInfo {
+    /// The raw PLATFORM_INFO value as found in the report, reserved bits
+    /// included, e.g. for attestation log records.
+    pub fn to_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// The raw PLATFORM_INFO value.
+    #[deprecated(note = "use to_u64")]
+    pub fn raw(&self) -> u64 {
+        self.to_u64()
+    }
+
+    /// Whether SMT is enabled on the platform.
+    pub fn is_smt_enabled(&self) -> bool {
+        self.smt_enabled() == 1
+    }
+
+    /// Whether TSME is enabled on the platform.
+    pub fn is_tsme_enabled(&self) -> bool {
+        self.tsme_enabled() == 1
+    }
+
+    /// Whether the platform is using ECC memory.
+    pub fn is_ecc_enabled(&self) -> bool {
+        self.ecc_enabled() == 1
+    }
+
+    /// Whether the RAPL feature is disabled.
+    pub fn is_rapl_disabled(&self) -> bool {
+        self.rapl_disabled() == 1
+    }
+
+    /// Whether ciphertext hiding is enabled.
+    pub fn is_ciphertext_hiding_enabled(&self) -> bool {
+        self.ciphertext_hiding_enabled() == 1
+    }
+
+    /// Whether the alias check has completed. Always false before report
+    /// version 3, which does not define the bit.
+    pub fn is_alias_check_complete(&self) -> bool {
+        self.alias_check_complete() == 1
+    }
+}
+
+impl From<PlatformInfo> for u64 {
+    fn from(value: PlatformInfo) -> Self {
+        value.to_u64()
+    }
+}
+
+impl Display for PlatformInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"
+Platform Info ({}):
+  SMT Enabled:               {}
+  TSME Enabled:              {}
+  ECC Enabled:               {}
+  RAPL Disabled:             {}
+  Ciphertext Hiding Enabled: {}
+  Alias Check Complete:      {}
+"#,
+            self.0,
+            self.smt_enabled(),
+            self.tsme_enabled(),
+            self.ecc_enabled(),
+            self.rapl_disabled(),
+            self.ciphertext_hiding_enabled(),
+            self.alias_check_complete(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::fixtures::{milan_report, milan_report_bytes};
+
+    #[test]
+    fn test_derived_key_with_launch_mit_vector() {
+        let key = DerivedKey::new(false, GuestFieldSelect::new(), 0, 0, 0, None);
+        assert_eq!(key.launch_mit_vector, None);
+
+        let key = key.with_launch_mit_vector(0x5);
+        assert_eq!(key.launch_mit_vector, Some(0x5));
+        assert_eq!(key.get_launch_mit_vector(), Some(0x5));
+    }
+
+    #[test]
+    fn test_guest_field_select_builder() {
+        let selection = GuestFieldSelect::new()
+            .with_measurement(true)
+            .with_guest_policy(true)
+            .with_tcb_version(true)
+            .with_tcb_version(false);
+
+        assert_eq!(selection.0, 0b1001);
+        assert_eq!(selection.to_string(), "guest_policy,measurement");
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_report_is_well_formed() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        };
+
+        for _ in 0..64 {
+            let bytes: Vec<u8> = (0..2048).map(|_| next()).collect();
+            let report = AttestationReport::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+            assert!(report.version == 2 || report.version == NormalizedReport::V3);
+            assert!(!report.has_nonzero_reserved());
+            assert!(report.policy.verify_reserved().is_ok());
+            assert!(report.validate_author_key_consistency().is_ok());
+            assert!(!matches!(
+                report.key_info.signing_key_type(),
+                SigningKey::Reserved(_)
+            ));
+            if report.version < NormalizedReport::V3 {
+                assert_eq!(report.plat_info.alias_check_complete(), 0);
+            }
+
+            let raw = bincode::serialize(&report).unwrap();
+            assert_eq!(AttestationReport::from_bytes_strict(&raw).unwrap(), report);
+        }
+    }
+
+    #[test]
+    fn test_snp_attestation_report_size() {
+        let bytes = milan_report_bytes();
+
+        assert_eq!(snp_attestation_report_size(2), Some(1184));
+        assert_eq!(snp_attestation_report_size(3), Some(1184));
+        assert_eq!(
+            snp_attestation_report_size(snp_report_version_from_bytes(&bytes).unwrap()),
+            Some(bytes.len())
+        );
+        assert_eq!(snp_attestation_report_size(0), None);
+        assert_eq!(snp_attestation_report_size(4), None);
+    }
+
+    #[test]
+    fn test_snp_report_version_from_bytes() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            snp_report_version_from_bytes(&bytes).unwrap(),
+            report.version
+        );
+        assert_eq!(snp_report_version_from_bytes(&[3, 0, 0, 0]).unwrap(), 3);
+        assert!(matches!(
+            snp_report_version_from_bytes(&bytes[..3]),
+            Err(AttestationReportError::BufferTooSmall(3))
+        ));
+    }
+
+    #[test]
+    fn test_platform_alias_check_satisfied() {
+        let mut report = milan_report();
+        report.policy.set_debug_allowed(0);
+
+        assert!(matches!(
+            report.platform_alias_check_satisfied(),
+            Err(AttestationReportError::FieldUnavailable { version: 2, .. })
+        ));
+
+        report.version = 3;
+        assert!(!report.platform_alias_check_satisfied().unwrap());
+
+        report.plat_info = PlatformInfo(1 << 5);
+        assert!(report.platform_alias_check_satisfied().unwrap());
+
+        // Allowing debugging does not exempt a guest from the alias check.
+        report.plat_info = PlatformInfo(0);
+        report.policy.set_debug_allowed(1);
+        assert!(!report.platform_alias_check_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_platform_info_flags() {
+        let info = PlatformInfo(0b10_1011);
+
+        assert_eq!(info.to_u64(), 0b10_1011);
+        assert!(info.is_smt_enabled());
+        assert!(info.is_tsme_enabled());
+        assert!(!info.is_ecc_enabled());
+        assert!(info.is_rapl_disabled());
+        assert!(!info.is_ciphertext_hiding_enabled());
+        assert!(info.is_alias_check_complete());
+
+        // The bit getters are kept.
+        assert_eq!(info.smt_enabled(), 1);
+        assert_eq!(info.ecc_enabled(), 0);
+
+        assert_eq!(
+            info.to_string(),
+            r#"
+Platform Info (43):
+  SMT Enabled:               1
+  TSME Enabled:              1
+  ECC Enabled:               0
+  RAPL Disabled:             1
+  Ciphertext Hiding Enabled: 0
+  Alias Check Complete:      1
+"#
+        );
+
+        let info: PlatformInfo = bincode::deserialize(&bincode::serialize(&info).unwrap()).unwrap();
+        assert_eq!(info.to_u64(), 0b10_1011);
+    }
+
+    #[test]
+    fn test_platform_info_to_u64() {
+        type IsSet = fn(&PlatformInfo) -> bool;
+
+        let flags: [(u32, IsSet); 6] = [
+            (0, PlatformInfo::is_smt_enabled),
+            (1, PlatformInfo::is_tsme_enabled),
+            (2, PlatformInfo::is_ecc_enabled),
+            (3, PlatformInfo::is_rapl_disabled),
+            (4, PlatformInfo::is_ciphertext_hiding_enabled),
+            (5, PlatformInfo::is_alias_check_complete),
+        ];
+
+        let mut all = 0;
+        for (bit, is_set) in flags {
+            let info = PlatformInfo(1 << bit);
+
+            assert!(is_set(&info));
+            assert_eq!(info.to_u64(), 1 << bit);
+            assert_eq!(u64::from(info), 1 << bit);
+            assert_eq!(u64::from(info), 1 << bit);
+
+            all |= info.to_u64();
+        }
+        assert_eq!(all, 0b11_1111);
+
+        // Reserved bits are passed through unchanged.
+        assert_eq!(PlatformInfo(1 << 63 | 1).to_u64(), 0x8000_0000_0000_0001);
+        assert_eq!(PlatformInfo::default().to_u64(), 0);
+    }
+
+    #[test]
+    fn test_reserved_regions() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert!(!report.has_nonzero_reserved());
+        assert!(AttestationReport::from_bytes_strict(&bytes).is_ok());
+
+        for (range, region) in report.reserved_regions() {
+            assert_eq!(&bytes[range.clone()], region);
+
+            // Poison the last byte of each region.
+            let mut poisoned = bytes.clone();
+            poisoned[range.end - 1] = 0xa5;
+
+            let lenient = AttestationReport::try_from(poisoned.as_slice()).unwrap();
+            assert!(lenient.has_nonzero_reserved());
+
+            match AttestationReport::from_bytes_strict(&poisoned) {
+                Err(AttestationReportError::ReservedNonZero { offset }) => {
+                    assert_eq!(offset, range.end - 1)
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_regions_v3() {
+        let mut bytes = milan_report_bytes();
+        bytes[0..4].copy_from_slice(&3u32.to_le_bytes());
+        bytes[0x188..0x18b].copy_from_slice(&[0x19, 0x11, 0x01]);
+
+        let report = AttestationReport::from_bytes_strict(&bytes).unwrap();
+        assert!(report
+            .reserved_regions()
+            .iter()
+            .any(|(range, _)| *range == (0x18b..0x1a0)));
+
+        bytes[0..4].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            AttestationReport::from_bytes_strict(&bytes),
+            Err(AttestationReportError::ReservedNonZero { offset: 0x188 })
+        ));
+    }
+
+    #[test]
+    fn test_normalized_report_v2() {
+        let report = milan_report();
+        let normalized = report.into_latest();
+
+        assert_eq!(normalized.source_version(), 2);
+        assert_eq!(normalized.report, report);
+        assert_eq!(normalized.cpuid_fam_id, None);
+        assert_eq!(normalized.cpuid_mod_id, None);
+        assert_eq!(normalized.cpuid_step, None);
+        assert_eq!(normalized.alias_check_complete, None);
+    }
+
+    #[test]
+    fn test_normalized_report_v3() {
+        // The Milan fixture upgraded to version 3, on a Genoa (19h/11h/1) CPU
+        // with the alias check completed.
+        let mut bytes = milan_report_bytes();
+        bytes[0..4].copy_from_slice(&3u32.to_le_bytes());
+        bytes[0x40] |= 1 << 5;
+        bytes[0x188..0x18b].copy_from_slice(&[0x19, 0x11, 0x01]);
+
+        let normalized = AttestationReport::try_from(bytes.as_slice())
+            .unwrap()
+            .into_latest();
+
+        assert_eq!(normalized.source_version(), 3);
+        assert_eq!(normalized.cpuid_fam_id, Some(0x19));
+        assert_eq!(normalized.cpuid_mod_id, Some(0x11));
+        assert_eq!(normalized.cpuid_step, Some(0x01));
+        assert_eq!(normalized.alias_check_complete, Some(true));
+
+        let encoded = bincode::serialize(&normalized).unwrap();
+        let decoded: NormalizedReport = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, normalized);
+    }
+
+    #[test]
+    fn test_normalized_report_tcb() {
+        let raw = 0x4500_0000_1800_0901u64;
+        let mut report = AttestationReport {
+            version: NormalizedReport::V3,
+            reported_tcb: TcbVersion::from_le_bytes(raw.to_le_bytes()),
+            ..Default::default()
+        };
+
+        // Milan keeps the layout of TcbVersion.
+        report.set_v3_fields(0x19, 0x01, 0x01, true);
+        let normalized = report.into_latest();
+        assert!(!normalized.has_fmc_tcb());
+        assert_eq!(normalized.reported_tcb(), report.reported_tcb.into());
+
+        // Turin carries an FMC SVN in byte 0.
+        report.set_v3_fields(0x1a, 0x02, 0x00, true);
+        let normalized = report.into_latest();
+        assert!(normalized.has_fmc_tcb());
+        assert_eq!(normalized.reported_tcb().fmc, Some(1));
+        assert_eq!(normalized.reported_tcb().snp, 24);
+        assert_eq!(normalized.reported_tcb().to_raw(), raw);
+        assert_eq!(normalized.current_tcb().fmc, Some(0));
+
+        // Version 2 does not tell the family and keeps the older layout.
+        report.version = 2;
+        assert!(!report.into_latest().has_fmc_tcb());
+    }
+
+    #[test]
+    fn test_cpuid_model_string() {
+        let model_string = |family, model, stepping| {
+            let mut report = AttestationReport {
+                version: NormalizedReport::V3,
+                ..Default::default()
+            };
+            report.set_v3_fields(family, model, stepping, true);
+            report.into_latest().cpuid_model_string()
+        };
+
+        assert_eq!(
+            model_string(0x19, 0x01, 0x01),
+            "AMD EPYC 7003 Series (Milan)"
+        );
+        assert_eq!(
+            model_string(0x19, 0x11, 0x01),
+            "AMD EPYC 9004 Series (Genoa)"
+        );
+        assert_eq!(
+            model_string(0x19, 0xa0, 0x02),
+            "AMD EPYC 97x4/8004 Series (Bergamo/Siena)"
+        );
+        assert_eq!(
+            model_string(0x1a, 0x02, 0x00),
+            "AMD EPYC 9005 Series (Turin)"
+        );
+        assert_eq!(
+            model_string(0x42, 0x01, 0x02),
+            "Unknown (fam=0x42 mod=0x01 step=0x02)"
+        );
+
+        assert_eq!(
+            AttestationReport::default()
+                .into_latest()
+                .cpuid_model_string(),
+            "Unknown (report version 2 has no CPUID fields)"
+        );
+    }
+
+    #[test]
+    fn test_check_launch_config() {
+        let report = AttestationReport {
+            family_id: [1; 16],
+            image_id: [2; 16],
+            host_data: [3; 32],
+            ..Default::default()
+        };
+
+        assert!(report.matches_launch_config(&[1; 16], &[2; 16], &[3; 32]));
+        assert_eq!(
+            report.check_launch_config(&[1; 16], &[2; 16], &[3; 32]),
+            Ok(())
+        );
+
+        assert!(!report.matches_launch_config(&[1; 16], &[2; 16], &[0; 32]));
+        assert_eq!(
+            report.check_launch_config(&[0; 16], &[2; 16], &[0; 32]),
+            Err(vec![
+                LaunchConfigError::FamilyIdMismatch,
+                LaunchConfigError::HostDataMismatch
+            ])
+        );
+    }
+
+    #[test]
+    fn test_guest_field_select_active_fields() {
+        let selection = GuestFieldSelect::new()
+            .with_measurement(true)
+            .with_guest_policy(true);
+
+        assert_eq!(selection.active_fields(), ["GUEST_POLICY", "MEASUREMENT"]);
+        assert!(GuestFieldSelect::none().active_fields().is_empty());
+        assert_eq!(
+            GuestFieldSelect::all().active_fields(),
+            GuestFieldSelect::SPEC_NAMES
+        );
+
+        for (name, spec_name) in GuestFieldSelect::FIELD_NAMES
+            .iter()
+            .zip(GuestFieldSelect::SPEC_NAMES.iter())
+        {
+            assert_eq!(name.to_ascii_uppercase(), *spec_name);
+        }
+    }
+
+    #[test]
+    fn test_guest_field_select_round_trip() {
+        for bits in 0..(1u64 << 7) {
+            let selection = GuestFieldSelect(bits);
+            let parsed: GuestFieldSelect = selection.to_string().parse().unwrap();
+
+            assert_eq!(parsed.0, bits);
+        }
+    }
+
+    #[test]
+    fn test_guest_field_select_from_str() {
+        let selection: GuestFieldSelect = " Measurement, tcb_version ".parse().unwrap();
+        assert_eq!(selection.0, 0b101000);
+
+        let selection: GuestFieldSelect = "".parse().unwrap();
+        assert_eq!(selection.0, 0);
+        assert_eq!(selection.to_string(), "none");
+    }
+
+    #[test]
+    fn test_guest_field_select_unknown_field() {
+        assert_eq!(
+            "measurement,chip_id"
+                .parse::<GuestFieldSelect>()
+                .unwrap_err(),
+            GuestFieldSelectError::UnknownField("chip_id".to_string())
+        );
+    }
+
+    #[test]
+    fn test_guest_policy_from_str() {
+        let policy: GuestPolicy = "abi_major=1,abi_minor=51,smt,debug=off,single_socket"
+            .parse()
+            .unwrap();
+
+        assert_eq!(policy.0, 0x130133);
+    }
+
+    #[test]
+    fn test_guest_policy_flag_string_round_trip() {
+        let base: u64 = 0x20000 | (1 << 8) | 51;
+
+        let mut policies: Vec<GuestPolicy> = GuestPolicy::FLAGS
+            .iter()
+            .map(|(_, bit)| GuestPolicy(base | (1 << bit)))
+            .collect();
+        policies.push(GuestPolicy(base));
+        policies.push(GuestPolicy(0x1ffffff));
+
+        for policy in policies {
+            let parsed: GuestPolicy = policy.to_flag_string().parse().unwrap();
+            assert_eq!(parsed, policy);
+        }
+    }
+
+    #[test]
+    fn test_guest_policy_from_str_errors() {
+        assert_eq!(
+            "smt,page_swap".parse::<GuestPolicy>().unwrap_err(),
+            PolicyError::UnknownKey("page_swap".to_string())
+        );
+        assert_eq!(
+            "abi_major=256".parse::<GuestPolicy>().unwrap_err(),
+            PolicyError::AbiOutOfRange("abi_major".to_string(), 256)
+        );
+        assert_eq!(
+            "abi_minor".parse::<GuestPolicy>().unwrap_err(),
+            PolicyError::InvalidValue("abi_minor".to_string(), "".to_string())
+        );
+        assert_eq!(
+            "debug=yes".parse::<GuestPolicy>().unwrap_err(),
+            PolicyError::InvalidValue("debug".to_string(), "yes".to_string())
+        );
+    }
+
+    #[test]
+    fn test_guest_policy_verify_reserved() {
+        assert!(GuestPolicy(0x30000).verify_reserved().is_ok());
+        assert!(GuestPolicy(0x1ffffff).verify_reserved().is_ok());
+        assert_eq!(
+            GuestPolicy(0x10000).verify_reserved(),
+            Err(PolicyError::ReservedBitUnset)
+        );
+        assert_eq!(
+            GuestPolicy(0x2030000).verify_reserved(),
+            Err(PolicyError::ReservedBitsSet(0x2000000))
+        );
+    }
+
+    #[test]
+    fn test_guest_policy_display() {
+        let expected = r#"
+    Guest Policy (22217011):
+    ABI Major:         1
+    ABI Minor:         51
+    SMT Allowed:       1
+    Migrate MA:        0
+    Debug Allowed:     0
+    Single Socket:     1
+    CXL Allowed:       0
+    AES 256 XTS:       1
+    RAPL Disabled:     0
+    Ciphertext Hiding: 1"#;
+
+        let policy: GuestPolicy =
+            "abi_major=1,abi_minor=51,smt,single_socket,mem_aes_256_xts,ciphertext_hiding"
+                .parse()
+                .unwrap();
+
+        assert_eq!(policy.to_string(), expected);
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let report = AttestationReport {
+            policy: "abi_major=1,abi_minor=51,smt,debug".parse().unwrap(),
+            ..Default::default()
+        };
+
+        let allowed: GuestPolicy = "abi_major=1,abi_minor=0,smt,debug".parse().unwrap();
+        assert_eq!(report.check_policy(&allowed), Ok(()));
+
+        let newer: GuestPolicy = "abi_major=1,abi_minor=55,smt,debug".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&newer),
+            Err(PolicyViolation::AbiVersionTooLow {
+                required: (1, 55),
+                present: (1, 51),
+            })
+        );
+
+        let no_debug: GuestPolicy = "abi_major=1,smt".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&no_debug),
+            Err(PolicyViolation::DebugNotAllowed)
+        );
+
+        let no_smt: GuestPolicy = "abi_major=1,debug".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&no_smt),
+            Err(PolicyViolation::SmtNotAllowed)
+        );
+
+        let single_socket: GuestPolicy = "smt,debug,single_socket".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&single_socket),
+            Err(PolicyViolation::SingleSocketRequired)
+        );
+
+        let aes: GuestPolicy = "smt,debug,mem_aes_256_xts".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&aes),
+            Err(PolicyViolation::AesKeyTooWeak)
+        );
+
+        let rapl: GuestPolicy = "smt,debug,rapl_dis".parse().unwrap();
+        assert_eq!(
+            report.check_policy(&rapl),
+            Err(PolicyViolation::RaplRequired)
+        );
+    }
+
+    #[test]
+    fn test_policy_compatibility() {
+        let guest: GuestPolicy = "abi_major=1,abi_minor=51,smt,single_socket"
+            .parse()
+            .unwrap();
+
+        let platform: GuestPolicy = "abi_major=1,abi_minor=55,single_socket".parse().unwrap();
+        assert!(guest.is_compatible_with(&platform));
+        assert!(guest.is_compatible_with(&guest));
+
+        let platform: GuestPolicy = "abi_major=1,abi_minor=50,smt,debug".parse().unwrap();
+        assert!(!guest.is_compatible_with(&platform));
+        assert_eq!(
+            guest.compatibility_violations(&platform),
+            vec![
+                PolicyViolation::AbiVersionTooLow {
+                    required: (1, 51),
+                    present: (1, 50),
+                },
+                PolicyViolation::DebugNotAllowed,
+                PolicyViolation::SingleSocketRequired,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signed_bytes() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let signed = report.signed_bytes().unwrap();
+
+        assert_eq!(signed.len(), 0x2a0);
+        assert_eq!(signed[..], bytes[..0x2a0]);
+
+        // Reserved bytes are signed as received.
+        let mut bytes = bytes;
+        bytes[0x4c] = 0x5a;
+        bytes[0x1eb] = 0xa5;
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(report.signed_bytes().unwrap()[..], bytes[..0x2a0]);
+    }
+
+    #[test]
+    fn test_measurement() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let mut expected = [0u8; 48];
+        expected.copy_from_slice(&bytes[0x90..0xc0]);
+        assert!(report.measurement.verify_against_expected(&expected));
+        assert_eq!(report.measurement, Measurement::from(expected));
+        assert_eq!(report.measurement.to_string(), hex::encode(expected));
+
+        let mut other = expected;
+        other[47] ^= 1;
+        assert!(!report.measurement.verify_against_expected(&other));
+    }
+
+    #[test]
+    fn test_report_and_chip_id() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(report.report_id.as_ref(), &bytes[0x140..0x160]);
+        assert_eq!(
+            report.report_id.to_string(),
+            hex::encode(&bytes[0x140..0x160])
+        );
+        assert_eq!(report.chip_id.as_ref(), &bytes[0x1a0..0x1e0]);
+        assert!(!report.chip_id.is_masked());
+        assert!(!report.chip_id_is_masked());
+        assert!(ChipId::default().is_masked());
+        assert!(AttestationReport::default().chip_id_is_masked());
+
+        assert_eq!(report.report_id, report.report_id);
+        assert_ne!(report.report_id, ReportId::default());
+    }
+
+    #[test]
+    fn test_hex_helpers() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let fields = [
+            (report.measurement_as_hex(), 0x90..0xc0),
+            (report.host_data_as_hex(), 0xc0..0xe0),
+            (report.id_key_digest_as_hex(), 0xe0..0x110),
+            (report.author_key_digest_as_hex(), 0x110..0x140),
+            (report.report_id_as_hex(), 0x140..0x160),
+            (report.report_id_ma_as_hex(), 0x160..0x180),
+            (report.chip_id_as_hex(), 0x1a0..0x1e0),
+        ];
+
+        for (hex, range) in fields {
+            assert_eq!(hex.len(), 2 * range.len());
+            assert_eq!(hex, hex::encode(&bytes[range]));
+            assert!(!hex.contains(|c: char| c.is_ascii_uppercase()));
+        }
+
+        assert_eq!(report.measurement_as_hex(), report.measurement.to_string());
+        assert_eq!(report.chip_id_as_hex(), report.chip_id_hex());
+    }
+
+    #[test]
+    fn test_find_in() {
+        let blob = hex::decode(include_bytes!(
+            "../../../../tests/certs_data/report_milan_in_blob.hex"
+        ))
+        .unwrap();
+        let expected = milan_report();
+
+        let (offset, report) = AttestationReport::find_in(&blob).unwrap();
+        assert_eq!(offset, 0x20);
+        assert_eq!(report, expected);
+
+        // The report alone is found at offset 0.
+        let (offset, _) = AttestationReport::find_in(&milan_report_bytes()).unwrap();
+        assert_eq!(offset, 0);
+
+        assert!(matches!(
+            AttestationReport::find_in(&blob[..0x20]),
+            Err(ReportSearchError::NotFound)
+        ));
+        assert!(matches!(
+            AttestationReport::find_in(&[0xa5; 4096]),
+            Err(ReportSearchError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_find_in_invalid_candidate() {
+        let mut blob = hex::decode(include_bytes!(
+            "../../../../tests/certs_data/report_milan_in_blob.hex"
+        ))
+        .unwrap();
+
+        // A set reserved byte fails the strict parse of the only candidate.
+        blob[0x20 + 0x1f8] = 0xff;
+
+        match AttestationReport::find_in(&blob) {
+            Err(ReportSearchError::InvalidCandidate { offset, .. }) => assert_eq!(offset, 0x20),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vmpl_checks() {
+        let mut report = AttestationReport {
+            vmpl: 2,
+            ..Default::default()
+        };
+
+        assert!(report.vmpl_matches(2));
+        assert!(!report.vmpl_matches(1));
+        assert!(!report.vmpl_matches(3));
+
+        assert!(report.vmpl_is_at_most(2));
+        assert!(report.vmpl_is_at_most(3));
+        assert!(!report.vmpl_is_at_most(1));
+
+        assert!(!report.vmpl_matches(4));
+        assert!(!report.vmpl_is_at_most(u32::MAX));
+
+        // A report claiming an invalid VMPL matches nothing.
+        report.vmpl = 7;
+        assert!((0..=MAX_VMPL).all(|vmpl| !report.vmpl_matches(vmpl)));
+        assert!(!report.vmpl_is_at_most(MAX_VMPL));
+    }
+
+    #[test]
+    fn test_guest_policy_check_abi() {
+        let report = AttestationReport {
+            policy: GuestPolicy(0x30000 | (1 << 8) | 51),
+            ..Default::default()
+        };
+
+        assert!(report.guest_policy_check_abi(1, 51));
+        assert!(report.guest_policy_check_abi(1, 0));
+        assert!(report.guest_policy_check_abi(0, 255));
+        assert!(!report.guest_policy_check_abi(1, 52));
+        assert!(!report.guest_policy_check_abi(2, 0));
+    }
+
+    #[test]
+    fn test_author_key_consistency() {
+        let mut report = AttestationReport::default();
+        assert!(!report.author_key_is_present());
+        assert!(report.validate_author_key_consistency().is_ok());
+
+        report.key_info = KeyInfo(1);
+        assert!(!report.author_key_is_present());
+        assert!(matches!(
+            report.validate_author_key_consistency(),
+            Err(AttestationReportError::AuthorKeyInconsistent {
+                author_key_en: true
+            })
+        ));
+
+        report.author_key_digest = [0x11; 48];
+        assert!(report.author_key_is_present());
+        assert!(report.validate_author_key_consistency().is_ok());
+
+        report.key_info = KeyInfo(0);
+        assert!(!report.author_key_is_present());
+        assert!(matches!(
+            report.validate_author_key_consistency(),
+            Err(AttestationReportError::AuthorKeyInconsistent {
+                author_key_en: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_is_migrated() {
+        let mut report = AttestationReport::default();
+        assert!(!report.is_migrated());
+
+        report.report_id_ma = [0xff; 32].into();
+        assert!(!report.is_migrated());
+
+        report.report_id_ma = [0x5a; 32].into();
+        assert!(report.is_migrated());
+
+        let report = milan_report();
+        assert!(!report.is_migrated());
+    }
+
+    #[test]
+    fn test_pretty_print_golden() {
+        let report = milan_report();
+
+        assert_eq!(
+            report.pretty_print(),
+            include_str!("../../../../tests/certs_data/report_milan_display.txt")
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_base64() {
+        use base64::Engine;
+
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let encoded = attestation_report_to_base64(&report).unwrap();
+        assert_eq!(
+            encoded,
+            base64::engine::general_purpose::STANDARD.encode(&bytes)
+        );
+        assert_eq!(attestation_report_from_base64(&encoded).unwrap(), report);
+        assert_eq!(
+            attestation_report_from_base64(&format!("{}\n", encoded)).unwrap(),
+            report
+        );
+
+        assert!(matches!(
+            attestation_report_from_base64("not base64!"),
+            Err(AttestationReportError::Base64(_))
+        ));
+        assert!(matches!(
+            attestation_report_from_base64(&encoded[..100]),
+            Err(AttestationReportError::UnexpectedEndOfData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sig_algo() {
+        let mut report = milan_report();
+        assert_eq!(report.sig_algo_type(), SigAlgo::EcdsaP384Sha384);
+        assert_eq!(report.sig_algo_name(), "ECDSA P-384 SHA-384");
+
+        report.sig_algo = 0;
+        assert_eq!(report.sig_algo_type(), SigAlgo::Unknown(0));
+        assert_eq!(report.sig_algo_name(), "Reserved");
+
+        report.sig_algo = 5;
+        assert_eq!(report.sig_algo_name(), "Unknown");
+        assert_eq!(report.sig_algo_type().to_string(), "Unknown(5)");
+        assert_eq!(u32::from(report.sig_algo_type()), 5);
+    }
+
+    #[test]
+    fn test_truncated_report() {
+        let bytes = milan_report_bytes();
+
+        for len in [0, 3, 100, 1183] {
+            assert!(matches!(
+                AttestationReport::try_from(&bytes[..len]),
+                Err(AttestationReportError::UnexpectedEndOfData {
+                    expected: 1184,
+                    actual,
+                }) if actual == len
+            ));
+        }
+
+        assert!(AttestationReport::try_from(&bytes[..1184]).is_ok());
+    }
+
+    #[test]
+    fn test_report_error_source() {
+        use std::error::Error;
+
+        let err = AttestationReportError::from(bincode::ErrorKind::SizeLimit);
+        assert!(err.source().is_some());
+
+        let err = AttestationReport::try_from(&milan_report_bytes()[..0x100]).unwrap_err();
+        assert!(err.source().is_none());
+
+        let err = AttestationReport::from_file(Path::new("/nonexistent/report.bin")).unwrap_err();
+        assert!(matches!(err, AttestationReportError::IoError(_)));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_kds_urls() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        const MILAN_VCEK_URL: &str = "https://kdsintf.amd.com/vcek/v1/Milan/\
+             d49554ec717f4e5b0fe6b143bcf0405bd7ae304727edf46603f2a76aef6a3abc\
+             15d7af38db757039029f0efacfd08e244324884738c72b082e2f87a44d541eb6\
+             ?blSPL=03&teeSPL=00&snpSPL=08&ucodeSPL=115";
+
+        assert_eq!(
+            report.kds_vcek_url_for(Generation::Milan).unwrap(),
+            MILAN_VCEK_URL
+        );
+        assert_eq!(
+            AttestationReport::default().kds_vcek_url_for(Generation::Milan),
+            Err(ChipIdError::ChipIdMasked)
+        );
+        assert_eq!(
+            AttestationReport::kds_vlek_url_for(Generation::Genoa),
+            "https://kdsintf.amd.com/vlek/v1/Genoa/cert_chain"
+        );
+
+        // Version 2 does not tell the product.
+        assert_eq!(report.kds_vcek_url(), Err(ChipIdError::UnknownProduct));
+        assert_eq!(report.kds_vlek_url(), Err(ChipIdError::UnknownProduct));
+
+        let mut report = report;
+        report.version = NormalizedReport::V3;
+        report.set_v3_fields(0x19, 0x01, 0x01, true);
+        assert_eq!(report.kds_vcek_url().unwrap(), MILAN_VCEK_URL);
+        assert_eq!(
+            report.kds_vlek_url().unwrap(),
+            "https://kdsintf.amd.com/vlek/v1/Milan/cert_chain"
+        );
+
+        report.set_v3_fields(0x19, 0x11, 0x01, true);
+        assert_eq!(
+            report.kds_vlek_url().unwrap(),
+            "https://kdsintf.amd.com/vlek/v1/Genoa/cert_chain"
+        );
+
+        // Turin is not served under this URL scheme.
+        report.set_v3_fields(0x1a, 0x02, 0x00, true);
+        assert_eq!(report.kds_vcek_url(), Err(ChipIdError::UnknownProduct));
+    }
+
+    #[test]
+    fn test_chip_id_hex() {
+        const CHIP_ID: &str = "d49554ec717f4e5b0fe6b143bcf0405bd7ae304727edf46603f2a76aef6a3abc\
+                               15d7af38db757039029f0efacfd08e244324884738c72b082e2f87a44d541eb6";
+
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(report.chip_id_hex(), CHIP_ID);
+        assert_eq!(
+            parse_chip_id(CHIP_ID).unwrap(),
+            <[u8; 64]>::from(report.chip_id)
+        );
+        assert_eq!(
+            parse_chip_id(&format!("0x{}", CHIP_ID.to_uppercase())).unwrap(),
+            <[u8; 64]>::from(report.chip_id)
+        );
+
+        assert_eq!(
+            parse_chip_id(&CHIP_ID[..64]),
+            Err(ChipIdError::InvalidLength(32))
+        );
+        assert!(matches!(
+            parse_chip_id("0xzz"),
+            Err(ChipIdError::InvalidHex(_))
+        ));
+    }
+
+    #[test]
+    fn test_guest_field_select_all_none() {
+        let all = GuestFieldSelect::all();
+
+        assert_eq!(all.0, 0x7f);
+        assert_eq!(
+            all.to_string(),
+            "guest_policy,image_id,family_id,measurement,guest_svn,tcb_version,launch_mit_vector"
+        );
+        assert_eq!(GuestFieldSelect::none().0, 0);
+    }
+
+    #[test]
+    fn test_guest_field_select_from_bits() {
+        assert_eq!(GuestFieldSelect::from_bits(0).unwrap().0, 0);
+        assert_eq!(GuestFieldSelect::from_bits(0x7f).unwrap().0, 0x7f);
+        assert_eq!(
+            GuestFieldSelect::from_bits(0x80).unwrap_err(),
+            GuestFieldSelectError::InvalidBits(0x80)
+        );
+        assert_eq!(
+            GuestFieldSelect::from_bits(u64::MAX).unwrap_err(),
+            GuestFieldSelectError::InvalidBits(!0x7f)
+        );
+    }
+
+    #[test]
+    fn test_guest_field_select_serde() {
+        let selection = GuestFieldSelect::new()
+            .with_measurement(true)
+            .with_guest_svn(true);
+
+        let bits = bincode::serialize(&selection).unwrap();
+        assert_eq!(bits, 0x18u64.to_le_bytes());
+        assert_eq!(
+            bincode::deserialize::<GuestFieldSelect>(&bits).unwrap().0,
+            0x18
+        );
+        assert!(bincode::deserialize::<GuestFieldSelect>(&0x80u64.to_le_bytes()).is_err());
+
+        let text = toml::to_string(&selection).unwrap();
+        assert!(text.contains("measurement = true"));
+        assert!(text.contains("guest_policy = false"));
+        assert_eq!(toml::from_str::<GuestFieldSelect>(&text).unwrap().0, 0x18);
+        assert_eq!(
+            toml::from_str::<GuestFieldSelect>("tcb_version = true")
+                .unwrap()
+                .0,
+            0x20
+        );
+        assert!(toml::from_str::<GuestFieldSelect>("chip_id = true").is_err());
+    }
+
+    #[test]
+    fn test_derived_key_checked() {
+        let mut report = AttestationReport {
+            current_major: 1,
+            current_minor: 57,
+            current_build: 9,
+            ..Default::default()
+        };
+
+        let capabilities = report.firmware_capabilities();
+        assert_eq!(capabilities.version.to_string(), "1.57");
+        assert_eq!(capabilities.build, 9);
+
+        let plain = DerivedKey::new(
+            false,
+            GuestFieldSelect::new().with_measurement(true),
+            0,
+            0,
+            0,
+            None,
+        );
+        assert!(plain.checked(&capabilities).is_ok());
+
+        // Either the value or the selection bit needs firmware 1.58.
+        let with_vector = plain.with_launch_mit_vector(1);
+        let with_bit = DerivedKey::new(
+            false,
+            GuestFieldSelect::new().with_launch_mit_vector(true),
+            0,
+            0,
+            0,
+            None,
+        );
+
+        for request in [with_vector, with_bit] {
+            assert!(matches!(
+                request.checked(&capabilities),
+                Err(UserApiError::UnsupportedFirmware {
+                    required: FirmwareCapabilities::LAUNCH_MIT_VECTOR,
+                    ..
+                })
+            ));
+
+            report.current_minor = 58;
+            assert!(request.checked(&report.firmware_capabilities()).is_ok());
+            report.current_minor = 57;
+        }
+    }
+
+    #[test]
+    fn test_derived_key_launch_mit_vector_gate() {
+        let capabilities = FirmwareCapabilities::new(
+            Version {
+                major: 1,
+                minor: 58,
+            },
+            0,
+        );
+        let request = DerivedKey::new(
+            false,
+            GuestFieldSelect::new().with_launch_mit_vector(true),
+            0,
+            0,
+            0,
+            Some(1),
+        );
+
+        assert!(matches!(
+            request.check_launch_mit_vector(),
+            Err(UserApiError::LaunchMitVectorUnchecked)
+        ));
+        assert!(request
+            .checked(&capabilities)
+            .unwrap()
+            .check_launch_mit_vector()
+            .is_ok());
+
+        // Without a vector, there is nothing for the kernel to drop.
+        let request = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0, None);
+        assert!(request.check_launch_mit_vector().is_ok());
+    }
+
+    #[test]
+    fn test_derived_key_serde() {
+        let key: DerivedKey = toml::from_str(
+            r#"
+            root_key_select = true
+            vmpl = 1
+            tcb_version = 0x7300000000000803
+            launch_mit_vector = 5
+
+            [guest_field_select]
+            measurement = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(key.get_root_key_select(), 1);
+        assert_eq!(key.guest_field_select.0, 0x8);
+        assert_eq!((key.vmpl, key.guest_svn), (1, 0));
+        assert_eq!(key.tcb_version, 0x7300000000000803);
+        assert_eq!(key.get_launch_mit_vector(), Some(5));
+
+        let text = toml::to_string(&key).unwrap();
+        let parsed: DerivedKey = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.get_root_key_select(), 1);
+        assert_eq!(parsed.guest_field_select.0, 0x8);
+
+        let default: DerivedKey = toml::from_str("").unwrap();
+        assert_eq!(default.get_root_key_select(), 0);
+        assert_eq!(default.guest_field_select.0, 0);
+
+        assert!(toml::from_str::<DerivedKey>("_reserved_0 = 1").is_err());
+    }
+
+    #[test]
+    fn test_secret_bytes_redacted() {
+        let secret = SecretBytes::from([0x42u8; 32]);
+
+        assert_eq!(format!("{secret:?}"), "[REDACTED 32 bytes]");
+        assert_eq!(secret.expose_secret(), &[0x42u8; 32]);
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn test_secret_bytes_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut secret = SecretBytes::from([0x42u8; 32]);
+        secret.zeroize();
+
+        assert_eq!(secret.expose_secret(), &[0u8; 32]);
+    }
+
+    #[test]
+    fn test_report_data_from_nonce() {
+        let data = ReportData::from_nonce(b"nonce").unwrap();
+
+        assert_eq!(&data[..5], b"nonce");
+        assert!(data[5..].iter().all(|b| *b == 0));
+
+        assert!(ReportData::from_nonce(&[0xff; 64]).is_ok());
+        assert_eq!(
+            ReportData::from_nonce(&[0xff; 65]),
+            Err(ReportDataError::NonceTooLong(65))
+        );
+    }
+
+    #[test]
+    fn test_report_data_is_zero_padded() {
+        let data = ReportData::from_nonce(b"nonce").unwrap();
+        assert!(ReportData::is_zero_padded(&data, 5));
+
+        let mut data = [0u8; 64];
+        data[..4].copy_from_slice(b"abcd");
+        data[40] = 1;
+
+        assert!(!ReportData::is_zero_padded(&data, 4));
+        assert!(ReportData::is_zero_padded(&data, 41));
+        assert!(ReportData::is_zero_padded(&[0xff; 64], 64));
+        assert!(ReportData::is_zero_padded(&[0xff; 64], 100));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    fn test_report_data_hashes() {
+        let sha512 = hex::decode(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        )
+        .unwrap();
+        let sha256 =
+            hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                .unwrap();
+
+        assert_eq!(ReportData::from_hash_sha512(b"abc")[..], sha512[..]);
+
+        let padded = ReportData::from_hash_sha256_padded(b"abc");
+        assert_eq!(padded[..32], sha256[..]);
+        assert_eq!(padded[32..], [0u8; 32]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    fn test_report_data_matches_payload() {
+        let schemes = [
+            (
+                ReportDataScheme::Nonce,
+                ReportData::from_nonce(b"abc").unwrap(),
+            ),
+            (
+                ReportDataScheme::Sha512,
+                ReportData::from_hash_sha512(b"abc"),
+            ),
+            (
+                ReportDataScheme::Sha256Padded,
+                ReportData::from_hash_sha256_padded(b"abc"),
+            ),
+        ];
+
+        for (scheme, report_data) in schemes {
+            let report = AttestationReport {
+                report_data,
+                ..Default::default()
+            };
+
+            assert!(report.report_data_matches_payload(b"abc", scheme));
+            assert!(!report.report_data_matches_payload(b"abd", scheme));
+        }
+
+        let report = AttestationReport::default();
+        assert!(!report.report_data_matches_payload(&[0; 65], ReportDataScheme::Nonce));
+    }
+
+    #[test]
+    fn test_diff() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert!(report.diff(&report).is_empty());
+
+        let mut changed = bytes.clone();
+        changed[0x90] ^= 0x01;
+        let other = AttestationReport::try_from(changed.as_slice()).unwrap();
+
+        let diffs = report.diff(&other);
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].field, "measurement");
+        assert_eq!(diffs[0].left, hex::encode(&bytes[0x90..0xc0]));
+        assert_eq!(diffs[0].right, hex::encode(&changed[0x90..0xc0]));
+
+        let mut changed = bytes.clone();
+        changed[0] = 3;
+        let other = AttestationReport::try_from(changed.as_slice()).unwrap();
+
+        assert_eq!(
+            report.diff(&other),
+            vec![FieldDiff {
+                field: "version",
+                left: "2".to_string(),
+                right: "3".to_string(),
+            }]
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_round_trip() {
+        let bytes: Vec<u8> = (0..std::mem::size_of::<AttestationReport>())
+            .map(|i| (i % 255 + 1) as u8)
+            .collect();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let cbor = report.to_cbor().unwrap();
+        assert_eq!(AttestationReport::from_cbor(&cbor).unwrap(), report);
+
+        // A byte string header (major type 2, 16-bit length) and the raw report.
+        assert_eq!(cbor[..3], [0x59, 0x04, 0xa0]);
+        assert_eq!(cbor[3..], bytes[..]);
+
+        assert!(matches!(
+            AttestationReport::from_cbor(&cbor[..cbor.len() - 1]),
+            Err(CborError::Decode(_))
+        ));
+
+        let mut short = vec![0x59, 0x04, 0x9f];
+        short.extend_from_slice(&bytes[1..]);
+        assert!(matches!(
+            AttestationReport::from_cbor(&short),
+            Err(CborError::InvalidLength(0x49f))
+        ));
+    }
+
+    #[cfg(all(feature = "cbor", feature = "serde_json"))]
+    #[test]
+    fn test_cbor_smaller_than_json() {
+        let report = milan_report();
+
+        let cbor = report.to_cbor().unwrap();
+        let json = report.to_json().unwrap();
+
+        assert!(cbor.len() < json.len());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_claims_snapshot() {
+        let report = milan_report();
+
+        let expected: BTreeMap<String, serde_json::Value> = serde_json::from_str(include_str!(
+            "../../../../tests/certs_data/report_milan_claims.json"
+        ))
+        .unwrap();
+
+        assert_eq!(report.to_claims(), expected);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_claims_snapshot_v3() {
+        let mut report = milan_report();
+        report.version = NormalizedReport::V3;
+        report.set_v3_fields(0x19, 0x01, 0x01, true);
+
+        let expected: BTreeMap<String, serde_json::Value> = serde_json::from_str(include_str!(
+            "../../../../tests/certs_data/report_milan_v3_claims.json"
+        ))
+        .unwrap();
+
+        assert_eq!(report.to_claims(), expected);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_claims_turin_tcb() {
+        let mut report = AttestationReport {
+            version: NormalizedReport::V3,
+            reported_tcb: TcbVersion::from_le_bytes([1, 2, 3, 4, 0, 0, 0, 5]),
+            ..Default::default()
+        };
+        report.set_v3_fields(0x1a, 0x02, 0x00, false);
+
+        let claims = report.to_claims();
+        assert_eq!(claims["tcb.reported.fmc"], 1);
+        assert_eq!(claims["tcb.reported.bootloader"], 2);
+        assert_eq!(claims["tcb.reported.tee"], 3);
+        assert_eq!(claims["tcb.reported.snp"], 4);
+        assert_eq!(claims["tcb.reported.microcode"], 5);
+        assert_eq!(claims["platform_info.alias_check_complete"], false);
+
+        // Earlier processors have no FMC SVN.
+        report.set_v3_fields(0x19, 0x11, 0x01, false);
+        assert!(!report.to_claims().contains_key("tcb.reported.fmc"));
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_round_trip() {
+        // Every byte set, so that no field round trips by accident as zero.
+        let bytes: Vec<u8> = (0..std::mem::size_of::<AttestationReport>())
+            .map(|i| (i % 255 + 1) as u8)
+            .collect();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let json = report.to_json().unwrap();
+        let parsed = AttestationReport::from_json(&json).unwrap();
+
+        assert_eq!(parsed, report);
+        assert_eq!(bincode::serialize(&parsed).unwrap(), bytes);
+
+        let report = milan_report();
+        assert_eq!(
+            AttestationReport::from_json(&report.to_json().unwrap()).unwrap(),
+            report
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_strict() {
+        let report = milan_report();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+
+        let strict =
+            |value: &serde_json::Value| AttestationReport::from_json_strict(&value.to_string());
+        assert_eq!(strict(&json).unwrap(), report);
+
+        // Unknown fields, at the top level and nested.
+        let mut extra = json.clone();
+        extra["cpuid_fam_id"] = 0x19.into();
+        let err = strict(&extra).unwrap_err();
+        assert!(matches!(err, JsonReportError::UnknownField(_)));
+        assert_eq!(err.pointer(), Some("/cpuid_fam_id"));
+        assert_eq!(
+            AttestationReport::from_json(&extra.to_string()).unwrap(),
+            report
+        );
+
+        let mut extra = json.clone();
+        extra["current_tcb"]["fmc"] = 0.into();
+        assert_eq!(
+            strict(&extra).unwrap_err().pointer(),
+            Some("/current_tcb/fmc")
+        );
+
+        let mut missing = json.clone();
+        missing.as_object_mut().unwrap().remove("host_data");
+        assert!(matches!(
+            strict(&missing),
+            Err(JsonReportError::MissingField(pointer)) if pointer == "/host_data"
+        ));
+
+        // Arrays of the wrong length.
+        let mut short = json.clone();
+        short["measurement"].as_array_mut().unwrap().pop();
+        assert!(matches!(
+            strict(&short),
+            Err(JsonReportError::InvalidLength {
+                pointer,
+                expected: 48,
+                actual: 47,
+            }) if pointer == "/measurement"
+        ));
+
+        let mut wrong_type = json.clone();
+        wrong_type["chip_id"][3] = "ff".into();
+        assert_eq!(
+            strict(&wrong_type).unwrap_err().pointer(),
+            Some("/chip_id/3")
+        );
+
+        let mut version = json;
+        version["version"] = 9.into();
+        assert!(matches!(
+            strict(&version),
+            Err(JsonReportError::UnsupportedVersion(9))
+        ));
+    }
+
+    #[test]
+    fn test_hash_set_dedup() {
+        use std::collections::HashSet;
+
+        let report = milan_report();
+
+        let reports: HashSet<AttestationReport> = [report, report].iter().copied().collect();
+        assert_eq!(reports.len(), 1);
+
+        let policies: HashSet<GuestPolicy> = [GuestPolicy(0x30000), GuestPolicy(0x30000)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(policies.len(), 1);
+
+        let tcbs: HashSet<TcbVersion> = [
+            report.reported_tcb,
+            report.current_tcb,
+            TcbVersion::default(),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        assert!(tcbs.contains(&report.reported_tcb));
+        assert!(tcbs.contains(&TcbVersion::default()));
+
+        let key_infos: HashSet<KeyInfo> = [KeyInfo(0), KeyInfo(0), KeyInfo(1)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(key_infos.len(), 2);
+    }
+
+    #[test]
+    fn test_key_info_signing_key() {
+        assert_eq!(KeyInfo(0b00000).signing_key_type(), SigningKey::Vcek);
+        assert_eq!(KeyInfo(0b00101).signing_key_type(), SigningKey::Vlek);
+        assert_eq!(KeyInfo(0b01010).signing_key_type(), SigningKey::Reserved(2));
+        assert_eq!(KeyInfo(0b11100).signing_key_type(), SigningKey::None);
+
+        assert!(KeyInfo(0b00010).is_vcek_signed());
+        assert!(KeyInfo(0b00100).is_vlek_signed());
+        assert!(KeyInfo(0b11101).is_unsigned());
+        assert!(!KeyInfo(0b01100).is_unsigned());
+    }
+
+    #[test]
+    fn test_signing_key_encodings() {
+        let expected = [
+            (SigningKey::Vcek, "VCEK"),
+            (SigningKey::Vlek, "VLEK"),
+            (SigningKey::Reserved(2), "Reserved (2)"),
+            (SigningKey::Reserved(3), "Reserved (3)"),
+            (SigningKey::Reserved(4), "Reserved (4)"),
+            (SigningKey::Reserved(5), "Reserved (5)"),
+            (SigningKey::Reserved(6), "Reserved (6)"),
+            (SigningKey::None, "None"),
+        ];
+
+        for (value, (key, name)) in expected.iter().enumerate() {
+            let key_info = KeyInfo((value as u32) << 2);
+
+            assert_eq!(key_info.signing_key_type(), *key);
+            assert_eq!(SigningKey::from(value as u32), *key);
+            assert_eq!(key.to_string(), *name);
+        }
+
+        let report = AttestationReport {
+            key_info: KeyInfo(0b101),
+            ..Default::default()
+        };
+        assert_eq!(report.key_info().signing_key_type(), SigningKey::Vlek);
+        assert!(report.key_info().author_key_enabled());
+        assert!(!KeyInfo(0b100).author_key_enabled());
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(report.version, 2);
+        assert!(report.key_info.is_vcek_signed());
+        assert_eq!(bincode::serialize(&report).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_try_from_short_bytes() {
+        let bytes = milan_report_bytes();
+
+        assert!(AttestationReport::try_from(&bytes[..0x100]).is_err());
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("sev-report-{}.bin", std::process::id()));
+        report.write_to_file(&path).unwrap();
+        let read_back = AttestationReport::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bincode::serialize(&read_back.unwrap()).unwrap(), bytes);
     }
 }
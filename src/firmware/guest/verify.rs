@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the claims made by an attestation report against values
+//! expected by the verifier.
+
+use crate::{error::MeasurementError, firmware::guest::AttestationReport};
+
+/// Check that the launch measurement of the report matches the expected
+/// one, e.g. as computed for the OVMF image with the `measurement` module.
+///
+/// The comparison runs in constant time.
+pub fn verify_measurement(
+    report: &AttestationReport,
+    expected: &[u8; 48],
+) -> Result<(), MeasurementError> {
+    match report.measurement.verify_against_expected(expected) {
+        true => Ok(()),
+        false => Err(MeasurementError::Mismatch {
+            expected: *expected,
+            actual: report.measurement.into(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::firmware::guest::testing::fake_v2_report_with_measurement;
+
+    #[test]
+    fn test_verify_measurement() {
+        let measurement = [0x5au8; 48];
+        let report = fake_v2_report_with_measurement(measurement);
+
+        assert!(verify_measurement(&report, &measurement).is_ok());
+
+        let mut expected = measurement;
+        expected[47] ^= 1;
+
+        match verify_measurement(&report, &expected) {
+            Err(MeasurementError::Mismatch {
+                expected: e,
+                actual: a,
+            }) => {
+                assert_eq!(e, expected);
+                assert_eq!(a, measurement);
+            }
+            _ => panic!("expected a measurement mismatch"),
+        }
+    }
+}
@@ -17,6 +17,8 @@ use super::linux::host::types::{
 #[cfg(target_os = "linux")]
 use crate::error::*;
 
+use serde::{Deserialize, Serialize};
+
 #[cfg(feature = "sev")]
 #[cfg(target_os = "linux")]
 use crate::{
@@ -40,12 +42,25 @@ use std::convert::TryInto;
 
 #[cfg(feature = "snp")]
 #[cfg(target_os = "linux")]
-use super::linux::host::types::SnpCommit;
+use super::linux::host::types::{SnpCommit, SnpSetConfig};
 
 /// The CPU-unique identifier for the platform.
-#[derive(Clone, Debug, PartialEq, Eq)]
+///
+/// It is 64 bytes long on Milan and Genoa, and may be shorter on newer
+/// parts. [Display](std::fmt::Display) prints it as continuous uppercase hex.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Identifier(pub Vec<u8>);
 
+impl Identifier {
+    /// The identifier as continuous lowercase hex, as it appears in the
+    /// `hwid` path segment of KDS VCEK URLs (and as
+    /// [chip_id_hex](crate::firmware::guest::AttestationReport::chip_id_hex)
+    /// prints the chip ID of a report).
+    pub fn kds_hwid(&self) -> String {
+        hex::encode(&self.0)
+    }
+}
+
 impl From<Identifier> for Vec<u8> {
     fn from(id: Identifier) -> Vec<u8> {
         id.0
@@ -62,6 +77,39 @@ impl std::fmt::Display for Identifier {
     }
 }
 
+/// The length of the identifier of parts whose kernel does not report it.
+#[cfg(target_os = "linux")]
+#[cfg(any(feature = "sev", feature = "snp"))]
+const DEFAULT_ID_LEN: usize = 64;
+
+/// Issues GET_ID2 through `ioctl`: first with an empty buffer, which the
+/// firmware rejects while reporting the length of the identifier, then
+/// with a buffer of that length.
+#[cfg(target_os = "linux")]
+#[cfg(any(feature = "sev", feature = "snp"))]
+fn request_identifier<F>(mut ioctl: F) -> Result<Identifier, Indeterminate<Error>>
+where
+    F: FnMut(&mut GetId<'_>) -> Result<(), Indeterminate<Error>>,
+{
+    let mut empty = [0u8; 0];
+    let mut probe = GetId::new(&mut empty);
+
+    // Only the length written back matters, the request itself fails.
+    let _ = ioctl(&mut probe);
+
+    let len = match probe.id_len() {
+        0 => DEFAULT_ID_LEN,
+        len => len,
+    };
+
+    let mut bytes = vec![0u8; len];
+    let mut id = GetId::new(&mut bytes);
+
+    ioctl(&mut id)?;
+
+    Ok(Identifier(id.as_slice().to_vec()))
+}
+
 /// A handle to the SEV platform.
 #[cfg(target_os = "linux")]
 pub struct Firmware(File);
@@ -77,6 +125,15 @@ impl Firmware {
 
     /// Reset the platform persistent state.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PLATFORM_RESET")
+        )
+    )]
     pub fn platform_reset(&mut self) -> Result<(), Indeterminate<Error>> {
         PLATFORM_RESET.ioctl(&mut self.0, &mut Command::from(&PlatformReset))?;
         Ok(())
@@ -84,6 +141,15 @@ impl Firmware {
 
     /// Query the platform status.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PLATFORM_STATUS")
+        )
+    )]
     pub fn platform_status(&mut self) -> Result<Status, Indeterminate<Error>> {
         let mut info: PlatformStatus = Default::default();
         PLATFORM_STATUS.ioctl(&mut self.0, &mut Command::from_mut(&mut info))?;
@@ -109,6 +175,15 @@ impl Firmware {
 
     /// Generate a new Platform Encryption Key (PEK).
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PEK_GEN")
+        )
+    )]
     pub fn pek_generate(&mut self) -> Result<(), Indeterminate<Error>> {
         PEK_GEN.ioctl(&mut self.0, &mut Command::from(&PekGen))?;
         Ok(())
@@ -116,6 +191,15 @@ impl Firmware {
 
     /// Request a signature for the PEK.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PEK_CSR")
+        )
+    )]
     pub fn pek_csr(&mut self) -> Result<Certificate, Indeterminate<Error>> {
         #[allow(clippy::uninit_assumed_init)]
         let mut pek: Certificate = unsafe { MaybeUninit::uninit().assume_init() };
@@ -127,6 +211,15 @@ impl Firmware {
 
     /// Generate a new Platform Diffie-Hellman (PDH) key pair.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PDH_GEN")
+        )
+    )]
     pub fn pdh_generate(&mut self) -> Result<(), Indeterminate<Error>> {
         PDH_GEN.ioctl(&mut self.0, &mut Command::from(&PdhGen))?;
         Ok(())
@@ -134,6 +227,15 @@ impl Firmware {
 
     /// Export the SEV certificate chain.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PDH_CERT_EXPORT")
+        )
+    )]
     pub fn pdh_cert_export(&mut self) -> Result<Chain, Indeterminate<Error>> {
         #[allow(clippy::uninit_assumed_init)]
         let mut chain: [Certificate; 3] = unsafe { MaybeUninit::uninit().assume_init() };
@@ -153,6 +255,15 @@ impl Firmware {
 
     /// Take ownership of the SEV platform.
     #[cfg(feature = "sev")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "PEK_CERT_IMPORT")
+        )
+    )]
     pub fn pek_cert_import(
         &mut self,
         pek: &Certificate,
@@ -167,14 +278,19 @@ impl Firmware {
     ///
     /// This is especially helpful for sending AMD an HTTP request to fetch
     /// the signed CEK certificate.
+    ///
+    /// The length of the identifier is queried from the firmware first, so
+    /// that both 64-byte and shorter identifiers are returned whole.
     #[cfg(any(feature = "sev", feature = "snp"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "ioctl", level = "debug", skip_all, fields(request = "GET_ID"))
+    )]
     pub fn get_identifier(&mut self) -> Result<Identifier, Indeterminate<Error>> {
-        let mut bytes = [0u8; 64];
-        let mut id = GetId::new(&mut bytes);
-
-        GET_ID.ioctl(&mut self.0, &mut Command::from_mut(&mut id))?;
-
-        Ok(Identifier(id.as_slice().to_vec()))
+        request_identifier(|id| {
+            GET_ID.ioctl(&mut self.0, &mut Command::from_mut(id))?;
+            Ok(())
+        })
     }
 
     /// Query the SNP platform status.
@@ -188,6 +304,15 @@ impl Firmware {
     /// let status: PlatformStatus = firmware.platform_status().unwrap();
     /// ```
     #[cfg(feature = "snp")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_PLATFORM_STATUS")
+        )
+    )]
     pub fn snp_platform_status(&mut self) -> Result<SnpPlatformStatus, Indeterminate<Error>> {
         let mut platform_status: SnpPlatformStatus = SnpPlatformStatus::default();
 
@@ -209,6 +334,15 @@ impl Firmware {
     /// let status: bool = firmware.snp_commit().unwrap();
     /// ```
     #[cfg(feature = "snp")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_COMMIT")
+        )
+    )]
     pub fn snp_commit(&mut self) -> Result<(), UserApiError> {
         let mut buf: SnpCommit = Default::default();
         SNP_COMMIT.ioctl(&mut self.0, &mut Command::from_mut(&mut buf))?;
@@ -218,6 +352,9 @@ impl Firmware {
 
     /// Set the SNP Configuration.
     ///
+    /// Fails with [UserApiError::ReportedTcbTooHigh] if the firmware rejects
+    /// the reported TCB for being newer than the platform's current TCB.
+    ///
     /// # Example:
     /// ```ignore
     /// let configuration = Config::new(
@@ -229,11 +366,22 @@ impl Firmware {
     /// let status: bool = firmware.snp_set_config(configuration).unwrap();
     /// ```
     #[cfg(feature = "snp")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_SET_CONFIG")
+        )
+    )]
     pub fn snp_set_config(&mut self, new_config: Config) -> Result<(), UserApiError> {
-        SNP_SET_CONFIG.ioctl(
-            &mut self.0,
-            &mut Command::from_mut(&mut new_config.try_into()?),
-        )?;
+        let mut set_config: SnpSetConfig = new_config.try_into()?;
+        let mut command = Command::from_mut(&mut set_config);
+
+        SNP_SET_CONFIG
+            .ioctl(&mut self.0, &mut command)
+            .map_err(|e| set_config_error(command.error, e))?;
 
         Ok(())
     }
@@ -241,6 +389,9 @@ impl Firmware {
     #[cfg(feature = "snp")]
     /// Insert a Version Loaded Endorsement Key Hashstick into the AMD Secure Processor.
     ///
+    /// See [snp_vlek_load_checked](Self::snp_vlek_load_checked) to reject
+    /// firmware without VLEK support before submitting the hashstick.
+    ///
     /// # Example:
     /// ```ignore
     /// # Read the VLEK Hashstick Bytes into your application.
@@ -250,17 +401,82 @@ impl Firmware {
     ///
     /// firmware.snp_vlek_load(hashstick_bytes.as_slice()).unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ioctl",
+            level = "debug",
+            skip_all,
+            fields(request = "SNP_VLEK_LOAD")
+        )
+    )]
     pub fn snp_vlek_load(&mut self, hashstick_bytes: &[u8]) -> Result<(), UserApiError> {
         use types::FFI::types::{SnpVlekLoad, WrappedVlekHashstick};
 
         let parsed_bytes: WrappedVlekHashstick = hashstick_bytes.try_into()?;
 
         let mut vlek_load: SnpVlekLoad = SnpVlekLoad::new(&parsed_bytes);
+        let mut command = Command::from_mut(&mut vlek_load);
 
-        SNP_VLEK_LOAD.ioctl(&mut self.0, &mut Command::from_mut(&mut vlek_load))?;
+        SNP_VLEK_LOAD
+            .ioctl(&mut self.0, &mut command)
+            .map_err(|e| vlek_load_error(command.error, e))?;
 
         Ok(())
     }
+
+    #[cfg(feature = "snp")]
+    /// Insert a VLEK hashstick like [snp_vlek_load](Self::snp_vlek_load),
+    /// failing with [UserApiError::UnsupportedFirmware] instead of an opaque
+    /// firmware error when `capabilities` predate [FirmwareCapabilities::VLEK].
+    ///
+    /// # Example:
+    /// ```ignore
+    /// let mut firmware: Firmware = Firmware::open().unwrap();
+    /// let capabilities = firmware.snp_platform_status().unwrap().capabilities();
+    ///
+    /// firmware
+    ///     .snp_vlek_load_checked(hashstick_bytes.as_slice(), &capabilities)
+    ///     .unwrap();
+    /// ```
+    pub fn snp_vlek_load_checked(
+        &mut self,
+        hashstick_bytes: &[u8],
+        capabilities: &FirmwareCapabilities,
+    ) -> Result<(), UserApiError> {
+        capabilities.require("the VLEK", FirmwareCapabilities::VLEK)?;
+
+        self.snp_vlek_load(hashstick_bytes)
+    }
+}
+
+/// Maps the firmware status of a failed SNP_VLEK_LOAD, falling back to the
+/// ioctl error when the firmware did not report one.
+#[cfg(all(feature = "snp", target_os = "linux"))]
+fn vlek_load_error(status: u32, io_error: std::io::Error) -> UserApiError {
+    match status {
+        0 => io_error.into(),
+        _ => match status.into() {
+            Indeterminate::Known(Error::InvalidKey) => HashstickError::InvalidKey.into(),
+            Indeterminate::Known(Error::BadSvn) => HashstickError::TcbMismatch.into(),
+            Indeterminate::Known(error) => error.into(),
+            Indeterminate::Unknown => UserApiError::Unknown,
+        },
+    }
+}
+
+/// Maps the firmware status of a failed SNP_SET_CONFIG, falling back to the
+/// ioctl error when the firmware did not report one.
+#[cfg(all(feature = "snp", target_os = "linux"))]
+fn set_config_error(status: u32, io_error: std::io::Error) -> UserApiError {
+    match status {
+        0 => io_error.into(),
+        _ => match status.into() {
+            Indeterminate::Known(Error::BadSvn) => UserApiError::ReportedTcbTooHigh,
+            Indeterminate::Known(error) => error.into(),
+            Indeterminate::Unknown => UserApiError::Unknown,
+        },
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -269,3 +485,109 @@ impl AsRawFd for Firmware {
         self.0.as_raw_fd()
     }
 }
+
+#[cfg(all(test, feature = "snp", target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// Stands in for the kernel's GET_ID2 handler on a part with an ID of
+    /// `id_len` bytes, reading and writing the request through its C layout
+    /// (`struct sev_user_data_get_id2`).
+    fn get_id2(
+        id_len: usize,
+        reports_len: bool,
+    ) -> impl FnMut(&mut GetId<'_>) -> Result<(), Indeterminate<Error>> {
+        move |request| {
+            assert_eq!(std::mem::size_of::<GetId<'_>>(), 12);
+
+            let raw =
+                unsafe { std::slice::from_raw_parts_mut(request as *mut GetId<'_> as *mut u8, 12) };
+            let address = u64::from_ne_bytes(raw[..8].try_into().unwrap());
+            let length = u32::from_ne_bytes(raw[8..].try_into().unwrap()) as usize;
+
+            if reports_len {
+                raw[8..].copy_from_slice(&(id_len as u32).to_ne_bytes());
+            }
+
+            if address == 0 || length < id_len {
+                return Err(Indeterminate::Known(Error::InvalidLen));
+            }
+
+            let id = unsafe { std::slice::from_raw_parts_mut(address as *mut u8, id_len) };
+            for (i, byte) in id.iter_mut().enumerate() {
+                *byte = i as u8;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_request_identifier() {
+        let expected: Vec<u8> = (0..64).collect();
+
+        let id = request_identifier(get_id2(64, true)).unwrap();
+        assert_eq!(id.0, expected);
+
+        let id = request_identifier(get_id2(32, true)).unwrap();
+        assert_eq!(id.0, &expected[..32]);
+        assert_eq!(id.to_string(), hex::encode_upper(&expected[..32]));
+        assert_eq!(id.kds_hwid(), hex::encode(&expected[..32]));
+
+        // Kernels which do not report the length get the full 64 bytes.
+        let id = request_identifier(get_id2(64, false)).unwrap();
+        assert_eq!(id.0, expected);
+    }
+
+    #[test]
+    fn test_identifier_serde() {
+        let id = Identifier(vec![0xab; 64]);
+        let bytes = bincode::serialize(&id).unwrap();
+
+        assert_eq!(bincode::deserialize::<Identifier>(&bytes).unwrap(), id);
+    }
+
+    #[test]
+    fn test_vlek_load_error() {
+        let io_error = || std::io::Error::from_raw_os_error(libc::EIO);
+
+        assert!(matches!(
+            vlek_load_error(0x27, io_error()),
+            UserApiError::HashstickError(HashstickError::InvalidKey)
+        ));
+        assert!(matches!(
+            vlek_load_error(0x21, io_error()),
+            UserApiError::HashstickError(HashstickError::TcbMismatch)
+        ));
+        assert!(matches!(
+            vlek_load_error(0x01, io_error()),
+            UserApiError::FirmwareError(Error::InvalidPlatformState)
+        ));
+        assert!(matches!(
+            vlek_load_error(0, io_error()),
+            UserApiError::FirmwareError(Error::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_config_error() {
+        let io_error = || std::io::Error::from_raw_os_error(libc::EIO);
+
+        assert!(matches!(
+            set_config_error(0x16, io_error()),
+            UserApiError::FirmwareError(Error::InvalidParam)
+        ));
+        assert!(matches!(
+            set_config_error(0x21, io_error()),
+            UserApiError::ReportedTcbTooHigh
+        ));
+        assert!(matches!(
+            set_config_error(0x01, io_error()),
+            UserApiError::FirmwareError(Error::InvalidPlatformState)
+        ));
+        assert!(matches!(
+            set_config_error(0, io_error()),
+            UserApiError::FirmwareError(Error::IoError(_))
+        ));
+    }
+}
@@ -4,10 +4,12 @@
 
 pub use crate::firmware::linux::host::types::PlatformStatusFlags;
 
-use crate::{firmware::host::State, Build};
+use crate::{error::LegacyMeasurementError, firmware::host::State, Build};
+
+use std::convert::{TryFrom, TryInto};
 
 #[cfg(feature = "openssl")]
-use std::convert::TryInto;
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
 
 #[cfg(feature = "openssl")]
 use crate::certs::sev::{
@@ -55,7 +57,7 @@ pub struct LegacyAttestationReport {
     /// 128-bit Nonce from the Command Buffer.
     pub mnonce: [u8; MNONCE_SIZE], // 0x00
     /// SHA-256 digest of launched guest.
-    pub launch_digest: [u8; POLICY_SIZE], // 0x10
+    pub launch_digest: [u8; DIGEST_SIZE], // 0x10
     /// Policy guest was launched with.
     pub policy: u32, // 0x30
     /// Key usage of SIG1 signing key.
@@ -96,3 +98,166 @@ impl Verifiable for (&Certificate, &LegacyAttestationReport) {
         Ok(())
     }
 }
+
+/// The launch measurement of a legacy SEV or SEV-ES guest, as returned by
+/// LAUNCH_MEASURE.
+///
+/// This is the HMAC the guest owner checks with the TIK before provisioning
+/// secrets, unrelated to SEV-SNP attestation reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct LegacyMeasurement {
+    /// HMAC-SHA-256 of the launch context, keyed with the TIK.
+    pub measure: [u8; DIGEST_SIZE],
+
+    /// The nonce chosen by the firmware for this measurement.
+    pub mnonce: [u8; MNONCE_SIZE],
+}
+
+impl TryFrom<&[u8]> for LegacyMeasurement {
+    type Error = LegacyMeasurementError;
+
+    /// Parses the 48 bytes of MEASURE followed by MNONCE.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != DIGEST_SIZE + MNONCE_SIZE {
+            return Err(LegacyMeasurementError::InvalidLength {
+                expected: DIGEST_SIZE + MNONCE_SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let (measure, mnonce) = bytes.split_at(DIGEST_SIZE);
+
+        Ok(Self {
+            measure: measure.try_into().unwrap(),
+            mnonce: mnonce.try_into().unwrap(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<crate::launch::sev::Measurement> for LegacyMeasurement {
+    fn from(value: crate::launch::sev::Measurement) -> Self {
+        Self {
+            measure: value.measure,
+            mnonce: value.mnonce,
+        }
+    }
+}
+
+impl LegacyMeasurement {
+    /// Checks the measurement against the one expected of a guest launched
+    /// with `policy` (in its 32-bit encoding), whose pages hash to
+    /// `launch_digest`, on firmware `build`.
+    ///
+    /// The measurement is HMAC-SHA-256, keyed with the TIK, over the byte
+    /// 04h, the API major and minor versions and build, the policy, the
+    /// launch digest and MNONCE. The TEK is not involved.
+    #[cfg(feature = "openssl")]
+    pub fn verify(
+        &self,
+        tik: &[u8],
+        build: Build,
+        policy: u32,
+        launch_digest: &[u8; DIGEST_SIZE],
+    ) -> Result<(), LegacyMeasurementError> {
+        let key = PKey::hmac(tik)?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+
+        signer.update(&[0x04])?;
+        signer.update(&[build.version.major, build.version.minor, build.build])?;
+        signer.update(&policy.to_le_bytes())?;
+        signer.update(launch_digest)?;
+        signer.update(&self.mnonce)?;
+
+        match memcmp::eq(&signer.sign_to_vec()?, &self.measure) {
+            true => Ok(()),
+            false => Err(LegacyMeasurementError::MeasurementMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_report_measurable_bytes() {
+        let report = LegacyAttestationReport {
+            mnonce: [0x11; MNONCE_SIZE],
+            launch_digest: [0x22; DIGEST_SIZE],
+            policy: 0x33,
+            ..Default::default()
+        };
+
+        let bytes = report.measurable_bytes();
+        assert_eq!(&bytes[..MNONCE_SIZE], &[0x11; MNONCE_SIZE]);
+        assert_eq!(&bytes[MNONCE_SIZE..POLICY_OFFSET], &[0x22; DIGEST_SIZE]);
+        assert_eq!(&bytes[POLICY_OFFSET..], &0x33u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn legacy_measurement_from_bytes() {
+        let mut bytes = [0u8; 48];
+        bytes[..32].copy_from_slice(&[0xaa; 32]);
+        bytes[32..].copy_from_slice(&[0x55; 16]);
+
+        let measurement = LegacyMeasurement::try_from(&bytes[..]).unwrap();
+        assert_eq!(measurement.measure, [0xaa; 32]);
+        assert_eq!(measurement.mnonce, [0x55; 16]);
+
+        assert!(matches!(
+            LegacyMeasurement::try_from(&bytes[..47]),
+            Err(LegacyMeasurementError::InvalidLength {
+                expected: 48,
+                actual: 47
+            })
+        ));
+    }
+
+    /// A synthetic measurement, not captured from a guest: the launch digest
+    /// is the SHA-256 of empty input and the measurement was computed from it
+    /// with the TIK below, for API 0.18 build 15 and the default policy.
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn legacy_measurement_verify() {
+        let measurement = LegacyMeasurement {
+            measure: [
+                0x6f, 0xaa, 0xb2, 0xda, 0xae, 0x38, 0x9b, 0xcd, 0x34, 0x05, 0xa0, 0x5d, 0x6c, 0xaf,
+                0xe3, 0x3c, 0x04, 0x14, 0xf7, 0xbe, 0xdd, 0x0b, 0xae, 0x19, 0xba, 0x5f, 0x38, 0xb7,
+                0xfd, 0x16, 0x64, 0xea,
+            ],
+            mnonce: [
+                0x4f, 0xbe, 0x0b, 0xed, 0xba, 0xd6, 0xc8, 0x6a, 0xe8, 0xf6, 0x89, 0x71, 0xd1, 0x03,
+                0xe5, 0x54,
+            ],
+        };
+        let tik = [
+            0x66, 0x32, 0x0d, 0xb7, 0x31, 0x58, 0xa3, 0x5a, 0x25, 0x5d, 0x05, 0x17, 0x58, 0xe9,
+            0x5e, 0xd4,
+        ];
+        let launch_digest = [
+            0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f,
+            0xb9, 0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b,
+            0x78, 0x52, 0xb8, 0x55,
+        ];
+        let build = Build {
+            version: crate::Version {
+                major: 0x00,
+                minor: 0x12,
+            },
+            build: 0x0f,
+        };
+
+        measurement.verify(&tik, build, 0, &launch_digest).unwrap();
+
+        assert!(matches!(
+            measurement.verify(&tik, build, 1, &launch_digest),
+            Err(LegacyMeasurementError::MeasurementMismatch)
+        ));
+        assert!(matches!(
+            measurement.verify(&[0; 16], build, 0, &launch_digest),
+            Err(LegacyMeasurementError::MeasurementMismatch)
+        ));
+    }
+}
@@ -5,10 +5,10 @@ pub use crate::firmware::linux::host::types::RawData;
 
 pub(crate) use crate::firmware::linux::host as FFI;
 
-use crate::Version;
-
-#[cfg(target_os = "linux")]
-use crate::error::CertError;
+use crate::{
+    error::{CertError, UserApiError},
+    Version,
+};
 
 use std::{
     convert::{TryFrom, TryInto},
@@ -188,6 +188,186 @@ impl CertTableEntry {
 
         Ok(unsafe { FFI::types::CertTableEntry::parse_table(cert_bytes_ptr).unwrap() })
     }
+
+    /// Parses a certificate table in the layout defined by the GHCB
+    /// specification, as written by the hypervisor into the buffer of an
+    /// extended guest request.
+    ///
+    /// Unlike [vec_bytes_to_cert_table](Self::vec_bytes_to_cert_table), every
+    /// entry and certificate is checked to lie within `bytes`.
+    pub fn parse_cert_table(bytes: &[u8]) -> Result<Vec<Self>, CertError> {
+        let mut entries = vec![];
+
+        for raw in bytes.chunks(CERT_TABLE_ENTRY_SIZE) {
+            if raw.len() < CERT_TABLE_ENTRY_SIZE {
+                break;
+            }
+
+            let guid = uuid::Uuid::from_slice(&raw[..16]).map_err(|_| CertError::InvalidGUID)?;
+
+            if guid.is_nil() {
+                return Ok(entries);
+            }
+
+            let offset = u32::from_le_bytes(raw[16..20].try_into().unwrap()) as usize;
+            let length = u32::from_le_bytes(raw[20..24].try_into().unwrap()) as usize;
+
+            let data = offset
+                .checked_add(length)
+                .and_then(|end| bytes.get(offset..end))
+                .ok_or(CertError::BufferOverflow)?;
+
+            entries
+                .push(Self::from_guid(&guid, data.to_vec()).map_err(|_| CertError::InvalidGUID)?);
+        }
+
+        // The table ran past the buffer without a terminating entry.
+        Err(CertError::BufferOverflow)
+    }
+}
+
+/// The size of an entry of the certificate table: a GUID, an offset and a
+/// length.
+const CERT_TABLE_ENTRY_SIZE: usize = 24;
+
+/// The certificate table is handed to the guest in whole 4K pages.
+const CERT_TABLE_ALIGNMENT: usize = 4096;
+
+/// Assembles the certificate table a hypervisor serves to guests through
+/// the extended guest request, in the layout defined by the GHCB
+/// specification.
+///
+/// The table lists one entry per certificate: its GUID, and the offset
+/// (from the start of the table) and length of its data. An all-zero entry
+/// terminates the table, the certificates follow it back to back, and the
+/// whole is zero-padded to a multiple of 4K.
+///
+/// Entries are ordered by [CertType], so that the same certificates always
+/// produce the same table.
+///
+/// # Example:
+///
+/// ```ignore
+/// let mut builder = CertTableBuilder::new();
+/// builder
+///     .add(CertType::ARK, ark)?
+///     .add(CertType::ASK, ask)?
+///     .add(CertType::VCEK, vcek)?;
+///
+/// let table = builder.build()?;
+/// builder.validate(&table)?;
+///
+/// std::fs::write("/var/lib/vmm/certs.blob", table)?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CertTableBuilder {
+    entries: Vec<CertTableEntry>,
+}
+
+impl CertTableBuilder {
+    /// Creates a builder for an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a DER-encoded certificate (or CRL) of the given type.
+    ///
+    /// Fails with [CertError::DuplicateCertType] if the table already holds
+    /// one of this type, and [CertError::InvalidGUID] for [CertType::Empty],
+    /// whose GUID is reserved for the terminating entry.
+    pub fn add(&mut self, cert_type: CertType, der: Vec<u8>) -> Result<&mut Self, CertError> {
+        if cert_type == CertType::Empty {
+            return Err(CertError::InvalidGUID);
+        }
+
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.cert_type == cert_type)
+        {
+            return Err(CertError::DuplicateCertType(cert_type));
+        }
+
+        self.entries.push(CertTableEntry::new(cert_type, der));
+        self.entries.sort();
+
+        Ok(self)
+    }
+
+    /// The size of the table, padding included, in bytes.
+    pub fn size(&self) -> usize {
+        let unpadded = CERT_TABLE_ENTRY_SIZE * (self.entries.len() + 1)
+            + self
+                .entries
+                .iter()
+                .map(|entry| entry.data.len())
+                .sum::<usize>();
+
+        let pages = (unpadded + CERT_TABLE_ALIGNMENT - 1) / CERT_TABLE_ALIGNMENT;
+
+        pages * CERT_TABLE_ALIGNMENT
+    }
+
+    /// Builds the table. An empty builder produces just the terminating
+    /// entry, in a zeroed page.
+    pub fn build(&self) -> Result<Vec<u8>, CertError> {
+        let mut table = vec![0u8; self.size()];
+        self.write(&mut table)?;
+
+        Ok(table)
+    }
+
+    /// Writes the table at the start of `buffer`, e.g. guest memory of a
+    /// fixed number of pages, and returns its size.
+    ///
+    /// Fails with [CertError::CertTableTooLarge] if the table does not fit.
+    /// The rest of the buffer is left untouched.
+    pub fn write(&self, buffer: &mut [u8]) -> Result<usize, CertError> {
+        let size = self.size();
+        let available = buffer.len();
+
+        let table = buffer.get_mut(..size).ok_or(CertError::CertTableTooLarge {
+            needed: size,
+            available,
+        })?;
+        table.fill(0);
+
+        let mut offset = CERT_TABLE_ENTRY_SIZE * (self.entries.len() + 1);
+
+        for (entry, raw) in self
+            .entries
+            .iter()
+            .zip(table.chunks_mut(CERT_TABLE_ENTRY_SIZE))
+        {
+            let guid = uuid::Uuid::try_from(entry.cert_type.clone())
+                .map_err(|_| CertError::InvalidGUID)?;
+            let length = entry.data.len();
+
+            raw[..16].copy_from_slice(guid.as_bytes());
+            raw[16..20].copy_from_slice(&(offset as u32).to_le_bytes());
+            raw[20..24].copy_from_slice(&(length as u32).to_le_bytes());
+
+            offset += length;
+        }
+
+        let mut offset = CERT_TABLE_ENTRY_SIZE * (self.entries.len() + 1);
+
+        for entry in &self.entries {
+            table[offset..offset + entry.data.len()].copy_from_slice(&entry.data);
+            offset += entry.data.len();
+        }
+
+        Ok(size)
+    }
+
+    /// Checks that `table` parses back, as guests parse it, into the
+    /// certificates of this builder.
+    pub fn validate(&self, table: &[u8]) -> Result<(), CertError> {
+        match CertTableEntry::parse_cert_table(table)? == self.entries {
+            true => Ok(()),
+            false => Err(CertError::CertTableMismatch),
+        }
+    }
 }
 
 impl Ord for CertTableEntry {
@@ -226,7 +406,7 @@ pub struct Build {
 /// Query the SEV-SNP platform status.
 ///
 /// (Chapter 8.3; Table 38)
-#[derive(Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct SnpPlatformStatus {
     /// The firmware API version (major.minor)
@@ -254,6 +434,186 @@ pub struct SnpPlatformStatus {
     pub reported_tcb_version: TcbVersion,
 }
 
+impl SnpPlatformStatus {
+    /// Whether the RMP has been initialized.
+    pub fn rmp_initialized(&self) -> bool {
+        self.is_rmp_init & 1 != 0
+    }
+
+    /// Whether CHIP_ID is masked (zeroed) in attestation reports.
+    pub fn chip_id_masked(&self) -> bool {
+        self.mask_chip_id & 1 != 0
+    }
+
+    /// Whether the chip key is masked, i.e. attestation reports are not
+    /// signed with the VCEK.
+    pub fn chip_key_masked(&self) -> bool {
+        self.mask_chip_id & (1 << 1) != 0
+    }
+
+    /// The features supported by the platform's firmware.
+    pub fn capabilities(&self) -> FirmwareCapabilities {
+        FirmwareCapabilities::new(self.version, self.build_id)
+    }
+}
+
+/// The features of the SEV-SNP firmware, by the ABI version which
+/// introduced them.
+///
+/// Checking these before issuing a request turns the firmware's generic
+/// INVALID_PARAM status into an error naming the missing feature.
+///
+/// # Example:
+///
+/// ```ignore
+/// let capabilities = firmware.snp_platform_status()?.capabilities();
+///
+/// if !capabilities.supports_vlek() {
+///     // Fall back to the VCEK.
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FirmwareCapabilities {
+    /// The firmware API version (major.minor).
+    pub version: Version,
+
+    /// The firmware build ID.
+    pub build: u32,
+}
+
+impl FirmwareCapabilities {
+    /// The first ABI version supporting VLEK signed reports and
+    /// SNP_VLEK_LOAD.
+    pub const VLEK: Version = Version {
+        major: 1,
+        minor: 54,
+    };
+
+    /// The first ABI version producing version 3 attestation reports.
+    pub const REPORT_V3: Version = Version {
+        major: 1,
+        minor: 56,
+    };
+
+    /// The first ABI version accepting a launch mitigation vector.
+    pub const LAUNCH_MIT_VECTOR: Version = Version {
+        major: 1,
+        minor: 58,
+    };
+
+    /// Creates the capabilities of firmware with the given API version and
+    /// build ID.
+    pub fn new(version: Version, build: u32) -> Self {
+        Self { version, build }
+    }
+
+    /// Whether the firmware API version is `required` or newer.
+    pub fn is_at_least(&self, required: Version) -> bool {
+        self.version >= required
+    }
+
+    /// Whether the firmware supports the Versioned Loaded Endorsement Key.
+    pub fn supports_vlek(&self) -> bool {
+        self.is_at_least(Self::VLEK)
+    }
+
+    /// Whether the firmware produces version 3 attestation reports.
+    pub fn supports_report_v3(&self) -> bool {
+        self.is_at_least(Self::REPORT_V3)
+    }
+
+    /// Whether the firmware accepts a launch mitigation vector in derived
+    /// key requests.
+    pub fn supports_launch_mit_vector(&self) -> bool {
+        self.is_at_least(Self::LAUNCH_MIT_VECTOR)
+    }
+
+    /// Fails with [UserApiError::UnsupportedFirmware] unless the firmware
+    /// API version is `required` or newer.
+    pub fn require(&self, feature: &'static str, required: Version) -> Result<(), UserApiError> {
+        match self.is_at_least(required) {
+            true => Ok(()),
+            false => Err(UserApiError::UnsupportedFirmware {
+                feature,
+                required,
+                present: self.version,
+            }),
+        }
+    }
+}
+
+impl From<&SnpPlatformStatus> for FirmwareCapabilities {
+    fn from(status: &SnpPlatformStatus) -> Self {
+        status.capabilities()
+    }
+}
+
+impl TryFrom<&[u8]> for SnpPlatformStatus {
+    type Error = UserApiError;
+
+    /// Parses the raw status returned by the firmware. Trailing bytes, as
+    /// found in the longer structure of newer firmware, are ignored.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() < std::mem::size_of::<Self>() {
+            return Err(UserApiError::InvalidResponseSize {
+                expected: std::mem::size_of::<Self>(),
+                actual: bytes.len(),
+            });
+        }
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let tcb_at = |offset: usize| {
+            TcbVersion::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+        };
+
+        Ok(Self {
+            version: Version {
+                major: bytes[0],
+                minor: bytes[1],
+            },
+            state: bytes[2],
+            is_rmp_init: bytes[3],
+            build_id: u32_at(0x4),
+            mask_chip_id: u32_at(0x8),
+            guest_count: u32_at(0xc),
+            platform_tcb_version: tcb_at(0x10),
+            reported_tcb_version: tcb_at(0x18),
+        })
+    }
+}
+
+impl Display for SnpPlatformStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"
+SNP Platform Status:
+API Version:                  {}
+State:                        {}
+RMP Initialized:              {}
+Build ID:                     {}
+Mask Chip ID:                 {}
+Mask Chip Key:                {}
+Guest Count:                  {}
+Current TCB:
+{}
+Reported TCB:
+{}
+"#,
+            self.version,
+            self.state,
+            self.rmp_initialized(),
+            self.build_id,
+            self.chip_id_masked(),
+            self.chip_key_masked(),
+            self.guest_count,
+            self.platform_tcb_version,
+            self.reported_tcb_version,
+        )
+    }
+}
+
 /// Sets the system wide configuration values for SNP.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(C, packed)]
@@ -320,7 +680,7 @@ impl TryFrom<FFI::types::SnpSetConfig> for Config {
 /// TcbVersion represents the version of the firmware.
 ///
 /// (Chapter 2.2; Table 3)
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(C)]
 pub struct TcbVersion {
     /// Current bootloader version.
@@ -353,6 +713,39 @@ TCB Version:
     }
 }
 
+/// TCBs are ordered component by component: one is newer than another
+/// only if none of its SVNs is lower and at least one is higher. TCBs
+/// with SVNs moving in opposite directions are incomparable.
+impl PartialOrd for TcbVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        use std::cmp::Ordering;
+
+        let components = [
+            (self.bootloader, other.bootloader),
+            (self.tee, other.tee),
+            (self.snp, other.snp),
+            (self.microcode, other.microcode),
+        ];
+
+        let mut ordering = Ordering::Equal;
+
+        for (left, right) in components {
+            match (ordering, left.cmp(&right)) {
+                (_, Ordering::Equal) => (),
+                (Ordering::Equal, component) => ordering = component,
+                (ordering, component) if ordering == component => (),
+                _ => return None,
+            }
+        }
+
+        // Stay consistent with `==`, which also compares the reserved bytes.
+        match ordering == Ordering::Equal && self != other {
+            true => None,
+            false => Some(ordering),
+        }
+    }
+}
+
 impl TcbVersion {
     /// Creates a new instance of a TcbVersion
     pub fn new(bootloader: u8, tee: u8, snp: u8, microcode: u8) -> Self {
@@ -364,6 +757,197 @@ impl TcbVersion {
             _reserved: Default::default(),
         }
     }
+
+    /// Whether no component of this TCB is newer than the same component
+    /// of `other`, as required of the reported TCB against the current one.
+    pub fn fits_within(&self, other: &TcbVersion) -> bool {
+        self <= other
+    }
+
+    /// Reads a TCB from the 8 bytes of its little-endian `u64` encoding, as
+    /// found in reports and certificate extensions: the bootloader SVN in
+    /// byte 0, the TEE SVN in byte 1, the SNP SVN in byte 6 and the
+    /// microcode SVN in byte 7.
+    pub fn from_le_bytes(bytes: [u8; 8]) -> Self {
+        Self {
+            bootloader: bytes[0],
+            tee: bytes[1],
+            _reserved: [bytes[2], bytes[3], bytes[4], bytes[5]],
+            snp: bytes[6],
+            microcode: bytes[7],
+        }
+    }
+
+    /// The little-endian `u64` encoding of this TCB, the reverse of
+    /// [from_le_bytes](Self::from_le_bytes).
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        let [r0, r1, r2, r3] = self._reserved;
+
+        [
+            self.bootloader,
+            self.tee,
+            r0,
+            r1,
+            r2,
+            r3,
+            self.snp,
+            self.microcode,
+        ]
+    }
+}
+
+/// Arbitrary SVNs, with the reserved bytes left zero.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TcbVersion {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
+/// The SVNs of a TCB_VERSION, decoded for the processor which produced it.
+///
+/// Turin (CPUID family 1Ah) and later reorder the TCB_VERSION and add the
+/// SVN of the FMC firmware: FMC, bootloader, TEE and SNP take bytes 0 to 3,
+/// and only the microcode keeps byte 7. Earlier processors use the layout
+/// of [TcbVersion] and have no FMC SVN.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TcbComponents {
+    /// SVN of the FMC firmware, on Turin and later.
+    pub fmc: Option<u8>,
+    /// SVN of PSP bootloader.
+    pub bootloader: u8,
+    /// SVN of PSP operating system.
+    pub tee: u8,
+    /// Security Version Number (SVN) of SNP firmware.
+    pub snp: u8,
+    /// Lowest current patch level of all the cores.
+    pub microcode: u8,
+}
+
+impl TcbComponents {
+    /// The first CPUID family encoding the TCB_VERSION with an FMC SVN.
+    pub const FMC_FAMILY: u8 = 0x1a;
+
+    /// Decodes a raw TCB_VERSION, in the Turin layout if `fmc` is set and in
+    /// the layout of [TcbVersion] otherwise.
+    pub fn from_raw(raw: u64, fmc: bool) -> Self {
+        let bytes = raw.to_le_bytes();
+
+        match fmc {
+            true => Self {
+                fmc: Some(bytes[0]),
+                bootloader: bytes[1],
+                tee: bytes[2],
+                snp: bytes[3],
+                microcode: bytes[7],
+            },
+            false => TcbVersion::from_le_bytes(bytes).into(),
+        }
+    }
+
+    /// Whether no component of this TCB is newer than the same component
+    /// of `other`. Never holds between a TCB with an FMC SVN and one
+    /// without.
+    pub fn fits_within(&self, other: &TcbComponents) -> bool {
+        self <= other
+    }
+
+    /// The raw TCB_VERSION, in the Turin layout if [fmc](Self::fmc) is set.
+    /// The reverse of [from_raw](Self::from_raw), with the reserved bytes
+    /// zeroed.
+    pub fn to_raw(&self) -> u64 {
+        let bytes = match self.fmc {
+            Some(fmc) => [
+                fmc,
+                self.bootloader,
+                self.tee,
+                self.snp,
+                0,
+                0,
+                0,
+                self.microcode,
+            ],
+            None => {
+                TcbVersion::new(self.bootloader, self.tee, self.snp, self.microcode).to_le_bytes()
+            }
+        };
+
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// Reads a TCB in the layout used before Turin, without an FMC SVN.
+impl From<TcbVersion> for TcbComponents {
+    fn from(tcb: TcbVersion) -> Self {
+        Self {
+            fmc: None,
+            bootloader: tcb.bootloader,
+            tee: tcb.tee,
+            snp: tcb.snp,
+            microcode: tcb.microcode,
+        }
+    }
+}
+
+impl Display for TcbComponents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"
+TCB Version:
+  Microcode:   {}
+  SNP:         {}
+  TEE:         {}
+  Boot Loader: {}
+  "#,
+            self.microcode, self.snp, self.tee, self.bootloader
+        )?;
+
+        match self.fmc {
+            Some(fmc) => write!(f, "FMC:         {fmc}\n  "),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Ordered component by component like [TcbVersion]. A TCB with an FMC SVN
+/// is incomparable with one without.
+impl PartialOrd for TcbComponents {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        use std::cmp::Ordering;
+
+        let fmc = match (self.fmc, other.fmc) {
+            (Some(left), Some(right)) => (left, right),
+            (None, None) => (0, 0),
+            _ => return None,
+        };
+
+        let components = [
+            fmc,
+            (self.bootloader, other.bootloader),
+            (self.tee, other.tee),
+            (self.snp, other.snp),
+            (self.microcode, other.microcode),
+        ];
+
+        let mut ordering = Ordering::Equal;
+
+        for (left, right) in components {
+            match (ordering, left.cmp(&right)) {
+                (_, Ordering::Equal) => (),
+                (Ordering::Equal, component) => ordering = component,
+                (ordering, component) if ordering == component => (),
+                _ => return None,
+            }
+        }
+
+        Some(ordering)
+    }
 }
 
 bitfield! {
@@ -400,9 +984,248 @@ impl Display for MaskId {
 
 #[cfg(test)]
 mod tests {
-    use super::CertType;
+    use super::{
+        CertTableBuilder, CertTableEntry, CertType, Config, FirmwareCapabilities, MaskId,
+        SnpPlatformStatus, TcbComponents, TcbVersion,
+    };
+    use crate::{
+        error::{CertError, UserApiError},
+        Version,
+    };
+    use std::convert::TryFrom;
     use uuid::Uuid;
 
+    /// A synthetic SNP_PLATFORM_STATUS laid out per the spec, not captured
+    /// from a host: API 1.55, build 21, two guests, CHIP_ID masked, TCB
+    /// 3/0/8/115. Eight trailing bytes stand in for fields added by newer
+    /// firmware.
+    const PLATFORM_STATUS: [u8; 40] = [
+        0x01, 0x37, 0x01, 0x01, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+        0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x73, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x08, 0x73, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    ];
+
+    #[test]
+    fn test_snp_platform_status_from_bytes() {
+        let status = SnpPlatformStatus::try_from(&PLATFORM_STATUS[..]).unwrap();
+
+        assert_eq!(status.version.to_string(), "1.55");
+        assert_eq!(status.state, 1);
+        assert!(status.rmp_initialized());
+        assert_eq!(status.build_id, 21);
+        assert!(status.chip_id_masked());
+        assert!(!status.chip_key_masked());
+        assert_eq!(status.guest_count, 2);
+        assert_eq!(status.platform_tcb_version, TcbVersion::new(3, 0, 8, 115));
+        assert_eq!(status.reported_tcb_version, TcbVersion::new(3, 0, 8, 115));
+
+        assert_eq!(
+            SnpPlatformStatus::try_from(&PLATFORM_STATUS[..32]).unwrap(),
+            status
+        );
+        assert!(matches!(
+            SnpPlatformStatus::try_from(&PLATFORM_STATUS[..31]),
+            Err(UserApiError::InvalidResponseSize {
+                expected: 32,
+                actual: 31
+            })
+        ));
+
+        let display = status.to_string();
+        assert!(display.contains("API Version:                  1.55\n"));
+        assert!(display.contains("Mask Chip ID:                 true\n"));
+    }
+
+    fn capabilities(major: u8, minor: u8) -> FirmwareCapabilities {
+        FirmwareCapabilities::new(Version { major, minor }, 0)
+    }
+
+    #[test]
+    fn test_firmware_capabilities_thresholds() {
+        let status = SnpPlatformStatus::try_from(&PLATFORM_STATUS[..]).unwrap();
+        let milan = status.capabilities();
+
+        assert_eq!(milan.version, capabilities(1, 55).version);
+        assert_eq!(milan.build, 21);
+        assert!(milan.supports_vlek());
+        assert!(!milan.supports_report_v3());
+        assert!(!milan.supports_launch_mit_vector());
+
+        assert!(!capabilities(1, 53).supports_vlek());
+        assert!(capabilities(1, 54).supports_vlek());
+
+        assert!(!capabilities(1, 55).supports_report_v3());
+        assert!(capabilities(1, 56).supports_report_v3());
+
+        assert!(!capabilities(1, 57).supports_launch_mit_vector());
+        assert!(capabilities(1, 58).supports_launch_mit_vector());
+
+        // A new major version supports everything, whatever its minor.
+        let next = capabilities(2, 0);
+        assert!(next.supports_vlek() && next.supports_report_v3());
+        assert!(next.supports_launch_mit_vector());
+        assert!(!capabilities(0, 99).supports_vlek());
+    }
+
+    #[test]
+    fn test_firmware_capabilities_require() {
+        let caps = capabilities(1, 57);
+
+        assert!(caps
+            .require("report v3", FirmwareCapabilities::REPORT_V3)
+            .is_ok());
+
+        let err = caps
+            .require(
+                "the launch mitigation vector",
+                FirmwareCapabilities::LAUNCH_MIT_VECTOR,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            UserApiError::UnsupportedFirmware {
+                required: FirmwareCapabilities::LAUNCH_MIT_VECTOR,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "SNP firmware 1.57 does not support the launch mitigation vector, which requires \
+             1.58 or newer"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn test_snp_platform_status_json() {
+        let status = SnpPlatformStatus::try_from(&PLATFORM_STATUS[..]).unwrap();
+
+        let json = serde_json::to_string(&status).unwrap();
+        let parsed: SnpPlatformStatus = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, status);
+    }
+
+    #[test]
+    fn test_tcb_version_partial_ord() {
+        use std::cmp::Ordering;
+
+        let tcb = TcbVersion::new(3, 0, 8, 115);
+
+        assert_eq!(tcb.partial_cmp(&tcb), Some(Ordering::Equal));
+        assert!(TcbVersion::new(3, 0, 10, 169) > tcb);
+        assert!(TcbVersion::new(3, 0, 8, 114) < tcb);
+        assert!(TcbVersion::new(2, 0, 8, 115) <= tcb);
+
+        let incomparable = TcbVersion::new(4, 0, 7, 115);
+        assert_eq!(incomparable.partial_cmp(&tcb), None);
+        assert_ne!(incomparable, tcb);
+
+        // A plain u64 comparison of the raw bytes would call this newer.
+        assert_eq!(
+            TcbVersion::new(3, 0, 8, 255).partial_cmp(&TcbVersion::new(3, 1, 8, 115)),
+            None
+        );
+
+        let mut reserved = tcb;
+        reserved._reserved[0] = 1;
+        assert_eq!(reserved.partial_cmp(&tcb), None);
+    }
+
+    #[test]
+    fn test_tcb_version_fits_within() {
+        let current = TcbVersion::new(3, 0, 10, 169);
+
+        assert!(current.fits_within(&current));
+        assert!(TcbVersion::new(2, 0, 8, 115).fits_within(&current));
+        assert!(!TcbVersion::new(4, 0, 10, 169).fits_within(&current));
+        assert!(!TcbVersion::new(3, 0, 8, 170).fits_within(&current));
+    }
+
+    #[test]
+    fn test_tcb_version_le_bytes() {
+        // The reported TCB of the Milan fixture report.
+        let bytes = 0x7308_0000_0000_0003u64.to_le_bytes();
+        let tcb = TcbVersion::from_le_bytes(bytes);
+
+        assert_eq!(tcb, TcbVersion::new(3, 0, 8, 115));
+        assert_eq!(tcb.to_le_bytes(), bytes);
+        assert_eq!(bincode::serialize(&tcb).unwrap(), bytes);
+
+        // The reserved bytes round-trip as well.
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(TcbVersion::from_le_bytes(bytes).to_le_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_tcb_components_raw() {
+        // Before Turin: the reported TCB of the Milan fixture report.
+        let raw = 0x7308_0000_0000_0003u64;
+        let tcb = TcbComponents::from_raw(raw, false);
+
+        assert_eq!(tcb, TcbVersion::new(3, 0, 8, 115).into());
+        assert_eq!(tcb.fmc, None);
+        assert_eq!(tcb.to_raw(), raw);
+
+        // Turin: FMC 1, bootloader 9, TEE 0, SNP 24, microcode 0x45.
+        let raw = 0x4500_0000_1800_0901u64;
+        let tcb = TcbComponents::from_raw(raw, true);
+
+        assert_eq!(
+            tcb,
+            TcbComponents {
+                fmc: Some(1),
+                bootloader: 9,
+                tee: 0,
+                snp: 24,
+                microcode: 0x45,
+            }
+        );
+        assert_eq!(tcb.to_raw(), raw);
+        assert!(tcb.to_string().contains("FMC:         1"));
+        assert!(!TcbComponents::from_raw(raw, false)
+            .to_string()
+            .contains("FMC"));
+    }
+
+    #[test]
+    fn test_tcb_components_partial_ord() {
+        let turin = TcbComponents::from_raw(0x4500_0000_1800_0901, true);
+
+        let mut newer_fmc = turin;
+        newer_fmc.fmc = Some(2);
+        assert!(newer_fmc > turin);
+
+        let mut older_snp = newer_fmc;
+        older_snp.snp = 23;
+        assert_eq!(older_snp.partial_cmp(&turin), None);
+
+        let milan: TcbComponents = TcbVersion::new(3, 0, 8, 115).into();
+        assert!(TcbComponents::from(TcbVersion::new(3, 0, 10, 169)) > milan);
+        assert_eq!(milan.partial_cmp(&turin), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_snp_set_config_layout() {
+        use super::FFI::types::{SnpCommit, SnpSetConfig};
+        use std::convert::TryFrom;
+
+        // struct sev_user_data_snp_config from include/uapi/linux/psp-sev.h.
+        assert_eq!(std::mem::size_of::<SnpSetConfig>(), 64);
+        assert_eq!(std::mem::size_of::<SnpCommit>(), 4);
+
+        let config = Config::new(TcbVersion::new(3, 0, 10, 169), MaskId(0b11));
+        let ffi = SnpSetConfig::try_from(config).unwrap();
+        let bytes: [u8; 64] = unsafe { std::mem::transmute(ffi) };
+
+        let mut expected = [0u8; 64];
+        expected[..8].copy_from_slice(&[3, 0, 0, 0, 0, 0, 10, 169]);
+        expected[8] = 0b11;
+
+        assert_eq!(bytes, expected);
+    }
+
     #[test]
     fn test_cert_type_sort_vcek() {
         let mut certs: Vec<CertType> = vec![
@@ -456,4 +1279,102 @@ mod tests {
         certs.sort();
         assert_eq!(certs, sorted_certs);
     }
+
+    #[test]
+    fn test_cert_table_builder() {
+        let mut builder = CertTableBuilder::new();
+        builder
+            .add(CertType::VCEK, vec![0xcc; 100])
+            .unwrap()
+            .add(CertType::ASK, vec![0xbb; 200])
+            .unwrap()
+            .add(CertType::ARK, vec![0xaa; 300])
+            .unwrap();
+
+        let table = builder.build().unwrap();
+        assert_eq!(table.len(), 4096);
+
+        // ARK, VCEK and ASK entries, the terminator, then the certificates
+        // in the same order.
+        let ark = Uuid::parse_str("c0b406a4-a803-4952-9743-3fb6014cd0ae").unwrap();
+        assert_eq!(&table[..16], ark.as_bytes());
+        assert_eq!(&table[16..20], &96u32.to_le_bytes());
+        assert_eq!(&table[20..24], &300u32.to_le_bytes());
+        assert_eq!(&table[24 + 16..24 + 20], &396u32.to_le_bytes());
+        assert_eq!(&table[72..96], &[0; 24]);
+        assert_eq!(&table[96..396], &[0xaa; 300][..]);
+        assert_eq!(&table[396..496], &[0xcc; 100][..]);
+        assert_eq!(&table[496..696], &[0xbb; 200][..]);
+        assert!(table[696..].iter().all(|b| *b == 0));
+
+        builder.validate(&table).unwrap();
+
+        let certs = CertTableEntry::parse_cert_table(&table).unwrap();
+        let types: Vec<CertType> = certs.into_iter().map(|c| c.cert_type).collect();
+        assert_eq!(types, [CertType::ARK, CertType::VCEK, CertType::ASK]);
+
+        // The order of insertion does not matter.
+        let mut other = CertTableBuilder::new();
+        other
+            .add(CertType::ARK, vec![0xaa; 300])
+            .unwrap()
+            .add(CertType::ASK, vec![0xbb; 200])
+            .unwrap()
+            .add(CertType::VCEK, vec![0xcc; 100])
+            .unwrap();
+        assert_eq!(other.build().unwrap(), table);
+    }
+
+    #[test]
+    fn test_cert_table_builder_edge_cases() {
+        let empty = CertTableBuilder::new().build().unwrap();
+        assert_eq!(empty, vec![0; 4096]);
+        assert!(CertTableEntry::parse_cert_table(&empty).unwrap().is_empty());
+
+        let mut builder = CertTableBuilder::new();
+        builder.add(CertType::VLEK, vec![1; 5000]).unwrap();
+        assert!(matches!(
+            builder.add(CertType::VLEK, vec![2; 10]),
+            Err(CertError::DuplicateCertType(CertType::VLEK))
+        ));
+        assert!(matches!(
+            builder.add(CertType::Empty, vec![]),
+            Err(CertError::InvalidGUID)
+        ));
+
+        assert_eq!(builder.size(), 8192);
+        let mut buffer = [0xffu8; 4096];
+        assert!(matches!(
+            builder.write(&mut buffer),
+            Err(CertError::CertTableTooLarge {
+                needed: 8192,
+                available: 4096
+            })
+        ));
+
+        let table = builder.build().unwrap();
+        assert!(matches!(
+            CertTableBuilder::new().validate(&table),
+            Err(CertError::CertTableMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_parse_cert_table_bounds() {
+        let mut builder = CertTableBuilder::new();
+        builder.add(CertType::VCEK, vec![0xcc; 100]).unwrap();
+        let table = builder.build().unwrap();
+
+        // The certificate runs past the end of the buffer.
+        assert!(matches!(
+            CertTableEntry::parse_cert_table(&table[..100]),
+            Err(CertError::BufferOverflow)
+        ));
+
+        // No terminating entry.
+        assert!(matches!(
+            CertTableEntry::parse_cert_table(&table[..24]),
+            Err(CertError::BufferOverflow)
+        ));
+    }
 }
@@ -4,6 +4,8 @@ use crate::{error::*, firmware::guest::*};
 
 use static_assertions::const_assert;
 
+use std::convert::{TryFrom, TryInto};
+
 /// This may end up being 4 when the Shadow Stack is enabled.
 /// [APMv2 - Table 15-38 - VMPL Permission Mask Definition](https://www.amd.com/system/files/TechDocs/24593.pdf#page=670&zoom=100,0,400)
 const MAX_VMPL: u32 = 3;
@@ -32,6 +34,10 @@ pub struct DerivedKeyReq {
     /// The TCB version to mix into the derived key. Must not
     /// exceed CommittedTcb.
     pub tcb_version: u64,
+
+    /// The mitigation vector value to mix into the derived key.
+    /// Zero when not requested.
+    pub launch_mit_vector: u64,
 }
 
 impl From<DerivedKey> for DerivedKeyReq {
@@ -43,6 +49,7 @@ impl From<DerivedKey> for DerivedKeyReq {
             vmpl: value.vmpl,
             guest_svn: value.guest_svn,
             tcb_version: value.tcb_version,
+            launch_mit_vector: value.get_launch_mit_vector().unwrap_or(0),
         }
     }
 }
@@ -56,6 +63,7 @@ impl From<&mut DerivedKey> for DerivedKeyReq {
             vmpl: value.vmpl,
             guest_svn: value.guest_svn,
             tcb_version: value.tcb_version,
+            launch_mit_vector: value.get_launch_mit_vector().unwrap_or(0),
         }
     }
 }
@@ -75,6 +83,75 @@ pub struct DerivedKeyRsp {
     pub key: [u8; 32],
 }
 
+/// Wipes the copy of the key left in the ioctl buffer.
+#[cfg(feature = "zeroize")]
+impl Drop for DerivedKeyRsp {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.key);
+    }
+}
+
+impl DerivedKeyRsp {
+    /// The derived key, only if the firmware reported success.
+    pub fn derived_key(&self) -> Option<&[u8; 32]> {
+        match self.status_as_error() {
+            Ok(()) => Some(&self.key),
+            Err(_) => None,
+        }
+    }
+
+    /// The status of the response as a result.
+    pub fn status_as_error(&self) -> Result<(), DerivedKeyError> {
+        match self.status {
+            0 => Ok(()),
+            0x16 => Err(DerivedKeyError::InvalidParameters),
+            status => Err(DerivedKeyError::UnknownStatus(status)),
+        }
+    }
+
+    /// Fails with the firmware's status unless the key was derived.
+    /// `request` is the request the response answers.
+    pub fn check_status(&self, request: &DerivedKey) -> Result<(), UserApiError> {
+        match self.status_as_error() {
+            Ok(()) => Ok(()),
+            // Firmware older than 1.58 does not know about the launch
+            // mitigation vector and rejects the request as invalid.
+            Err(DerivedKeyError::InvalidParameters) if request.uses_launch_mit_vector() => {
+                Err(UserApiError::LaunchMitVectorError)
+            }
+            Err(_) => FirmwareStatus::from(self.status).into_result(Some(request.vmpl)),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for DerivedKeyRsp {
+    type Error = UserApiError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != std::mem::size_of::<Self>() {
+            return Err(UserApiError::InvalidResponseSize {
+                expected: std::mem::size_of::<Self>(),
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(Self {
+            status: u32::from_ne_bytes(bytes[..4].try_into().unwrap()),
+            reserved_0: bytes[4..32].try_into().unwrap(),
+            key: bytes[32..].try_into().unwrap(),
+        })
+    }
+}
+
+/// Size of the pages backing the certificate buffer.
+pub const PAGE_SIZE: usize = 4096;
+
+/// A page of the certificate buffer. A `Vec` of these is contiguous and
+/// page aligned, as expected by the kernel.
+#[repr(C, align(4096))]
+#[derive(Clone, Copy)]
+pub struct Page(pub [u8; PAGE_SIZE]);
+
 /// Information provided by the guest owner for requesting an attestation
 /// report and associated certificate chain from the AMD Secure Processor.
 ///
@@ -93,8 +170,9 @@ pub struct ExtReportReq {
 }
 
 impl ExtReportReq {
-    /// Creates a new exteded report with a one, 4K-page
-    /// for the certs_address field and the certs_len field.
+    /// Creates a new extended report request without a certificate buffer.
+    /// Use [with_cert_buffer](Self::with_cert_buffer) to attach one before
+    /// submitting it.
     pub fn new(data: &ReportReq) -> Self {
         Self {
             data: *data,
@@ -102,6 +180,30 @@ impl ExtReportReq {
             certs_len: 0u32,
         }
     }
+
+    /// Allocates a zeroed, page-aligned certificate buffer of `size_pages`
+    /// pages and points the request at it.
+    ///
+    /// The returned pages back `certs_address`: they must be kept alive,
+    /// and the `Vec` must not be grown or reallocated, for as long as the
+    /// request may be submitted to the kernel. The hypervisor writes the
+    /// certificate table into them.
+    pub fn with_cert_buffer(
+        mut self,
+        size_pages: usize,
+    ) -> Result<(Self, Vec<Page>), UserApiError> {
+        let len = size_pages
+            .checked_mul(PAGE_SIZE)
+            .and_then(|len| u32::try_from(len).ok())
+            .filter(|len| *len != 0)
+            .ok_or(CertError::BufferOverflow)?;
+
+        let mut pages = vec![Page([0u8; PAGE_SIZE]); size_pages];
+        self.certs_address = pages.as_mut_ptr() as u64;
+        self.certs_len = len;
+
+        Ok((self, pages))
+    }
 }
 
 /// Information provided by the guest owner for requesting an attestation
@@ -124,7 +226,7 @@ impl Default for ReportReq {
     fn default() -> Self {
         Self {
             report_data: [0; 64],
-            vmpl: 1,
+            vmpl: 0,
             _reserved: Default::default(),
         }
     }
@@ -136,7 +238,7 @@ impl ReportReq {
     /// # Arguments
     ///
     /// * `report_data` - (Optional) 64 bytes of unique data to be included in the generated report.
-    /// * `vmpl` - The VMPL level the guest VM is running on.
+    /// * `vmpl` - The VMPL level the guest VM is running on. Defaults to 0.
     pub fn new(report_data: Option<[u8; 64]>, vmpl: Option<u32>) -> Result<Self, UserApiError> {
         let mut request = Self::default();
 
@@ -145,15 +247,74 @@ impl ReportReq {
         }
 
         if let Some(vmpl) = vmpl {
-            if vmpl > MAX_VMPL {
-                return Err(UserApiError::VmplError);
-            } else {
-                request.vmpl = vmpl;
-            }
+            request.set_vmpl(vmpl)?;
         }
 
         Ok(request)
     }
+
+    /// Instantiates a new [ReportReq](self::ReportReq) carrying `data` at the
+    /// start of the report data.
+    ///
+    /// The report data is signed into the attestation report as is, so the
+    /// bytes past `data` are explicitly zero-filled rather than left to
+    /// whatever the caller's buffer held.
+    #[allow(dead_code)]
+    pub fn new_zeroed_with_data(data: &[u8]) -> Result<Self, UserApiError> {
+        let report_data = ReportData::from_nonce(data)
+            .map_err(|_| UserApiError::ReportDataTooLong(data.len()))?;
+
+        Ok(Self {
+            report_data,
+            ..Default::default()
+        })
+    }
+
+    /// Whether the report data past the first `data_len` bytes is all
+    /// zeroes, i.e. nothing beyond the caller's data would be signed into
+    /// the report.
+    #[allow(dead_code)]
+    pub fn validate_report_data_padded(&self, data_len: usize) -> bool {
+        ReportData::is_zero_padded(&self.report_data, data_len)
+    }
+
+    /// Instantiates a new [ReportReq](self::ReportReq) whose report data is the
+    /// SHA-512 digest of `nonce`, so challenges of any length can be bound to
+    /// the report.
+    ///
+    /// Requires either the `openssl` or the `crypto_nossl` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `nonce` - Non-empty data to be hashed into the report data.
+    #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+    pub fn with_nonce(nonce: &[u8]) -> Result<Self, UserApiError> {
+        if nonce.is_empty() {
+            return Err(UserApiError::EmptyNonceError);
+        }
+
+        Ok(Self {
+            report_data: ReportData::from_hash_sha512(nonce),
+            ..Default::default()
+        })
+    }
+
+    /// The VMPL to put into the attestation report.
+    #[allow(dead_code)]
+    pub fn vmpl(&self) -> u32 {
+        self.vmpl
+    }
+
+    /// Sets the VMPL to put into the attestation report.
+    pub fn set_vmpl(&mut self, vmpl: u32) -> Result<(), UserApiError> {
+        if vmpl > MAX_VMPL {
+            return Err(UserApiError::VmplError);
+        }
+
+        self.vmpl = vmpl;
+
+        Ok(())
+    }
 }
 
 /// The response from the PSP containing the generated attestation report.
@@ -181,15 +342,17 @@ pub struct ReportRsp {
     /// Size in bytes of the report.
     pub report_size: u32,
     reserved_0: [u8; 24],
-    /// The attestation report generated by the firmware.
-    pub report: AttestationReport,
-    /// Padding bits to meet the memory page alignment.
-    reserved_1: [u8; 4000
-        - (std::mem::size_of::<AttestationReport>()
-            + (std::mem::size_of::<u32>() * 2)
-            + std::mem::size_of::<[u8; 24]>())],
+    /// The attestation report generated by the firmware, followed by
+    /// padding to meet the memory page alignment. Only the first
+    /// `report_size` bytes are meaningful.
+    report: [u8; REPORT_BUFFER_SIZE],
 }
 
+/// Room left for the report after the response header. Newer firmware may
+/// produce reports larger than [AttestationReport], up to this size.
+const REPORT_BUFFER_SIZE: usize =
+    4000 - ((std::mem::size_of::<u32>() * 2) + std::mem::size_of::<[u8; 24]>());
+
 // Compile-time check that the size is what is expected.
 // Will error out with:
 //
@@ -197,6 +360,7 @@ pub struct ReportRsp {
 //      `0_usize - 1_usize`, which would overflow
 //
 const_assert!(std::mem::size_of::<ReportRsp>() == 4000);
+const_assert!(std::mem::size_of::<AttestationReport>() <= REPORT_BUFFER_SIZE);
 
 impl Default for ReportRsp {
     fn default() -> Self {
@@ -204,17 +368,160 @@ impl Default for ReportRsp {
             status: Default::default(),
             report_size: Default::default(),
             reserved_0: Default::default(),
-            report: Default::default(),
-            reserved_1: [0u8; 4000
-                - (std::mem::size_of::<AttestationReport>()
-                    + (std::mem::size_of::<u32>() * 2)
-                    + std::mem::size_of::<[u8; 24]>())],
+            report: [0u8; REPORT_BUFFER_SIZE],
         }
     }
 }
 
+impl ReportRsp {
+    /// Fails with the firmware's status unless the request succeeded.
+    /// `vmpl` is the VMPL explicitly requested, if any.
+    pub fn check_status(&self, vmpl: Option<u32>) -> Result<(), UserApiError> {
+        FirmwareStatus::from(self.status).into_result(vmpl)
+    }
+
+    /// The raw report, of the size given by the firmware.
+    ///
+    /// Fails if `report_size` exceeds the response buffer.
+    pub fn report_bytes(&self) -> Result<&[u8], UserApiError> {
+        self.report
+            .get(..self.report_size as usize)
+            .ok_or(UserApiError::InvalidReportSize(self.report_size))
+    }
+
+    /// Checks the status, then parses the report, so that a report is never
+    /// read from a failed response.
+    ///
+    /// Reports older than version 2 are rejected. Reports larger than
+    /// [AttestationReport] are accepted; the fields it does not know about
+    /// are ignored.
+    pub fn parse_report(&self) -> Result<AttestationReport, AttestationReportError> {
+        match FirmwareStatus::from(self.status) {
+            FirmwareStatus::Success => (),
+            status => return Err(AttestationReportError::StatusError(status)),
+        }
+
+        let bytes = self.report.get(..self.report_size as usize).ok_or(
+            AttestationReportError::ReportSizeExceedsBuffer {
+                report_size: self.report_size as usize,
+                buffer_size: REPORT_BUFFER_SIZE,
+            },
+        )?;
+
+        let report = AttestationReport::try_from(bytes)?;
+
+        if report.version < 2 {
+            return Err(AttestationReportError::UnsupportedReportVersion(
+                report.version,
+            ));
+        }
+
+        Ok(report)
+    }
+
+    /// The attestation report, which used to be a public field.
+    #[deprecated(note = "use parse_report, which also checks the status")]
+    #[allow(dead_code)]
+    pub fn report(&self) -> Result<AttestationReport, AttestationReportError> {
+        self.parse_report()
+    }
+}
+
 #[cfg(test)]
 mod test {
+    mod snp_derived_key_req {
+        use crate::firmware::{
+            guest::{DerivedKey, GuestFieldSelect},
+            linux::guest::types::DerivedKeyReq,
+        };
+
+        #[test]
+        pub fn test_from_derived_key() {
+            let derived_key = DerivedKey::new(true, GuestFieldSelect(0x41), 1, 2, 3, Some(4));
+            let actual: DerivedKeyReq = derived_key.into();
+
+            assert_eq!(actual.root_key_select, 1);
+            assert_eq!(actual.guest_field_select, 0x41);
+            assert_eq!(actual.vmpl, 1);
+            assert_eq!(actual.guest_svn, 2);
+            assert_eq!(actual.tcb_version, 3);
+            assert_eq!(actual.launch_mit_vector, 4);
+        }
+
+        #[test]
+        pub fn test_from_derived_key_no_mit_vector() {
+            let derived_key = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0, None);
+            let actual: DerivedKeyReq = derived_key.into();
+
+            assert_eq!(actual.root_key_select, 0);
+            assert_eq!(actual.launch_mit_vector, 0);
+        }
+    }
+
+    mod snp_derived_key_rsp {
+        use crate::{
+            error::{DerivedKeyError, UserApiError},
+            firmware::linux::guest::types::DerivedKeyRsp,
+        };
+        use std::convert::TryFrom;
+
+        #[test]
+        pub fn test_try_from_bytes() {
+            let mut bytes = [0u8; 64];
+            bytes[..4].copy_from_slice(&0x16u32.to_ne_bytes());
+            bytes[32..].copy_from_slice(&[0xab; 32]);
+
+            let actual = DerivedKeyRsp::try_from(&bytes[..]).unwrap();
+
+            assert_eq!(actual.status, 0x16);
+            assert_eq!(actual.key, [0xab; 32]);
+        }
+
+        #[test]
+        pub fn test_try_from_bytes_wrong_size() {
+            assert!(matches!(
+                DerivedKeyRsp::try_from(&[0u8; 63][..]),
+                Err(UserApiError::InvalidResponseSize {
+                    expected: 64,
+                    actual: 63
+                })
+            ));
+
+            assert!(matches!(
+                DerivedKeyRsp::try_from(&[0u8; 65][..]),
+                Err(UserApiError::InvalidResponseSize {
+                    expected: 64,
+                    actual: 65
+                })
+            ));
+        }
+
+        #[test]
+        pub fn test_derived_key() {
+            let mut rsp = DerivedKeyRsp {
+                key: [0xab; 32],
+                ..Default::default()
+            };
+
+            assert_eq!(rsp.derived_key(), Some(&[0xab; 32]));
+            assert_eq!(rsp.status_as_error(), Ok(()));
+
+            rsp.status = 0x16;
+            assert_eq!(rsp.derived_key(), None);
+            assert_eq!(
+                rsp.status_as_error(),
+                Err(DerivedKeyError::InvalidParameters)
+            );
+
+            rsp.status = 0x27;
+            assert_eq!(rsp.derived_key(), None);
+            assert_eq!(
+                rsp.status_as_error(),
+                Err(DerivedKeyError::UnknownStatus(0x27))
+            );
+        }
+    }
+
     mod snp_report_req {
         use crate::firmware::linux::guest::types::ReportReq;
         #[test]
@@ -237,7 +544,6 @@ mod test {
         }
 
         #[test]
-        #[should_panic]
         pub fn test_new_error() {
             let report_data: [u8; 64] = [
                 65, 77, 68, 32, 105, 115, 32, 101, 120, 116, 114, 101, 109, 101, 108, 121, 32, 97,
@@ -245,15 +551,351 @@ mod test {
                 101, 32, 98, 101, 115, 116, 32, 67, 80, 85, 115, 33, 32, 65, 77, 68, 32, 82, 111,
                 99, 107, 115, 33, 33, 33, 33, 33, 33,
             ];
-            let expected: ReportReq = ReportReq {
-                report_data,
-                vmpl: 7,
-                _reserved: [0; 28],
+
+            assert!(matches!(
+                ReportReq::new(Some(report_data), Some(7)),
+                Err(crate::error::UserApiError::VmplError)
+            ));
+        }
+
+        #[test]
+        pub fn test_default_vmpl() {
+            assert_eq!(ReportReq::default().vmpl(), 0);
+            assert_eq!(ReportReq::new(None, None).unwrap().vmpl(), 0);
+        }
+
+        #[test]
+        pub fn test_vmpl_range() {
+            for vmpl in 0..=3 {
+                assert_eq!(ReportReq::new(None, Some(vmpl)).unwrap().vmpl(), vmpl);
+            }
+
+            for vmpl in [4, 5, u32::MAX] {
+                assert!(matches!(
+                    ReportReq::new(None, Some(vmpl)),
+                    Err(crate::error::UserApiError::VmplError)
+                ));
+            }
+        }
+
+        #[test]
+        #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+        pub fn test_with_nonce() {
+            let expected = hex::decode(
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            )
+            .unwrap();
+
+            let actual: ReportReq = ReportReq::with_nonce(b"abc").unwrap();
+
+            assert_eq!(actual.report_data[..], expected[..]);
+            assert_eq!(actual.vmpl, ReportReq::default().vmpl);
+        }
+
+        #[test]
+        #[cfg(any(feature = "openssl", feature = "crypto_nossl"))]
+        pub fn test_with_nonce_empty() {
+            assert!(matches!(
+                ReportReq::with_nonce(&[]),
+                Err(crate::error::UserApiError::EmptyNonceError)
+            ));
+        }
+    }
+
+    mod snp_report_req_zeroed {
+        use crate::{error::UserApiError, firmware::linux::guest::types::ReportReq};
+
+        #[test]
+        pub fn test_new_zeroed_with_data() {
+            let request = ReportReq::new_zeroed_with_data(b"nonce").unwrap();
+
+            assert_eq!(&request.report_data[..5], b"nonce");
+            assert_eq!(request.report_data[5..], [0u8; 59]);
+            assert!(request.validate_report_data_padded(5));
+
+            let request = ReportReq::new_zeroed_with_data(&[0xff; 64]).unwrap();
+            assert_eq!(request.report_data, [0xff; 64]);
+            assert!(request.validate_report_data_padded(64));
+            assert!(request.validate_report_data_padded(100));
+        }
+
+        #[test]
+        pub fn test_new_zeroed_with_data_too_long() {
+            assert!(matches!(
+                ReportReq::new_zeroed_with_data(&[0u8; 65]),
+                Err(UserApiError::ReportDataTooLong(65))
+            ));
+        }
+
+        #[test]
+        pub fn test_validate_report_data_padded() {
+            let mut data = [0u8; 64];
+            data[..4].copy_from_slice(b"abcd");
+            data[40] = 1;
+
+            let request = ReportReq::new(Some(data), None).unwrap();
+
+            assert!(!request.validate_report_data_padded(4));
+            assert!(request.validate_report_data_padded(41));
+        }
+    }
+
+    mod snp_ext_report_req {
+        use crate::{
+            error::{CertError, UserApiError},
+            firmware::linux::guest::types::{ExtReportReq, ReportReq, PAGE_SIZE},
+        };
+
+        #[test]
+        pub fn test_with_cert_buffer() {
+            let (request, pages) = ExtReportReq::new(&ReportReq::default())
+                .with_cert_buffer(3)
+                .unwrap();
+
+            assert_eq!(pages.len(), 3);
+            assert_eq!(request.certs_address, pages.as_ptr() as u64);
+            assert_eq!(request.certs_address % PAGE_SIZE as u64, 0);
+            assert_eq!(request.certs_len, 3 * PAGE_SIZE as u32);
+        }
+
+        #[test]
+        pub fn test_with_cert_buffer_invalid_size() {
+            let request = ExtReportReq::new(&ReportReq::default());
+
+            for size_pages in [0, usize::MAX, 1 << 20] {
+                assert!(matches!(
+                    request.with_cert_buffer(size_pages),
+                    Err(UserApiError::ApiError(CertError::BufferOverflow))
+                ));
+            }
+        }
+    }
+
+    mod snp_firmware_status {
+        use crate::{
+            error::{FirmwareStatus, UserApiError},
+            firmware::{
+                guest::{DerivedKey, GuestFieldSelect},
+                linux::guest::types::{DerivedKeyRsp, ReportRsp},
+            },
+        };
+
+        fn report_status(status: u32, vmpl: Option<u32>) -> Result<(), UserApiError> {
+            let rsp = ReportRsp {
+                status,
+                ..Default::default()
             };
+            rsp.check_status(vmpl)
+        }
 
-            let actual: ReportReq = ReportReq::new(Some(report_data), Some(0)).unwrap();
+        #[test]
+        fn test_status_codes() {
+            let codes = [
+                (0x00, FirmwareStatus::Success),
+                (0x07, FirmwareStatus::PolicyFailure),
+                (0x09, FirmwareStatus::InvalidAddress),
+                (0x16, FirmwareStatus::InvalidParam),
+                (0x27, FirmwareStatus::InvalidKey),
+                (0x42, FirmwareStatus::Other(0x42)),
+            ];
 
-            assert_eq!(expected, actual);
+            for (code, status) in codes.iter() {
+                assert_eq!(FirmwareStatus::from(*code), *status);
+                assert_eq!(u32::from(*status), *code);
+            }
+        }
+
+        #[test]
+        fn test_success() {
+            assert!(report_status(0, Some(2)).is_ok());
+        }
+
+        #[test]
+        fn test_report_status_errors() {
+            for (code, expected) in [
+                (0x07, FirmwareStatus::PolicyFailure),
+                (0x09, FirmwareStatus::InvalidAddress),
+                (0x16, FirmwareStatus::InvalidParam),
+                (0x1234, FirmwareStatus::Other(0x1234)),
+            ]
+            .iter()
+            {
+                match report_status(*code, None) {
+                    Err(UserApiError::FirmwareStatus { status, vmpl: None }) => {
+                        assert_eq!(status, *expected)
+                    }
+                    other => panic!("unexpected result for {:#x}: {:?}", code, other),
+                }
+            }
+        }
+
+        #[test]
+        fn test_invalid_param_names_vmpl() {
+            let err = report_status(0x16, Some(0)).unwrap_err();
+            assert!(err.to_string().contains("requested VMPL (0)"));
+
+            let err = report_status(0x16, None).unwrap_err();
+            assert!(!err.to_string().contains("VMPL"));
+
+            let err = report_status(0x42, None).unwrap_err();
+            assert!(err.to_string().contains("0x42"));
+        }
+
+        #[test]
+        fn test_derived_key_status() {
+            let request = DerivedKey::new(false, GuestFieldSelect(1), 1, 0, 0, None);
+            let mut rsp = DerivedKeyRsp::default();
+
+            assert!(rsp.check_status(&request).is_ok());
+
+            rsp.status = 0x27;
+            assert!(matches!(
+                rsp.check_status(&request),
+                Err(UserApiError::FirmwareStatus {
+                    status: FirmwareStatus::InvalidKey,
+                    vmpl: Some(1)
+                })
+            ));
+
+            rsp.status = 0x16;
+            assert!(matches!(
+                rsp.check_status(&request),
+                Err(UserApiError::FirmwareStatus {
+                    status: FirmwareStatus::InvalidParam,
+                    vmpl: Some(1)
+                })
+            ));
+        }
+
+        #[test]
+        fn test_derived_key_status_mit_vector() {
+            let request = DerivedKey::new(false, GuestFieldSelect(1), 1, 0, 0, Some(1));
+            let rsp = DerivedKeyRsp {
+                status: 0x16,
+                ..Default::default()
+            };
+
+            assert!(matches!(
+                rsp.check_status(&request),
+                Err(UserApiError::LaunchMitVectorError)
+            ));
+
+            // Selecting the launch mitigation vector alone is rejected the same way.
+            let mut selection = GuestFieldSelect(1);
+            selection.set_launch_mit_vector(1);
+            let request = DerivedKey::new(false, selection, 1, 0, 0, None);
+            assert!(matches!(
+                rsp.check_status(&request),
+                Err(UserApiError::LaunchMitVectorError)
+            ));
+        }
+    }
+
+    mod snp_report_rsp {
+        use crate::{
+            error::{AttestationReportError, FirmwareStatus, UserApiError},
+            firmware::{guest::AttestationReport, linux::guest::types::ReportRsp},
+        };
+
+        const REPORT_SIZE: usize = std::mem::size_of::<AttestationReport>();
+
+        fn response(report: &[u8], report_size: u32) -> ReportRsp {
+            let mut rsp = ReportRsp::default();
+            rsp.report[..report.len()].copy_from_slice(report);
+            rsp.report_size = report_size;
+            rsp
+        }
+
+        #[test]
+        fn test_report() {
+            let mut bytes = vec![0u8; REPORT_SIZE];
+            bytes[0] = 2;
+            bytes[0x50..0x90].copy_from_slice(&[0xaa; 64]);
+
+            let rsp = response(&bytes, REPORT_SIZE as u32);
+            assert_eq!(rsp.report_bytes().unwrap(), &bytes[..]);
+
+            let report = rsp.parse_report().unwrap();
+            assert_eq!(report.version, 2);
+            assert_eq!(report.report_data, [0xaa; 64]);
+        }
+
+        #[test]
+        fn test_default_report() {
+            let report = AttestationReport::default();
+            let bytes = bincode::serialize(&report).unwrap();
+
+            let rsp = response(&bytes, REPORT_SIZE as u32);
+            assert_eq!(rsp.parse_report().unwrap(), report);
+            assert_eq!(report.version, 2);
+        }
+
+        #[test]
+        fn test_larger_report() {
+            let mut bytes = vec![0u8; 1232];
+            bytes[0] = 5;
+            bytes[0x50..0x90].copy_from_slice(&[0xaa; 64]);
+            bytes[REPORT_SIZE..].copy_from_slice(&[0x55; 1232 - REPORT_SIZE]);
+
+            let rsp = response(&bytes, 1232);
+            assert_eq!(rsp.report_bytes().unwrap().len(), 1232);
+
+            let report = rsp.parse_report().unwrap();
+            assert_eq!(report.version, 5);
+            assert_eq!(report.report_data, [0xaa; 64]);
+        }
+
+        #[test]
+        fn test_invalid_report_size() {
+            let rsp = response(&[], 4000);
+            assert!(matches!(
+                rsp.report_bytes(),
+                Err(UserApiError::InvalidReportSize(4000))
+            ));
+        }
+
+        #[test]
+        fn test_parse_report() {
+            let mut bytes = vec![0u8; REPORT_SIZE];
+            bytes[0] = 2;
+            bytes[0x50..0x90].copy_from_slice(&[0xaa; 64]);
+
+            let mut rsp = response(&bytes, REPORT_SIZE as u32);
+            assert!(rsp.parse_report().is_ok());
+            #[allow(deprecated)]
+            let report = rsp.report().unwrap();
+            assert_eq!(report.report_data, [0xaa; 64]);
+
+            rsp.status = 0x16;
+            assert!(matches!(
+                rsp.parse_report(),
+                Err(AttestationReportError::StatusError(
+                    FirmwareStatus::InvalidParam
+                ))
+            ));
+
+            let rsp = response(&[0u8; 64], 64);
+            assert!(matches!(
+                rsp.parse_report(),
+                Err(AttestationReportError::UnexpectedEndOfData { actual: 64, .. })
+            ));
+
+            let rsp = response(&[], 4000);
+            assert!(matches!(
+                rsp.parse_report(),
+                Err(AttestationReportError::ReportSizeExceedsBuffer {
+                    report_size: 4000,
+                    buffer_size: REPORT_BUFFER_SIZE,
+                })
+            ));
+
+            bytes[0] = 1;
+            let rsp = response(&bytes, REPORT_SIZE as u32);
+            assert!(matches!(
+                rsp.parse_report(),
+                Err(AttestationReportError::UnsupportedReportVersion(1))
+            ));
         }
     }
 }
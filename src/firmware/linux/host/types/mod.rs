@@ -30,17 +30,33 @@ pub struct GetId<'a> {
 #[cfg(any(feature = "sev", feature = "snp"))]
 #[cfg(target_os = "linux")]
 impl<'a> GetId<'a> {
-    pub fn new(id: &'a mut [u8; 64]) -> Self {
+    /// A request for the ID, to be written to `id`. An empty `id` only
+    /// queries the length of the ID.
+    pub fn new(id: &'a mut [u8]) -> Self {
         Self {
-            id_addr: id.as_mut_ptr() as _,
+            id_addr: match id.is_empty() {
+                true => 0,
+                false => id.as_mut_ptr() as _,
+            },
             id_len: id.len() as _,
             _phantom: PhantomData,
         }
     }
 
+    /// The length of the buffer or, after the GET_ID2 ioctl is called, the
+    /// length of the unique CPU ID written back by the kernel. The kernel
+    /// reports it even when the buffer was too small.
+    pub fn id_len(&self) -> usize {
+        self.id_len as _
+    }
+
     /// This method is only meaningful if called *after* the GET_ID2 ioctl is called because the
     /// kernel will write the length of the unique CPU ID to `GetId.id_len`.
     pub fn as_slice(&self) -> &[u8] {
+        if self.id_addr == 0 {
+            return &[];
+        }
+
         unsafe { std::slice::from_raw_parts(self.id_addr as *const u8, self.id_len as _) }
     }
 }
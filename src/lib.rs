@@ -58,6 +58,17 @@
 //! etc.) are used. `openssl` and `crypto_nossl` are mutually exclusive,
 //! and enabling both at the same time leads to a compiler error.
 //!
+//! ## Testing Without Hardware
+//!
+//! Guest requests go through the [GuestFirmware](crate::firmware::guest::GuestFirmware) trait.
+//! Enabling the `mock` feature provides `MockFirmware`, which returns
+//! caller-seeded reports and keys and can be scripted to fail, so that
+//! attestation flows can be tested without `/dev/sev-guest`.
+//!
+//! The `testing` feature provides `firmware::guest::testing`, with builders
+//! of synthetic but structurally valid version 2 and 3 reports, such as
+//! `fake_v3_report_with_measurement`.
+//!
 //! ## Remarks
 //!
 //! Note that the linux kernel provides access to these APIs through a set
@@ -76,6 +87,35 @@
 //!
 //! `cargo cinstall --prefix=/usr --libdir=/usr/lib64`
 //!
+//! With the `ffi` feature, the C API also covers parsing and verifying
+//! SEV-SNP attestation reports; see `include/sev_snp.h`.
+//!
+//! ## Fuzzing
+//!
+//! The `arbitrary` feature implements `arbitrary::Arbitrary` for the SEV-SNP
+//! report types, generating structurally valid reports for fuzzing verifiers.
+//! Fuzz targets for the report parser live in `fuzz/`:
+//!
+//! `cargo fuzz run attestation_report`
+//!
+//! ## Tracing
+//!
+//! The `tracing` feature instruments firmware ioctls and certificate chain
+//! verification with `tracing` spans at the debug level. Report data, nonces
+//! and derived keys are never recorded, at most their lengths.
+//!
+//! ## Async Runtimes
+//!
+//! The `tokio` feature provides `firmware::guest::nonblocking`, which runs
+//! guest requests on tokio's blocking thread pool, optionally with a timeout.
+//!
+//! ## Attestation Tokens
+//!
+//! The `tokens` feature provides `firmware::guest::tokens`, which mints JWT
+//! and COSE_Sign1 tokens of the claims of a verified report, signed with an
+//! ES384 key, for services which should not handle the report and its
+//! certificate chain. It enables `openssl`.
+//!
 //! [firmware]: ./src/firmware/
 //! [launch]: ./src/launch/
 
@@ -93,6 +133,8 @@ compile_error!(
 /// SEV and SEV-SNP certificates interface.
 pub mod certs;
 
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod firmware;
 #[cfg(target_os = "linux")]
 pub mod launch;
@@ -13,6 +13,7 @@ use std::{
 
 use crate::{
     error::IdBlockError,
+    firmware::guest::AttestationReport,
     measurement::idblock_types::{
         FamilyId, IdAuth, IdBlock, IdBlockLaunchDigest, IdMeasurements, ImageId, SevEcdsaPubKey,
         SevEcdsaSig, CURVE_P384_NID,
@@ -105,16 +106,37 @@ pub fn generate_key_digest(key_path: PathBuf) -> Result<IdBlockLaunchDigest, IdB
 
     let pub_key = SevEcdsaPubKey::try_from(&ec_key)?;
 
-    Ok(IdBlockLaunchDigest::new(
-        sha384(
-            bincode::serialize(&pub_key)
-                .map_err(|e| IdBlockError::BincodeError(*e))?
-                .as_slice(),
-        )
-        .try_into()?,
+    Ok(IdBlockLaunchDigest::new(key_digest(&pub_key)?.try_into()?))
+}
+
+/// Calculate the sha384 digest of a public key in the SEV ECDSA encoding,
+/// as found in the ID key and author key digests of an attestation report.
+fn key_digest(pub_key: &SevEcdsaPubKey) -> Result<[u8; 48], IdBlockError> {
+    Ok(sha384(
+        bincode::serialize(pub_key)
+            .map_err(|e| IdBlockError::BincodeError(*e))?
+            .as_slice(),
     ))
 }
 
+/// Check that the ID key digest of an attestation report matches the
+/// provided ID public key.
+pub fn verify_id_key_digest(
+    report: &AttestationReport,
+    id_key: &SevEcdsaPubKey,
+) -> Result<bool, IdBlockError> {
+    Ok(key_digest(id_key)? == report.id_key_digest)
+}
+
+/// Check that the author key digest of an attestation report matches the
+/// provided author public key.
+pub fn verify_author_key_digest(
+    report: &AttestationReport,
+    author_key: &SevEcdsaPubKey,
+) -> Result<bool, IdBlockError> {
+    Ok(key_digest(author_key)? == report.author_key_digest)
+}
+
 /// Calculate the different pieces needed for a complete pre-attestation.
 /// ID-BLOCK, AUTH-BLOCK, id-key digest and auth-key digest.
 pub fn snp_calculate_id(
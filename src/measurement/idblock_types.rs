@@ -9,7 +9,7 @@ use openssl::{
     md::Md,
     md_ctx::MdCtx,
     nid::Nid,
-    pkey::{PKey, Private},
+    pkey::{HasPublic, PKey, Private},
 };
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
@@ -162,12 +162,12 @@ pub struct SevEcdsaPubKey {
     pub data: SevEcdsaKeyData,
 }
 
-// Create SEV ECDSA public key from EC private key
-impl TryFrom<&EcKey<Private>> for SevEcdsaPubKey {
+// Create SEV ECDSA public key from an EC private or public key
+impl<T: HasPublic> TryFrom<&EcKey<T>> for SevEcdsaPubKey {
     type Error = IdBlockError;
 
-    fn try_from(priv_key: &EcKey<Private>) -> Result<Self, Self::Error> {
-        let pub_key = priv_key.public_key();
+    fn try_from(key: &EcKey<T>) -> Result<Self, Self::Error> {
+        let pub_key = key.public_key();
 
         let mut sev_key = SevEcdsaPubKey {
             curve: CURVE_P384,
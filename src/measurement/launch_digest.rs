@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pre-compute an SNP launch digest page by page, for comparison with the
+//! MEASUREMENT of an attestation report.
+
+use crate::{
+    error::*,
+    launch::snp::PageType,
+    measurement::gctx::{Gctx, Updating, LD_SIZE},
+};
+
+/// Builds the launch digest the firmware computes over the pages added
+/// with SNP_LAUNCH_UPDATE.
+///
+/// Each page is folded into the digest through its PAGE_INFO structure:
+/// the current digest, the SHA-384 digest of the page contents (zeroes for
+/// ZERO, SECRETS and CPUID pages), the page type and the GPA. Pages must be
+/// added in the order the VMM launched them.
+///
+/// # Example:
+///
+/// ```ignore
+/// let mut ld = LaunchDigest::new();
+///
+/// ld.normal(ovmf_gpa, &ovmf)?
+///     .zero(sec_mem_gpa, sec_mem_len)?
+///     .secrets(secrets_gpa)?
+///     .cpuid(cpuid_gpa)?
+///     .vmsa(&bsp_vmsa)?;
+///
+/// assert_eq!(ld.digest(), report.measurement.into());
+/// ```
+pub struct LaunchDigest(Gctx<Updating>);
+
+impl Default for LaunchDigest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LaunchDigest {
+    /// Starts from the all-zero digest of a fresh guest context.
+    pub fn new() -> Self {
+        Self(Gctx::default())
+    }
+
+    /// Resumes from a previously computed digest, e.g. a pre-computed
+    /// OVMF hash.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, MeasurementError> {
+        Ok(Self(Gctx::new(seed)?))
+    }
+
+    /// Adds NORMAL pages holding `contents`, starting at `gpa`. The length
+    /// must be a multiple of 4096.
+    pub fn normal(&mut self, gpa: u64, contents: &[u8]) -> Result<&mut Self, GCTXError> {
+        self.0
+            .update_page(PageType::Normal, gpa, Some(contents), None)?;
+        Ok(self)
+    }
+
+    /// Adds `len` bytes of ZERO pages starting at `gpa`. `len` must be a
+    /// multiple of 4096.
+    pub fn zero(&mut self, gpa: u64, len: usize) -> Result<&mut Self, GCTXError> {
+        self.0.update_page(PageType::Zero, gpa, None, Some(len))?;
+        Ok(self)
+    }
+
+    /// Adds a VMSA page, given as the raw 4K page the VMM launched the
+    /// vCPU with. VMSA pages are measured at a fixed GPA.
+    pub fn vmsa(&mut self, vmsa: &[u8]) -> Result<&mut Self, GCTXError> {
+        if vmsa.len() != 4096 {
+            return Err(GCTXError::InvalidBlockSize);
+        }
+
+        self.0.update_page(PageType::Vmsa, 0, Some(vmsa), None)?;
+        Ok(self)
+    }
+
+    /// Adds the SECRETS page at `gpa`.
+    pub fn secrets(&mut self, gpa: u64) -> Result<&mut Self, GCTXError> {
+        self.0.update_page(PageType::Secrets, gpa, None, None)?;
+        Ok(self)
+    }
+
+    /// Adds the CPUID page at `gpa`.
+    pub fn cpuid(&mut self, gpa: u64) -> Result<&mut Self, GCTXError> {
+        self.0.update_page(PageType::Cpuid, gpa, None, None)?;
+        Ok(self)
+    }
+
+    /// The launch digest of the pages added so far.
+    pub fn digest(&self) -> [u8; LD_SIZE] {
+        *self.0.finished().ld()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use openssl::sha::sha384;
+
+    /// The PAGE_INFO structure of the SNP ABI, hashed by hand.
+    fn page_info(ld: &[u8; 48], contents: &[u8; 48], page_type: u8, gpa: u64) -> [u8; 48] {
+        let mut info = vec![];
+        info.extend_from_slice(ld);
+        info.extend_from_slice(contents);
+        info.extend_from_slice(&0x70u16.to_le_bytes());
+        info.extend_from_slice(&[page_type, 0, 0, 0, 0, 0]);
+        info.extend_from_slice(&gpa.to_le_bytes());
+
+        sha384(&info)
+    }
+
+    #[test]
+    fn page_info_layout() {
+        let page = [0xa5u8; 4096];
+
+        let mut ld = LaunchDigest::new();
+        ld.normal(0x1000, &page)
+            .unwrap()
+            .zero(0x2000, 4096)
+            .unwrap()
+            .secrets(0x3000)
+            .unwrap()
+            .cpuid(0x4000)
+            .unwrap()
+            .vmsa(&page)
+            .unwrap();
+
+        let mut expected = [0u8; 48];
+        expected = page_info(&expected, &sha384(&page), 1, 0x1000);
+        expected = page_info(&expected, &[0; 48], 3, 0x2000);
+        expected = page_info(&expected, &[0; 48], 5, 0x3000);
+        expected = page_info(&expected, &[0; 48], 6, 0x4000);
+        expected = page_info(&expected, &sha384(&page), 2, 0xFFFFFFFFF000);
+
+        assert_eq!(ld.digest(), expected);
+    }
+
+    #[test]
+    fn vmsa_must_be_one_page() {
+        let mut ld = LaunchDigest::new();
+
+        assert!(matches!(
+            ld.vmsa(&[0; 8192]),
+            Err(GCTXError::InvalidBlockSize)
+        ));
+        assert!(matches!(
+            ld.normal(0, &[0; 100]),
+            Err(GCTXError::InvalidBlockSize)
+        ));
+    }
+}
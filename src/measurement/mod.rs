@@ -6,6 +6,9 @@
 #[cfg(all(target_os = "linux", feature = "snp", feature = "openssl"))]
 pub mod gctx;
 
+#[cfg(all(target_os = "linux", feature = "snp", feature = "openssl"))]
+pub mod launch_digest;
+
 #[cfg(any(feature = "sev", feature = "snp"))]
 pub mod ovmf;
 
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Milan fixtures of `tests/certs_data`, shared by the unit tests.
+
+/// The Milan fixture report, as signed by the fixture VCEK.
+pub fn milan_report_bytes() -> Vec<u8> {
+    hex::decode(include_bytes!("../../tests/certs_data/report_milan.hex")).unwrap()
+}
+
+/// The Milan fixture report, parsed.
+#[cfg(feature = "snp")]
+pub fn milan_report() -> crate::firmware::guest::AttestationReport {
+    use std::convert::TryFrom;
+
+    crate::firmware::guest::AttestationReport::try_from(milan_report_bytes().as_slice()).unwrap()
+}
+
+/// The builtin Milan ARK and ASK, with the VCEK of the fixture report.
+#[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
+pub fn milan_chain() -> crate::certs::snp::Chain {
+    use crate::certs::snp::{builtin::milan, ca, Certificate, Chain};
+
+    Chain {
+        ca: ca::Chain {
+            ark: milan::ark().unwrap(),
+            ask: milan::ask().unwrap(),
+        },
+        vek: Certificate::from_der(include_bytes!("../../tests/certs_data/vcek_milan.der"))
+            .unwrap(),
+    }
+}
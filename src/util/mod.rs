@@ -3,6 +3,8 @@
 //! Helpful primitives for developing the crate.
 
 pub mod cached_chain;
+#[cfg(test)]
+pub(crate) mod fixtures;
 mod impl_const_id;
 
 use std::{
@@ -185,4 +185,14 @@ mod snp {
         let new_config = Config::new(TcbVersion::new(1, 0, 1, 1), MaskId(31));
         fw.snp_set_config(new_config).unwrap();
     }
+
+    #[cfg_attr(not(has_sev), ignore)]
+    #[test]
+    #[serial]
+    fn vlek_load_invalid() {
+        let mut fw: Firmware = Firmware::open().unwrap();
+
+        // A hashstick not wrapped for this platform must be rejected.
+        assert!(fw.snp_vlek_load(&[1u8; 432]).is_err());
+    }
 }
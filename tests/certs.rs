@@ -24,81 +24,557 @@ mod sev {
 
 #[cfg(all(feature = "snp", any(feature = "openssl", feature = "crypto_nossl")))]
 mod snp {
-    use sev::certs::snp::{builtin::milan, ca, Certificate, Chain, Verifiable};
+    use sev::{
+        certs::snp::{builtin::milan, ca, Certificate, Chain, Verifiable},
+        firmware::guest::AttestationReport,
+    };
+
+    use std::convert::TryFrom;
 
     const TEST_MILAN_VCEK_DER: &[u8] = include_bytes!("certs_data/vcek_milan.der");
 
     const TEST_MILAN_ATTESTATION_REPORT: &[u8] = include_bytes!("certs_data/report_milan.hex");
 
-    #[test]
-    fn milan_chain() {
-        let ark = milan::ark().unwrap();
-        let ask = milan::ask().unwrap();
-        let vcek = Certificate::from_der(TEST_MILAN_VCEK_DER).unwrap();
-
-        let ca = ca::Chain { ark, ask };
+    fn milan_chain_from_fixture() -> Chain {
+        let ca = ca::Chain {
+            ark: milan::ark().unwrap(),
+            ask: milan::ask().unwrap(),
+        };
 
-        let chain = Chain {
+        Chain {
             ca,
-            vek: vcek.clone(),
-        };
+            vek: Certificate::from_der(TEST_MILAN_VCEK_DER).unwrap(),
+        }
+    }
 
-        assert_eq!(chain.verify().ok(), Some(&vcek));
+    fn milan_report_bytes() -> Vec<u8> {
+        hex::decode(TEST_MILAN_ATTESTATION_REPORT).unwrap()
+    }
+
+    fn milan_report_from_fixture() -> AttestationReport {
+        AttestationReport::try_from(milan_report_bytes().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn milan_chain() {
+        let chain = milan_chain_from_fixture();
+
+        assert_eq!(chain.verify().ok(), Some(&chain.vek));
     }
 
     #[test]
     fn milan_chain_invalid() {
-        let ark = milan::ark().unwrap();
-        let ask = milan::ask().unwrap();
         let vcek = {
             let mut buf = TEST_MILAN_VCEK_DER.to_vec();
             buf[40] ^= 0xff;
             Certificate::from_der(&buf).unwrap()
         };
 
-        let ca = ca::Chain { ark, ask };
-
-        let chain = Chain { ca, vek: vcek };
+        let chain = Chain {
+            vek: vcek,
+            ..milan_chain_from_fixture()
+        };
 
         assert_eq!(chain.verify().ok(), None);
     }
 
     #[test]
     fn milan_report() {
-        use sev::firmware::guest::AttestationReport;
+        let chain = milan_chain_from_fixture();
+        let report = milan_report_from_fixture();
 
-        let ark = milan::ark().unwrap();
-        let ask = milan::ask().unwrap();
+        assert_eq!((&chain, &report).verify().ok(), Some(()));
+    }
+
+    #[test]
+    fn milan_report_invalid() {
+        let chain = milan_chain_from_fixture();
+
+        let mut report_bytes = milan_report_bytes();
+        report_bytes[0] ^= 0x80;
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        assert_eq!((&chain, &report).verify().ok(), None);
+    }
+
+    #[test]
+    fn milan_report_unsigned() {
+        use sev::error::ReportSignatureError;
+
+        let chain = milan_chain_from_fixture();
+
+        // Set KEY_INFO.SIGNING_KEY (bits 4:2 at offset 0x48) to None.
+        let mut report_bytes = milan_report_bytes();
+        report_bytes[0x48] = (report_bytes[0x48] & !0x1c) | (7 << 2);
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        let err = (&chain, &report).verify().unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ReportSignatureError>());
+
+        assert_eq!(err, Some(&ReportSignatureError::SignatureMissing));
+    }
+
+    #[test]
+    fn milan_report_vlek_mismatch() {
+        let chain = milan_chain_from_fixture();
+
+        // Set KEY_INFO.SIGNING_KEY (bits 4:2 at offset 0x48) to VLEK.
+        let mut report_bytes = milan_report_bytes();
+        report_bytes[0x48] = (report_bytes[0x48] & !0x1c) | (1 << 2);
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        let err = (&chain, &report).verify().unwrap_err();
+
+        assert!(err.to_string().starts_with("VLEK does not sign"));
+    }
+
+    #[test]
+    fn amd_extensions() {
+        use sev::{
+            certs::snp::{AmdCertExtensions, ProductName},
+            firmware::host::TcbVersion,
+        };
+
+        let report = milan_report_from_fixture();
+        let extensions = milan_chain_from_fixture().vek.amd_extensions().unwrap();
+
+        assert_eq!(extensions.product_name.as_deref(), Some("Milan-B0"));
+        assert_eq!(extensions.product, Some(ProductName::Milan));
+        assert_eq!(extensions.bl_spl, Some(3));
+        assert_eq!(extensions.tee_spl, Some(0));
+        assert_eq!(extensions.snp_spl, Some(8));
+        assert_eq!(extensions.ucode_spl, Some(115));
+        assert_eq!(extensions.tcb(), Some(TcbVersion::new(3, 0, 8, 115)));
+        assert_eq!(extensions.hw_id.as_deref(), Some(report.chip_id.as_ref()));
+        assert_eq!(extensions.csp_id, None);
+
+        let vlek = Certificate::from_pem(include_bytes!("certs_data/vlek_test.pem")).unwrap();
+        let extensions = vlek.amd_extensions().unwrap();
+
+        assert_eq!(extensions.csp_id.as_deref(), Some("test-csp"));
+        assert!(extensions.bl_spl.is_some());
+        assert_eq!(extensions.product, None);
+        assert_eq!(extensions.hw_id, None);
+        assert_eq!(extensions.tcb(), None);
+
+        assert_eq!(
+            milan::ark().unwrap().amd_extensions().unwrap(),
+            AmdCertExtensions::default()
+        );
+    }
+
+    #[test]
+    fn milan_report_signing_key_matches_chain() {
+        use sev::{
+            error::AttestationReportError,
+            firmware::{
+                guest::{AttestationReport, SigningKey},
+                host::CertType,
+            },
+        };
+
+        let vlek_chain = Chain {
+            vek: Certificate::from_pem(include_bytes!("certs_data/vlek_test.pem")).unwrap(),
+            ..milan_chain_from_fixture()
+        };
+
+        let mut report_bytes = milan_report_bytes();
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        assert!(report
+            .signing_key_matches_cert_chain(&milan_chain_from_fixture())
+            .is_ok());
+        assert!(matches!(
+            report.signing_key_matches_cert_chain(&vlek_chain),
+            Err(AttestationReportError::KeyTypeMismatch {
+                report: SigningKey::Vcek,
+                chain: CertType::VLEK,
+            })
+        ));
+
+        // Set KEY_INFO.SIGNING_KEY (bits 4:2 at offset 0x48) to VLEK.
+        report_bytes[0x48] = (report_bytes[0x48] & !0x1c) | (1 << 2);
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        assert!(report.signing_key_matches_cert_chain(&vlek_chain).is_ok());
+        assert!(matches!(
+            report.signing_key_matches_cert_chain(&milan_chain_from_fixture()),
+            Err(AttestationReportError::KeyTypeMismatch {
+                report: SigningKey::Vlek,
+                chain: CertType::VCEK,
+            })
+        ));
+
+        // Unsigned reports match no chain.
+        report_bytes[0x48] |= 0x1c;
+        let report = AttestationReport::try_from(report_bytes.as_slice()).unwrap();
+
+        assert!(matches!(
+            report.signing_key_matches_cert_chain(&milan_chain_from_fixture()),
+            Err(AttestationReportError::KeyTypeMismatch {
+                report: SigningKey::None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn signing_key_unknown_leaf() {
+        use sev::error::AttestationReportError;
+
+        let report = milan_report_from_fixture();
+
+        // The CRL test VCEK carries no AMD extensions.
+        let chain = crl_test_chain(TEST_CRL_ASK);
+
+        assert!(matches!(
+            report.signing_key_matches_cert_chain(&chain),
+            Err(AttestationReportError::UnknownLeafCertificate)
+        ));
+    }
+
+    #[test]
+    fn milan_report_cert_tcb_mismatch() {
+        use sev::{certs::snp::verify_cert_matches_report, error::CertMismatchError};
+
+        let vcek = milan_chain_from_fixture().vek;
+        let mut report = milan_report_from_fixture();
+
+        assert_eq!(verify_cert_matches_report(&vcek, &report), Ok(()));
+
+        report.reported_tcb.snp += 1;
+        assert_eq!(
+            verify_cert_matches_report(&vcek, &report),
+            Err(CertMismatchError::TcbMismatch {
+                field: "snp",
+                cert: 8,
+                report: 9,
+            })
+        );
+        report.reported_tcb.snp -= 1;
+
+        let mut chip_id: [u8; 64] = report.chip_id.into();
+        chip_id[0] ^= 0xff;
+        report.chip_id = chip_id.into();
+        assert_eq!(
+            verify_cert_matches_report(&vcek, &report),
+            Err(CertMismatchError::HwIdMismatch)
+        );
+
+        // A masked chip ID cannot be tied to the certificate hwID.
+        report.chip_id = Default::default();
+        assert_eq!(verify_cert_matches_report(&vcek, &report), Ok(()));
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn milan_report_signed_bytes() {
+        use openssl::{ecdsa::EcdsaSig, sha::sha384};
+        use std::convert::TryFrom;
+
+        let vcek = milan_chain_from_fixture().vek;
+        let report = milan_report_from_fixture();
+
+        let digest = sha384(&report.signed_bytes().unwrap());
+        let sig = EcdsaSig::try_from(&report.signature).unwrap();
+        let key = vcek.public_key().unwrap().ec_key().unwrap();
+
+        assert!(sig.verify(&digest, &key).unwrap());
+    }
+
+    fn chain_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("sev-chain-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("ark.pem"), milan::ark().unwrap().to_pem().unwrap()).unwrap();
+        std::fs::write(dir.join("ask.der"), milan::ask().unwrap().to_der().unwrap()).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn milan_chain_from_dir() {
+        let dir = chain_dir("mixed");
+        std::fs::write(dir.join("vcek.der"), TEST_MILAN_VCEK_DER).unwrap();
+
+        let chain = Chain::from_dir(&dir).unwrap();
         let vcek = Certificate::from_der(TEST_MILAN_VCEK_DER).unwrap();
 
-        let ca = ca::Chain { ark, ask };
+        assert_eq!(chain.verify().ok(), Some(&vcek));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn milan_chain_from_dir_corrupted() {
+        let dir = chain_dir("corrupted");
+        std::fs::write(dir.join("vcek.der"), &TEST_MILAN_VCEK_DER[..100]).unwrap();
+
+        let err = Chain::from_dir(&dir).err().unwrap();
 
-        let chain = Chain { ca, vek: vcek };
+        assert!(err.to_string().contains("vcek.der"));
 
-        let report_bytes = hex::decode(TEST_MILAN_ATTESTATION_REPORT).unwrap();
-        let report: AttestationReport =
-            unsafe { std::ptr::read(report_bytes.as_ptr() as *const _) };
+        std::fs::remove_file(dir.join("vcek.der")).unwrap();
+        let err = Chain::from_dir(&dir).err().unwrap();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
+    #[test]
+    fn certificate_from_bytes() {
+        let ark = milan::ark().unwrap();
+
+        assert_eq!(
+            Certificate::from_bytes(&ark.to_pem().unwrap()).unwrap(),
+            ark
+        );
+        assert_eq!(
+            Certificate::from_bytes(&ark.to_der().unwrap()).unwrap(),
+            ark
+        );
+        assert!(Certificate::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn milan_report_find_in_verified() {
+        use sev::error::ReportSearchError;
+
+        let chain = milan_chain_from_fixture();
+        let mut blob = hex::decode(include_bytes!("certs_data/report_milan_in_blob.hex")).unwrap();
+
+        let (offset, report) = AttestationReport::find_in_verified(&blob, &chain).unwrap();
+        assert_eq!(offset, 0x20);
         assert_eq!((&chain, &report).verify().ok(), Some(()));
+
+        // Tampering with the MEASUREMENT keeps the report parseable, but
+        // breaks its signature.
+        blob[0x20 + 0x90] ^= 0xff;
+        assert!(AttestationReport::find_in(&blob).is_ok());
+        assert!(matches!(
+            AttestationReport::find_in_verified(&blob, &chain),
+            Err(ReportSearchError::InvalidSignature { offset: 0x20, .. })
+        ));
     }
 
     #[test]
-    fn milan_report_invalid() {
-        use sev::firmware::guest::AttestationReport;
+    fn milan_report_cached() {
+        use sev::certs::snp::{verify_report_with_cache, MemoryCertCache};
 
-        let ark = milan::ark().unwrap();
-        let ask = milan::ask().unwrap();
-        let vcek = Certificate::from_der(TEST_MILAN_VCEK_DER).unwrap();
+        let report = milan_report_from_fixture();
 
-        let ca = ca::Chain { ark, ask };
+        let cache = MemoryCertCache::new(4);
+        let mut fetches = 0;
 
-        let chain = Chain { ca, vek: vcek };
+        for _ in 0..2 {
+            verify_report_with_cache(&report, &cache, |_| {
+                fetches += 1;
+                Ok(milan_chain_from_fixture())
+            })
+            .unwrap();
+        }
 
-        let mut report_bytes = hex::decode(TEST_MILAN_ATTESTATION_REPORT).unwrap();
-        report_bytes[0] ^= 0x80;
-        let report: AttestationReport =
-            unsafe { std::ptr::read(report_bytes.as_ptr() as *const _) };
+        assert_eq!(fetches, 1);
+        assert_eq!(cache.len(), 1);
+    }
 
-        assert_eq!((&chain, &report).verify().ok(), None);
+    #[test]
+    fn milan_report_cache_invalid_chain() {
+        use sev::certs::snp::{verify_report_with_cache, MemoryCertCache};
+
+        let report = milan_report_from_fixture();
+
+        let cache = MemoryCertCache::new(4);
+
+        let result = verify_report_with_cache(&report, &cache, |_| {
+            let mut chain = milan_chain_from_fixture();
+            chain.vek = milan::ask().unwrap();
+            Ok(chain)
+        });
+
+        assert!(result.is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn milan_report_cache_untrusted_ark() {
+        use sev::certs::snp::{verify_report_with_cache, MemoryCertCache};
+
+        let report = milan_report_from_fixture();
+
+        let cache = MemoryCertCache::new(4);
+
+        // The chain verifies on its own, but its self-signed ARK is not AMD's.
+        let result = verify_report_with_cache(&report, &cache, |_| {
+            let chain = crl_test_chain(TEST_CRL_ASK);
+            assert!(chain.verify().is_ok());
+            Ok(chain)
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "the chain's ARK is not one of AMD's builtin ARKs"
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cert_cache_lru_eviction() {
+        use sev::{
+            certs::snp::{CertCache, CertCacheKey, MemoryCertCache},
+            firmware::{guest::SigningKey, host::TcbVersion},
+        };
+
+        fn key(id: u8) -> CertCacheKey {
+            CertCacheKey {
+                chip_id: [id; 64].into(),
+                tcb: TcbVersion::new(3, 0, 8, 115),
+                signing_key: SigningKey::Vcek,
+            }
+        }
+
+        let cache = MemoryCertCache::new(2);
+        cache.put(key(1), milan_chain_from_fixture());
+        cache.put(key(2), milan_chain_from_fixture());
+
+        // Touch the first entry so that the second is the least recently used.
+        assert!(cache.get(&key(1)).is_some());
+        cache.put(key(3), milan_chain_from_fixture());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+
+        let vlek = CertCacheKey {
+            signing_key: SigningKey::Vlek,
+            ..key(1)
+        };
+        assert!(cache.get(&vlek).is_none());
+
+        let newer = CertCacheKey {
+            tcb: TcbVersion::new(3, 0, 8, 116),
+            ..key(1)
+        };
+        assert!(cache.get(&newer).is_none());
+    }
+
+    #[test]
+    fn cert_cache_shared() {
+        use sev::certs::snp::{CertCache, CertCacheKey, MemoryCertCache};
+        use std::sync::Arc;
+
+        let report = milan_report_from_fixture();
+        let key = CertCacheKey::from(&report);
+
+        let cache = Arc::new(MemoryCertCache::new(1));
+        cache.put(key, milan_chain_from_fixture());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                std::thread::spawn(move || cache.get(&key).is_some())
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+    }
+
+    const TEST_CRL_ARK: &[u8] = include_bytes!("certs_data/crl/ark.pem");
+    const TEST_CRL_ASK: &[u8] = include_bytes!("certs_data/crl/ask.pem");
+    const TEST_CRL_ASK_REVOKED: &[u8] = include_bytes!("certs_data/crl/ask_revoked.pem");
+    const TEST_CRL_VCEK: &[u8] = include_bytes!("certs_data/crl/vcek.pem");
+
+    // Signed by the test ARK, revoking the ASK with serial 0x201 for key
+    // compromise. One CRL is valid for a century, the other expired a
+    // second after it was issued.
+    const TEST_CRL: &[u8] = include_bytes!("certs_data/crl/crl.der");
+    const TEST_CRL_EXPIRED: &[u8] = include_bytes!("certs_data/crl/crl_expired.pem");
+
+    fn crl_test_chain(ask: &[u8]) -> Chain {
+        Chain::from_pem(TEST_CRL_ARK, ask, TEST_CRL_VCEK).unwrap()
+    }
+
+    #[test]
+    fn crl_revoked_certs() {
+        use sev::certs::snp::{Crl, RevocationReason};
+
+        let crl = Crl::from_bytes(TEST_CRL).unwrap();
+        let revoked = crl.revoked_certs().unwrap();
+
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].serial, vec![0x02, 0x01]);
+        assert_eq!(revoked[0].reason, Some(RevocationReason::KeyCompromise));
+        assert!(revoked[0].revocation_date <= crl.this_update().unwrap());
+        assert!(!crl.is_expired().unwrap());
+    }
+
+    #[test]
+    fn crl_chain_not_revoked() {
+        use sev::certs::snp::Crl;
+
+        let chain = crl_test_chain(TEST_CRL_ASK);
+        let crl = Crl::from_der(TEST_CRL).unwrap();
+
+        assert_eq!(chain.verify_with_crl(&crl).ok(), Some(&chain.vek));
+    }
+
+    #[test]
+    fn crl_chain_revoked() {
+        use sev::{
+            certs::snp::{Crl, RevocationReason},
+            error::CrlError,
+        };
+
+        let chain = crl_test_chain(TEST_CRL_ASK_REVOKED);
+        assert!(chain.verify().is_ok());
+
+        let crl = Crl::from_der(TEST_CRL).unwrap();
+
+        match chain.verify_with_crl(&crl) {
+            Err(CrlError::Revoked(revoked)) => {
+                assert_eq!(revoked.serial, vec![0x02, 0x01]);
+                assert_eq!(revoked.reason, Some(RevocationReason::KeyCompromise));
+            }
+            other => panic!("expected a revoked chain, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn crl_expired() {
+        use sev::{certs::snp::Crl, error::CrlError};
+
+        let crl = Crl::from_bytes(TEST_CRL_EXPIRED).unwrap();
+        assert!(crl.is_expired().unwrap());
+
+        let chain = crl_test_chain(TEST_CRL_ASK);
+        assert!(matches!(
+            chain.verify_with_crl(&crl),
+            Err(CrlError::Expired { next_update }) if Some(next_update) == crl.next_update().unwrap()
+        ));
+
+        // Revocation is reported ahead of expiry.
+        let chain = crl_test_chain(TEST_CRL_ASK_REVOKED);
+        assert!(matches!(
+            chain.verify_with_crl(&crl),
+            Err(CrlError::Revoked(_))
+        ));
+    }
+
+    #[test]
+    fn crl_wrong_issuer() {
+        use sev::{certs::snp::Crl, error::CrlError};
+
+        let crl = Crl::from_der(TEST_CRL).unwrap();
+
+        assert!(matches!(
+            milan_chain_from_fixture().verify_with_crl(&crl),
+            Err(CrlError::Verification(_))
+        ));
     }
 }
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Builds `tests/ffi/report.c` against the static library and checks that
+//! it sees the same report as the Rust API.
+
+#![cfg(feature = "ffi")]
+
+use sev::{ffi::SEV_SNP_OK, firmware::guest::AttestationReport};
+
+use std::{convert::TryFrom, path::PathBuf, process::Command};
+
+/// The libraries `libsev.a` depends on; see `rustc --print native-static-libs`.
+fn native_libs() -> Vec<&'static str> {
+    let mut libs = vec![
+        "-lgcc_s",
+        "-lutil",
+        "-lrt",
+        "-lpthread",
+        "-lm",
+        "-ldl",
+        "-lc",
+    ];
+
+    if cfg!(feature = "openssl") {
+        libs.extend(["-lssl", "-lcrypto"].iter());
+    }
+
+    libs
+}
+
+#[test]
+fn c_report_api() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let data = root.join("tests/certs_data");
+    let builtin = root.join("src/certs/snp/builtin/milan");
+
+    // Integration tests are built in target/<profile>/deps, along with the
+    // library they link against.
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let program = deps.join("ffi-report");
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("tests/ffi/report.c"))
+        .arg(deps.join("libsev.a"))
+        .args(native_libs())
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+
+    let expected_verify = if cfg!(any(feature = "openssl", feature = "crypto_nossl")) {
+        SEV_SNP_OK
+    } else {
+        sev::ffi::SEV_SNP_ERR_UNSUPPORTED
+    };
+
+    let output = Command::new(&program)
+        .arg(data.join("report_milan.hex"))
+        .arg(builtin.join("ark.pem"))
+        .arg(builtin.join("ask.pem"))
+        .arg(data.join("vcek_milan.der"))
+        .arg(expected_verify.to_string())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let bytes = hex::decode(std::fs::read(data.join("report_milan.hex")).unwrap()).unwrap();
+    let report = AttestationReport::try_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        hex::encode(report.measurement)
+    );
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: Apache-2.0
+/*
+ * Exercises the report API of sev_snp.h end-to-end.
+ *
+ * Usage: report REPORT_HEX ARK_PEM ASK_PEM VCEK_DER EXPECTED_VERIFY_CODE
+ *
+ * Prints the parsed MEASUREMENT as hex and exits non-zero on any mismatch.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "sev_snp.h"
+
+static unsigned char *read_file(const char *path, size_t *len)
+{
+	FILE *f = fopen(path, "rb");
+	unsigned char *buf;
+	long size;
+
+	if (!f)
+		return NULL;
+
+	fseek(f, 0, SEEK_END);
+	size = ftell(f);
+	rewind(f);
+
+	buf = malloc(size);
+	if (buf && fread(buf, 1, size, f) != (size_t)size) {
+		free(buf);
+		buf = NULL;
+	}
+
+	fclose(f);
+	*len = size;
+
+	return buf;
+}
+
+static unsigned char *decode_hex(const unsigned char *hex, size_t hex_len, size_t *len)
+{
+	unsigned char *buf = malloc(hex_len / 2);
+	unsigned int byte;
+	size_t i;
+
+	if (!buf)
+		return NULL;
+
+	for (i = 0; i + 1 < hex_len; i += 2) {
+		char pair[3] = { hex[i], hex[i + 1], 0 };
+
+		if (sscanf(pair, "%2x", &byte) != 1) {
+			free(buf);
+			return NULL;
+		}
+
+		buf[i / 2] = byte;
+	}
+
+	*len = hex_len / 2;
+
+	return buf;
+}
+
+static int check(const char *what, int got, int expected)
+{
+	if (got == expected)
+		return 0;
+
+	fprintf(stderr, "%s: got %d (%s), expected %d (%s)\n", what,
+		got, sev_snp_strerror(got), expected, sev_snp_strerror(expected));
+
+	return 1;
+}
+
+int main(int argc, char **argv)
+{
+	unsigned char measurement[SEV_SNP_MEASUREMENT_LEN];
+	unsigned char report_data[SEV_SNP_REPORT_DATA_LEN];
+	unsigned char chip_id[SEV_SNP_CHIP_ID_LEN];
+	unsigned char *hex, *bytes, *ark, *ask, *vcek;
+	size_t hex_len, len, ark_len, ask_len, vcek_len, i;
+	sev_snp_report_t *report = NULL;
+	int failed = 0;
+
+	if (argc != 6) {
+		fprintf(stderr, "usage: %s REPORT_HEX ARK_PEM ASK_PEM VCEK_DER CODE\n", argv[0]);
+		return 2;
+	}
+
+	hex = read_file(argv[1], &hex_len);
+	ark = read_file(argv[2], &ark_len);
+	ask = read_file(argv[3], &ask_len);
+	vcek = read_file(argv[4], &vcek_len);
+
+	if (!hex || !ark || !ask || !vcek) {
+		fprintf(stderr, "failed to read inputs\n");
+		return 2;
+	}
+
+	bytes = decode_hex(hex, hex_len, &len);
+	if (!bytes) {
+		fprintf(stderr, "invalid report hex\n");
+		return 2;
+	}
+
+	failed |= check("parse short", sev_snp_report_parse(bytes, 16, &report),
+			SEV_SNP_ERR_INVALID_REPORT);
+	failed |= check("parse NULL", sev_snp_report_parse(NULL, len, &report),
+			SEV_SNP_ERR_NULL_POINTER);
+
+	if (check("parse", sev_snp_report_parse(bytes, len, &report), SEV_SNP_OK))
+		return 1;
+
+	failed |= check("measurement",
+			sev_snp_report_measurement(report, measurement, sizeof(measurement)),
+			SEV_SNP_OK);
+	failed |= check("report_data",
+			sev_snp_report_report_data(report, report_data, sizeof(report_data)),
+			SEV_SNP_OK);
+	failed |= check("chip_id", sev_snp_report_chip_id(report, chip_id, sizeof(chip_id)),
+			SEV_SNP_OK);
+	failed |= check("chip_id short", sev_snp_report_chip_id(report, chip_id, 8),
+			SEV_SNP_ERR_BUFFER_TOO_SMALL);
+	failed |= check("verify",
+			sev_snp_report_verify(report, ark, ark_len, ask, ask_len, vcek, vcek_len),
+			atoi(argv[5]));
+
+	/* A VCEK that does not parse must not be mistaken for a bad signature. */
+	failed |= check("verify bad vcek",
+			sev_snp_report_verify(report, ark, ark_len, ask, ask_len, vcek, 16),
+			atoi(argv[5]) == SEV_SNP_OK ? SEV_SNP_ERR_INVALID_CERTIFICATE
+						     : SEV_SNP_ERR_UNSUPPORTED);
+
+	for (i = 0; i < sizeof(measurement); i++)
+		printf("%02x", measurement[i]);
+	printf("\n");
+
+	sev_snp_report_free(report);
+	sev_snp_report_free(NULL);
+
+	free(bytes);
+	free(hex);
+	free(ark);
+	free(ask);
+	free(vcek);
+
+	return failed;
+}
@@ -1,37 +1,512 @@
 // SPDX-License-Identifier: Apache-2.0
 
-#[cfg(all(feature = "snp", target_os = "linux"))]
+#[cfg(all(feature = "snp", any(target_os = "linux", feature = "mock")))]
 use sev::firmware::guest::*;
 
+#[cfg(all(feature = "snp", feature = "mock"))]
+use sev::{error::*, firmware::host::CertTableEntry};
+
+/// The guest device, or a mock standing in for it when the device is absent.
+#[cfg(all(
+    feature = "snp",
+    target_os = "linux",
+    any(has_sev_guest, not(feature = "mock"))
+))]
+fn firmware() -> Box<dyn GuestFirmware> {
+    Box::new(Firmware::open().unwrap())
+}
+
+#[cfg(all(
+    feature = "snp",
+    target_os = "linux",
+    not(has_sev_guest),
+    feature = "mock"
+))]
+fn firmware() -> Box<dyn GuestFirmware> {
+    Box::new(MockFirmware::default())
+}
+
 #[cfg(all(feature = "snp", target_os = "linux"))]
-#[cfg_attr(not(has_sev_guest), ignore)]
+#[cfg_attr(not(any(has_sev_guest, feature = "mock")), ignore)]
 #[test]
 fn get_report() {
     let unique_data = [0u8; 64];
 
-    let mut fw = Firmware::open().unwrap();
+    let mut fw = firmware();
 
     fw.get_report(None, Some(unique_data), None).unwrap();
 }
 
 #[cfg(all(feature = "snp", target_os = "linux"))]
-#[cfg_attr(not(has_sev_guest), ignore)]
+#[cfg_attr(not(any(has_sev_guest, feature = "mock")), ignore)]
+#[test]
+fn get_report_raw() {
+    use std::convert::TryFrom;
+
+    let unique_data = [0x42u8; 64];
+
+    let mut fw = firmware();
+
+    let raw = fw.get_report_raw(None, Some(unique_data), None).unwrap();
+    let report = AttestationReport::try_from(raw.as_slice()).unwrap();
+
+    assert!(raw.len() >= std::mem::size_of::<AttestationReport>());
+    assert_eq!(report.report_data, unique_data);
+}
+
+#[cfg(all(feature = "snp", target_os = "linux"))]
+#[cfg_attr(not(any(has_sev_guest, feature = "mock")), ignore)]
 #[test]
 fn get_ext_report() {
     let unique_data = [0u8; 64];
 
-    let mut fw = Firmware::open().unwrap();
+    let mut fw = firmware();
 
     fw.get_ext_report(None, Some(unique_data), None).unwrap();
 }
 
 #[cfg(all(feature = "snp", target_os = "linux"))]
-#[cfg_attr(not(has_sev_guest), ignore)]
+#[cfg_attr(not(any(has_sev_guest, feature = "mock")), ignore)]
 #[test]
 fn get_derived_key() {
-    let derived_key = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0);
+    let derived_key = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0, None);
 
-    let mut fw = Firmware::open().unwrap();
+    let mut fw = firmware();
 
     fw.get_derived_key(None, derived_key).unwrap();
 }
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+fn report_with_retry<F: GuestFirmware>(
+    fw: &mut F,
+    data: [u8; 64],
+) -> Result<AttestationReport, UserApiError> {
+    loop {
+        match fw.get_report(None, Some(data), None) {
+            Err(UserApiError::VmmError(VmmError::RateLimitRetryRequest)) => continue,
+            result => return result,
+        }
+    }
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_get_report_retry() {
+    let mut fw = MockFirmware::default();
+    fw.push_error(VmmError::RateLimitRetryRequest.into());
+    fw.push_error(VmmError::RateLimitRetryRequest.into());
+
+    let report = report_with_retry(&mut fw, [0xaa; 64]).unwrap();
+
+    assert_eq!(report.report_data, [0xaa; 64]);
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_get_report_raw() {
+    use std::convert::TryFrom;
+
+    let mut fw = MockFirmware::default();
+
+    let raw = fw.get_report_raw(None, Some([0xaa; 64]), Some(1)).unwrap();
+
+    assert_eq!(
+        AttestationReport::try_from(raw.as_slice()).unwrap(),
+        fw.get_report(None, Some([0xaa; 64]), Some(1)).unwrap()
+    );
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_scripted_errors() {
+    let key = [0x5a; 32];
+    let mut fw = MockFirmware::new(AttestationReport::default())
+        .with_certificates(vec![])
+        .with_derived_key(key);
+    fw.push_error(Error::InvalidParam.into());
+
+    let derived_key = DerivedKey::new(false, GuestFieldSelect(1), 0, 0, 0, None);
+
+    assert!(matches!(
+        fw.get_derived_key(None, derived_key),
+        Err(UserApiError::FirmwareError(Error::InvalidParam))
+    ));
+    assert_eq!(
+        fw.get_derived_key(None, derived_key)
+            .unwrap()
+            .expose_secret(),
+        &key
+    );
+
+    let (report, certs) = fw.get_ext_report(None, None, Some(2)).unwrap();
+    assert_eq!(report.vmpl, 2);
+    assert_eq!(certs, Some(vec![]));
+
+    assert!(matches!(
+        fw.get_report(None, None, Some(4)),
+        Err(UserApiError::VmplError)
+    ));
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_derived_key_from_config() {
+    let config = r#"
+        # Seal to the VMRK, bound to the launched image and its SVN.
+        root_key_select = true
+        guest_svn = 2
+
+        [guest_field_select]
+        measurement = true
+        guest_svn = true
+    "#;
+
+    let request: DerivedKey = toml::from_str(config).unwrap();
+    assert_eq!(request.get_root_key_select(), 1);
+    assert_eq!(
+        request.guest_field_select.active_fields(),
+        ["MEASUREMENT", "GUEST_SVN"]
+    );
+    assert_eq!(request.guest_svn, 2);
+
+    let mut fw = MockFirmware::default().with_derived_key([0x5a; 32]);
+    let key = fw.get_derived_key(None, request).unwrap();
+
+    assert_eq!(key.expose_secret(), &[0x5a; 32]);
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_attest() {
+    use sev::firmware::guest::attest::*;
+
+    let mut fw = MockFirmware::default();
+
+    let options = AttestOptions {
+        vmpl: Some(1),
+        include_certificates: false,
+        ..AttestOptions::new([0x42; 64])
+    };
+    let bundle = attest(&mut fw, options).unwrap();
+
+    assert_eq!(bundle.nonce, [0x42; 64]);
+    assert_eq!(bundle.report.report_data, bundle.nonce);
+    assert_eq!(bundle.report.vmpl, 1);
+    assert_eq!(
+        bincode::serialize(&bundle.report).unwrap(),
+        bundle.report_bytes
+    );
+    assert!(bundle.certificates.is_empty());
+
+    let options = AttestOptions {
+        report_data: ReportDataSource::RandomNonce,
+        ..AttestOptions::new([0; 64])
+    };
+    let bundle = attest(&mut fw, options).unwrap();
+    assert_eq!(bundle.report.report_data, bundle.nonce);
+    assert_ne!(bundle.nonce, [0; 64]);
+}
+
+/// Firmware returning reports with trailing bytes, as newer firmware with
+/// larger reports does.
+#[cfg(all(feature = "snp", feature = "mock"))]
+struct LargerReports(MockFirmware);
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+impl GuestFirmware for LargerReports {
+    fn get_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<AttestationReport, UserApiError> {
+        self.0.get_report(message_version, data, vmpl)
+    }
+
+    fn get_ext_report(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(AttestationReport, Option<Vec<CertTableEntry>>), UserApiError> {
+        self.0.get_ext_report(message_version, data, vmpl)
+    }
+
+    fn get_ext_report_raw(
+        &mut self,
+        message_version: Option<u8>,
+        data: Option<[u8; 64]>,
+        vmpl: Option<u32>,
+    ) -> Result<(Vec<u8>, Option<Vec<CertTableEntry>>), UserApiError> {
+        let (mut report, certificates) = self.0.get_ext_report_raw(message_version, data, vmpl)?;
+        report.extend_from_slice(&[0xa5; 32]);
+
+        Ok((report, certificates))
+    }
+
+    fn get_derived_key(
+        &mut self,
+        message_version: Option<u8>,
+        derived_key_request: DerivedKey,
+    ) -> Result<SecretBytes<32>, UserApiError> {
+        self.0.get_derived_key(message_version, derived_key_request)
+    }
+}
+
+#[cfg(all(feature = "snp", feature = "mock"))]
+#[test]
+fn mock_attest_ext_report_raw() {
+    use sev::firmware::guest::attest::*;
+
+    let mut fw = LargerReports(MockFirmware::default());
+
+    let bundle = attest(&mut fw, AttestOptions::new([0x42; 64])).unwrap();
+
+    // The firmware's bytes are kept as is, not re-serialized from the
+    // parsed report.
+    assert_eq!(
+        bundle.report_bytes[bundle.report_bytes.len() - 32..],
+        [0xa5; 32]
+    );
+    assert_eq!(
+        bincode::serialize(&bundle.report).unwrap(),
+        bundle.report_bytes[..bundle.report_bytes.len() - 32]
+    );
+}
+
+#[cfg(all(feature = "snp", feature = "mock", feature = "openssl"))]
+mod mock_bundle {
+    use super::*;
+
+    use sev::{
+        certs::snp::builtin::milan,
+        firmware::{
+            guest::attest::*,
+            host::{CertTableEntry, CertType, TcbComponents, TcbVersion},
+        },
+    };
+    use std::convert::TryFrom;
+
+    /// A mock serving the Milan fixture report along with its certificates.
+    /// Requesting the fixture's own REPORT_DATA yields the report exactly as
+    /// signed by its VCEK.
+    fn milan_attest() -> AttestationBundle {
+        let report = milan_report();
+
+        let mut fw = MockFirmware::new(report).with_certificates(vec![
+            CertTableEntry::new(CertType::ARK, milan::ark().unwrap().to_der().unwrap()),
+            CertTableEntry::new(CertType::ASK, milan::ask().unwrap().to_der().unwrap()),
+            CertTableEntry::new(
+                CertType::VCEK,
+                include_bytes!("certs_data/vcek_milan.der").to_vec(),
+            ),
+        ]);
+
+        attest(&mut fw, AttestOptions::new(report.report_data)).unwrap()
+    }
+
+    fn milan_report() -> AttestationReport {
+        let bytes = hex::decode(include_bytes!("certs_data/report_milan.hex")).unwrap();
+        AttestationReport::try_from(bytes.as_slice()).unwrap()
+    }
+
+    /// The nonce the relying party issued for the fixture report.
+    fn nonce() -> [u8; 64] {
+        milan_report().report_data
+    }
+
+    const POLICY: GuestPolicy = GuestPolicy(0x30000);
+
+    fn min_tcb() -> TcbComponents {
+        TcbVersion::new(3, 0, 8, 115).into()
+    }
+
+    #[test]
+    fn verify() {
+        let bundle = milan_attest();
+
+        // The bundle survives transport.
+        let bundle: AttestationBundle =
+            bincode::deserialize(&bincode::serialize(&bundle).unwrap()).unwrap();
+
+        verify_bundle(&bundle, &nonce(), &POLICY, &min_tcb()).unwrap();
+    }
+
+    #[test]
+    fn tampered_report() {
+        let mut bundle = milan_attest();
+        bundle.report_bytes[0x90] ^= 1;
+        bundle.report = AttestationReport::try_from(bundle.report_bytes.as_slice()).unwrap();
+
+        assert!(matches!(
+            verify_bundle(&bundle, &nonce(), &POLICY, &min_tcb()),
+            Err(BundleError::Signature(_))
+        ));
+
+        let mut bundle = milan_attest();
+        bundle.report_bytes[0x90] ^= 1;
+
+        assert!(matches!(
+            verify_bundle(&bundle, &nonce(), &POLICY, &min_tcb()),
+            Err(BundleError::ReportMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejected_claims() {
+        let bundle = milan_attest();
+
+        let mut other = bundle.clone();
+        other.nonce[0] ^= 1;
+        assert!(matches!(
+            verify_bundle(&other, &nonce(), &POLICY, &min_tcb()),
+            Err(BundleError::NonceMismatch)
+        ));
+
+        // A replayed report, for a nonce issued earlier.
+        let mut stale = nonce();
+        stale[0] ^= 1;
+        assert!(matches!(
+            verify_bundle(&bundle, &stale, &POLICY, &min_tcb()),
+            Err(BundleError::UnexpectedNonce)
+        ));
+
+        assert!(matches!(
+            verify_bundle(&bundle, &nonce(), &GuestPolicy(0x20000), &min_tcb()),
+            Err(BundleError::Policy(PolicyViolation::SmtNotAllowed))
+        ));
+
+        assert!(matches!(
+            verify_bundle(
+                &bundle,
+                &nonce(),
+                &POLICY,
+                &TcbVersion::new(3, 0, 9, 115).into()
+            ),
+            Err(BundleError::TcbTooLow { .. })
+        ));
+
+        // A Turin minimum, with an FMC SVN, does not admit a Milan report.
+        let turin_min = TcbComponents {
+            fmc: Some(0),
+            ..min_tcb()
+        };
+        assert!(matches!(
+            verify_bundle(&bundle, &nonce(), &POLICY, &turin_min),
+            Err(BundleError::TcbTooLow { .. })
+        ));
+
+        let mut other = bundle;
+        other.certificates.clear();
+        assert!(matches!(
+            verify_bundle(&other, &nonce(), &POLICY, &min_tcb()),
+            Err(BundleError::MissingCertificates)
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    mod spans {
+        use super::*;
+
+        use std::{
+            fmt::Debug,
+            sync::{Arc, Mutex},
+        };
+        use tracing::{
+            field::{Field, Visit},
+            span, Dispatch, Event, Metadata, Subscriber,
+        };
+
+        /// A span as recorded: its path from the root span, and its fields.
+        type Recorded = (String, String);
+
+        struct Fields(String);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0 += &format!(" {}={:?}", field.name(), value);
+            }
+        }
+
+        /// Records every span along with the span it was entered under.
+        #[derive(Default)]
+        struct Recorder {
+            spans: Mutex<Vec<Recorded>>,
+            stack: Mutex<Vec<u64>>,
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+                let mut fields = Fields(String::new());
+                attrs.record(&mut fields);
+
+                let mut spans = self.spans.lock().unwrap();
+                let path = match self.stack.lock().unwrap().last() {
+                    Some(parent) => format!(
+                        "{}/{}",
+                        spans[*parent as usize - 1].0,
+                        attrs.metadata().name()
+                    ),
+                    None => attrs.metadata().name().to_string(),
+                };
+
+                spans.push((path, fields.0));
+                span::Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+
+                assert!(!fields.0.contains("report_data"));
+            }
+
+            fn enter(&self, span: &span::Id) {
+                self.stack.lock().unwrap().push(span.into_u64());
+            }
+
+            fn exit(&self, _: &span::Id) {
+                self.stack.lock().unwrap().pop();
+            }
+        }
+
+        #[test]
+        fn attest_and_verify() {
+            let recorder = Arc::new(Recorder::default());
+
+            tracing::dispatcher::with_default(&Dispatch::new(recorder.clone()), || {
+                verify_bundle(&milan_attest(), &nonce(), &POLICY, &min_tcb()).unwrap();
+            });
+
+            let spans = recorder.spans.lock().unwrap();
+            let paths: Vec<&str> = spans.iter().map(|(path, _)| path.as_str()).collect();
+
+            assert_eq!(
+                paths,
+                [
+                    "attest",
+                    "verify_bundle",
+                    "verify_bundle/verify_report",
+                    "verify_bundle/verify_report/verify_chain",
+                    "verify_bundle/verify_report/verify_chain/verify_ca_chain",
+                    "verify_bundle/verify_report/verify_chain/verify_ca_chain/verify_certificate",
+                    "verify_bundle/verify_report/verify_chain/verify_ca_chain/verify_certificate",
+                    "verify_bundle/verify_report/verify_chain/verify_certificate",
+                ]
+            );
+
+            // The mock issues no ioctl, so none is traced under "attest".
+            assert_eq!(spans[5].1, " signer=\"ARK\" signee=\"ARK\"");
+            assert_eq!(spans[6].1, " signer=\"ARK\" signee=\"ASK\"");
+            assert_eq!(spans[7].1, " signer=\"ASK\" signee=\"VEK\"");
+        }
+    }
+}
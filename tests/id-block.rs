@@ -13,7 +13,7 @@ use base64::{engine::general_purpose, Engine as _};
 use hex::{self, FromHex};
 
 use sev::measurement::{
-    idblock::{load_priv_key, snp_calculate_id},
+    idblock::{load_priv_key, snp_calculate_id, verify_author_key_digest, verify_id_key_digest},
     idblock_types::{IdAuth, IdBlockLaunchDigest, SevEcdsaPubKey, SevEcdsaSig},
 };
 
@@ -166,3 +166,37 @@ fn test_auth_block_generation() {
     // Comparing auth_blocks
     assert_eq!(id_auth_str, expected_auth_block);
 }
+
+// Testing that the key digests of a report are checked against the ID and author keys.
+#[test]
+fn test_verify_key_digests() {
+    use sev::firmware::guest::AttestationReport;
+
+    let id_key = load_priv_key("./tests/measurement/test_id_key.pem".into()).unwrap();
+    let auth_key = load_priv_key("./tests/measurement/test_auth_key.pem".into()).unwrap();
+
+    // The public halves of the keys give the same digests as the private ones.
+    let id_pub_key =
+        openssl::ec::EcKey::from_public_key(id_key.group(), id_key.public_key()).unwrap();
+    let id_pub_key = SevEcdsaPubKey::try_from(&id_pub_key).unwrap();
+    let auth_pub_key = SevEcdsaPubKey::try_from(&auth_key).unwrap();
+
+    let mut report = AttestationReport::default();
+    report.id_key_digest = general_purpose::STANDARD
+        .decode("WfssaG36T+9J84M5qndckAUcKNVzg93DuvO160sUlt87DbvcuuX+J2d6ZEggdz/i")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    report.author_key_digest = general_purpose::STANDARD
+        .decode("7tgRafsMteDsNHlPEo/U4MSkb2fGWhrfqB/LJLzAiMJyNpt//D6ytq9qQoShs2xf")
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    assert!(verify_id_key_digest(&report, &id_pub_key).unwrap());
+    assert!(verify_author_key_digest(&report, &auth_pub_key).unwrap());
+
+    // Swapped keys must not match.
+    assert!(!verify_id_key_digest(&report, &auth_pub_key).unwrap());
+    assert!(!verify_author_key_digest(&report, &id_pub_key).unwrap());
+}
@@ -10,6 +10,24 @@ mod snp_tests {
         vmsa::{GuestFeatures, VMMType},
     };
 
+    // Known answer from sev-snp-measure for the OVMF pages alone, rebuilt
+    // page by page from the raw firmware image.
+    #[test]
+    fn test_launch_digest_ovmf_pages() {
+        use sev::measurement::launch_digest::LaunchDigest;
+
+        let ovmf = std::fs::read("./tests/measurement/ovmf_AmdSev_suffix.bin").unwrap();
+        let gpa = 0x1_0000_0000 - ovmf.len() as u64;
+
+        let mut ld = LaunchDigest::new();
+        ld.normal(gpa, &ovmf).unwrap();
+
+        assert_eq!(
+            hex::encode(ld.digest()),
+            "edcf6d1c57ce868a167c990f58c8667c698269ef9e0803246419eea914186343054d557e1f17acd93b032c106bc70d25"
+        );
+    }
+
     // Test if we can compute a full LD from a pre generated hash using snp only kernel
     #[test]
     fn test_snp_ovmf_hash_gen_snp_only() {